@@ -0,0 +1,559 @@
+use crate::core::Entry;
+use crate::storage::SearchFilters;
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+pub const DEFAULT_SOCKET_PATH: &str = "~/.rustory/agent.sock";
+
+// search 응답은 limit만큼의 엔트리를 담으므로 넉넉히 둔다. 길이 필드는 u32 big-endian.
+const MAX_FRAME_BYTES: usize = 64 * 1024 * 1024;
+
+/// 접속한 클라이언트가 요청을 보내지 않고 붙잡고 있는 시간의 상한. agent는 요청을 하나씩 처리한다.
+const SERVER_READ_TIMEOUT: Duration = Duration::from_secs(2);
+const SERVER_WRITE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 요청은 보낸 쪽의 `db_path`를 함께 싣는다. agent가 다른 DB를 열고 있으면 거절하고,
+/// 클라이언트는 직접 SQLite에 쓰는 경로로 돌아간다.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum Request {
+    /// 설정을 읽지 않는 hook 경로는 `db_path`를 비워 보낸다. 이때는 agent가 설정상 기본 DB를 열고 있을 때만
+    /// 받고, 비어 있는 `user_id`/`device_id`는 agent가 설정으로 채운다.
+    Record {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        db_path: Option<String>,
+        entry: Entry,
+    },
+    Search {
+        db_path: String,
+        #[serde(default)]
+        query: Option<String>,
+        #[serde(default)]
+        filters: SearchFilters,
+        limit: usize,
+    },
+    Status,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum Response {
+    /// `recorded=false`면 ignore 규칙 등으로 기록하지 않은 것이다.
+    Recorded {
+        recorded: bool,
+    },
+    Entries {
+        entries: Vec<Entry>,
+    },
+    Status(AgentStatus),
+    Error {
+        error: String,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentStatus {
+    pub pid: u32,
+    pub version: String,
+    pub db_path: String,
+    pub socket_path: String,
+    pub started_at_unix: i64,
+    pub records: u64,
+    pub searches: u64,
+}
+
+/// agent가 요청을 위임하는 대상. `rr agent`는 열어 둔 `LocalStore`와 미리 컴파일한 기록 규칙으로 구현한다.
+pub trait Handler {
+    fn record(&mut self, entry: Entry) -> Result<bool>;
+    fn search(
+        &mut self,
+        query: Option<&str>,
+        filters: &SearchFilters,
+        limit: usize,
+    ) -> Result<Vec<Entry>>;
+}
+
+pub fn write_frame<W: Write, T: Serialize>(w: &mut W, value: &T) -> Result<()> {
+    let body = serde_json::to_vec(value).context("encode agent frame")?;
+    if body.len() > MAX_FRAME_BYTES {
+        bail!("agent frame too large: {} bytes", body.len());
+    }
+    w.write_all(&(body.len() as u32).to_be_bytes())
+        .context("write agent frame length")?;
+    w.write_all(&body).context("write agent frame")?;
+    w.flush().context("flush agent frame")
+}
+
+pub fn read_frame<R: Read, T: for<'de> Deserialize<'de>>(r: &mut R) -> Result<T> {
+    let mut len = [0u8; 4];
+    r.read_exact(&mut len).context("read agent frame length")?;
+    let len = u32::from_be_bytes(len) as usize;
+    if len > MAX_FRAME_BYTES {
+        bail!("agent frame too large: {len} bytes");
+    }
+    let mut body = vec![0u8; len];
+    r.read_exact(&mut body).context("read agent frame")?;
+    serde_json::from_slice(&body).context("parse agent frame")
+}
+
+/// agent에 요청 1개를 보낸다. 소켓이 없거나 아무도 listen하지 않으면 `Ok(None)`(= agent 없음).
+#[cfg(unix)]
+pub fn request(socket_path: &Path, req: &Request, timeout: Duration) -> Result<Option<Response>> {
+    use std::os::unix::net::UnixStream;
+
+    let mut stream = match UnixStream::connect(socket_path) {
+        Ok(stream) => stream,
+        Err(err)
+            if matches!(
+                err.kind(),
+                std::io::ErrorKind::NotFound | std::io::ErrorKind::ConnectionRefused
+            ) =>
+        {
+            return Ok(None);
+        }
+        Err(err) => {
+            return Err(err).with_context(|| format!("connect agent: {}", socket_path.display()));
+        }
+    };
+    stream.set_read_timeout(Some(timeout)).ok();
+    stream.set_write_timeout(Some(timeout)).ok();
+
+    write_frame(&mut stream, req)?;
+    read_frame(&mut stream).map(Some)
+}
+
+#[cfg(not(unix))]
+pub fn request(
+    _socket_path: &Path,
+    _req: &Request,
+    _timeout: Duration,
+) -> Result<Option<Response>> {
+    Ok(None)
+}
+
+/// `stop`이 켜진 뒤 소켓에 한 번 접속하면(`wake`) accept 대기에서 빠져나온다.
+/// `serves_default_db`가 false면 `db_path` 없이 온 기록 요청을 거절한다.
+#[cfg(unix)]
+pub fn serve(
+    socket_path: &Path,
+    db_path: &Path,
+    serves_default_db: bool,
+    handler: &mut dyn Handler,
+    stop: &AtomicBool,
+) -> Result<()> {
+    let listener = bind_socket(socket_path)?;
+    let served_db = canonical_db_path(db_path);
+    let mut status = AgentStatus {
+        pid: std::process::id(),
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        db_path: db_path.display().to_string(),
+        socket_path: socket_path.display().to_string(),
+        started_at_unix: time::OffsetDateTime::now_utc().unix_timestamp(),
+        records: 0,
+        searches: 0,
+    };
+
+    for stream in listener.incoming() {
+        if stop.load(Ordering::SeqCst) {
+            break;
+        }
+        let mut stream = match stream {
+            Ok(stream) => stream,
+            Err(err) => {
+                eprintln!("warn: agent accept failed: {err}");
+                continue;
+            }
+        };
+        stream.set_read_timeout(Some(SERVER_READ_TIMEOUT)).ok();
+        stream.set_write_timeout(Some(SERVER_WRITE_TIMEOUT)).ok();
+
+        let req: Request = match read_frame(&mut stream) {
+            Ok(req) => req,
+            Err(err) => {
+                // 연결만 열고 닫는 접속(wake, 상태 확인 스크립트 등)은 조용히 넘긴다.
+                if !is_eof(&err) {
+                    eprintln!("warn: agent bad request: {err:#}");
+                }
+                continue;
+            }
+        };
+        let resp = handle_request(handler, &served_db, serves_default_db, &mut status, req);
+        if let Err(err) = write_frame(&mut stream, &resp) {
+            eprintln!("warn: agent response failed: {err:#}");
+        }
+    }
+
+    let _ = std::fs::remove_file(socket_path);
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn serve(
+    _socket_path: &Path,
+    _db_path: &Path,
+    _serves_default_db: bool,
+    _handler: &mut dyn Handler,
+    _stop: &AtomicBool,
+) -> Result<()> {
+    bail!("rr agent requires unix domain sockets")
+}
+
+/// 종료 신호를 받은 뒤 accept 대기를 깨운다.
+#[cfg(unix)]
+pub fn wake(socket_path: &Path) {
+    let _ = std::os::unix::net::UnixStream::connect(socket_path);
+}
+
+#[cfg(not(unix))]
+pub fn wake(_socket_path: &Path) {}
+
+fn handle_request(
+    handler: &mut dyn Handler,
+    db_path: &Path,
+    serves_default_db: bool,
+    status: &mut AgentStatus,
+    req: Request,
+) -> Response {
+    let result = match req {
+        Request::Status => return Response::Status(status.clone()),
+        Request::Record {
+            db_path: req_db_path,
+            entry,
+        } => match req_db_path {
+            Some(req_db_path) => check_db_path(db_path, &req_db_path),
+            None if serves_default_db => Ok(()),
+            None => Err(anyhow::anyhow!(
+                "agent serves {} (not the configured default db)",
+                db_path.display()
+            )),
+        }
+        .and_then(|_| {
+            let recorded = handler.record(entry)?;
+            status.records += 1;
+            Ok(Response::Recorded { recorded })
+        }),
+        Request::Search {
+            db_path: req_db_path,
+            query,
+            filters,
+            limit,
+        } => check_db_path(db_path, &req_db_path).and_then(|_| {
+            let entries = handler.search(query.as_deref(), &filters, limit)?;
+            status.searches += 1;
+            Ok(Response::Entries { entries })
+        }),
+    };
+    result.unwrap_or_else(|err| Response::Error {
+        error: format!("{err:#}"),
+    })
+}
+
+/// `served`는 `canonical_db_path`를 거친 경로다. 심볼릭 링크나 `..`이 섞인 같은 DB도 같다고 본다.
+fn check_db_path(served: &Path, requested: &str) -> Result<()> {
+    let requested = canonical_db_path(&crate::config::expand_home_path(requested)?);
+    if requested != served {
+        bail!(
+            "agent serves {} (requested {})",
+            served.display(),
+            requested.display()
+        );
+    }
+    Ok(())
+}
+
+/// DB 파일이 아직 없으면(첫 기록 전) 입력 경로를 그대로 쓴다.
+pub fn canonical_db_path(path: &Path) -> PathBuf {
+    std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+fn is_eof(err: &anyhow::Error) -> bool {
+    err.downcast_ref::<std::io::Error>()
+        .is_some_and(|e| e.kind() == std::io::ErrorKind::UnexpectedEof)
+}
+
+/// 남아 있는 소켓 파일은 접속해 보고, 살아 있는 agent가 없을 때만 지운다.
+#[cfg(unix)]
+fn bind_socket(socket_path: &Path) -> Result<std::os::unix::net::UnixListener> {
+    use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+    use std::os::unix::net::{UnixListener, UnixStream};
+
+    if socket_path.exists() {
+        if UnixStream::connect(socket_path).is_ok() {
+            bail!("agent already running: {}", socket_path.display());
+        }
+        std::fs::remove_file(socket_path)
+            .with_context(|| format!("remove stale agent socket: {}", socket_path.display()))?;
+    }
+    if let Some(parent) = socket_path.parent()
+        && !parent.as_os_str().is_empty()
+    {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("create agent socket dir: {}", parent.display()))?;
+    }
+
+    // 기록/검색 API라 같은 사용자만 접속할 수 있게 한다. 권한을 바꾸기 전에 다른 사용자가 접속하지 못하도록
+    // 0700 임시 디렉터리 안에서 bind하고 0600으로 바꾼 뒤 제자리로 옮긴다.
+    let file_name = socket_path
+        .file_name()
+        .with_context(|| format!("invalid agent socket path: {}", socket_path.display()))?;
+    let staging = socket_path.with_file_name(format!(
+        ".{}.{}.tmp",
+        file_name.to_string_lossy(),
+        std::process::id()
+    ));
+    let _ = std::fs::remove_dir_all(&staging);
+    std::fs::DirBuilder::new()
+        .mode(0o700)
+        .create(&staging)
+        .with_context(|| format!("create agent socket dir: {}", staging.display()))?;
+    let staged = staging.join(file_name);
+    let bound = UnixListener::bind(&staged)
+        .with_context(|| format!("bind agent socket: {}", socket_path.display()))
+        .and_then(|listener| {
+            std::fs::set_permissions(&staged, std::fs::Permissions::from_mode(0o600))
+                .context("chmod agent socket")?;
+            std::fs::rename(&staged, socket_path)
+                .with_context(|| format!("move agent socket: {}", socket_path.display()))?;
+            Ok(listener)
+        });
+    let _ = std::fs::remove_dir_all(&staging);
+    bound
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::storage::LocalStore;
+    use std::sync::Arc;
+    use tempfile::tempdir;
+    use time::OffsetDateTime;
+
+    struct StoreHandler {
+        store: LocalStore,
+    }
+
+    impl Handler for StoreHandler {
+        fn record(&mut self, entry: Entry) -> Result<bool> {
+            if entry.cmd.starts_with(' ') {
+                return Ok(false);
+            }
+            if entry.cmd == "fail" {
+                bail!("record failed");
+            }
+            self.store.insert_entries(std::slice::from_ref(&entry))?;
+            Ok(true)
+        }
+
+        fn search(
+            &mut self,
+            query: Option<&str>,
+            filters: &SearchFilters,
+            limit: usize,
+        ) -> Result<Vec<Entry>> {
+            self.store.search(query.unwrap_or(""), filters, limit)
+        }
+    }
+
+    fn entry(entry_id: &str, cmd: &str) -> Entry {
+        Entry {
+            entry_id: entry_id.to_string(),
+            device_id: "dev1".to_string(),
+            user_id: "user1".to_string(),
+            ts: OffsetDateTime::from_unix_timestamp(1).unwrap(),
+            cmd: cmd.to_string(),
+            cwd: "/tmp".to_string(),
+            exit_code: 0,
+            duration_ms: 1,
+            shell: "zsh".to_string(),
+            hostname: "host".to_string(),
+            version: "0.1.0".to_string(),
+            session_id: None,
+            origin_seq: None,
+            origin_id: None,
+        }
+    }
+
+    #[test]
+    fn agent_records_searches_and_reports_status_over_socket() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("history.db");
+        let socket = dir.path().join("run/agent.sock");
+        let db = db_path.to_str().unwrap().to_string();
+
+        // agent가 없으면 클라이언트는 None을 받고 직접 쓰기로 돌아간다.
+        assert!(
+            request(&socket, &Request::Status, Duration::from_secs(1))
+                .unwrap()
+                .is_none()
+        );
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let join = {
+            let (socket, db_path, stop) = (socket.clone(), db_path.clone(), stop.clone());
+            std::thread::spawn(move || {
+                let mut handler = StoreHandler {
+                    store: LocalStore::open(db_path.to_str().unwrap()).unwrap(),
+                };
+                serve(&socket, &db_path, false, &mut handler, &stop).unwrap();
+            })
+        };
+        for _ in 0..100 {
+            if socket.exists() {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        let call = |req: Request| {
+            request(&socket, &req, Duration::from_secs(5))
+                .unwrap()
+                .expect("agent running")
+        };
+
+        let resp = call(Request::Record {
+            db_path: Some(db.clone()),
+            entry: entry("id-1", "git status"),
+        });
+        assert!(
+            matches!(resp, Response::Recorded { recorded: true }),
+            "{resp:?}"
+        );
+        let resp = call(Request::Record {
+            db_path: Some(db.clone()),
+            entry: entry("id-2", " secret"),
+        });
+        assert!(
+            matches!(resp, Response::Recorded { recorded: false }),
+            "{resp:?}"
+        );
+
+        let resp = call(Request::Search {
+            db_path: db.clone(),
+            query: Some("git".to_string()),
+            filters: SearchFilters::default(),
+            limit: 10,
+        });
+        let Response::Entries { entries } = resp else {
+            panic!("expected entries: {resp:?}");
+        };
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].entry_id, "id-1");
+
+        let resp = call(Request::Record {
+            db_path: Some(dir.path().join("other.db").display().to_string()),
+            entry: entry("id-3", "ls"),
+        });
+        let Response::Error { error } = resp else {
+            panic!("expected db_path mismatch: {resp:?}");
+        };
+        assert!(error.contains("agent serves"), "{error}");
+
+        // 같은 DB를 다른 표기로 가리켜도 받아들인다.
+        std::fs::create_dir_all(dir.path().join("sub")).unwrap();
+        let resp = call(Request::Record {
+            db_path: Some(dir.path().join("sub/../history.db").display().to_string()),
+            entry: entry("id-4", "ls"),
+        });
+        assert!(
+            matches!(resp, Response::Recorded { recorded: true }),
+            "{resp:?}"
+        );
+
+        // 설정 기본 DB를 열고 있지 않은 agent는 db_path 없는 기록을 거절한다.
+        let resp = call(Request::Record {
+            db_path: None,
+            entry: entry("id-6", "ls"),
+        });
+        let Response::Error { error } = resp else {
+            panic!("expected default db rejection: {resp:?}");
+        };
+        assert!(error.contains("configured default db"), "{error}");
+
+        // 실패한 기록은 세지 않는다.
+        let resp = call(Request::Record {
+            db_path: Some(db.clone()),
+            entry: entry("id-5", "fail"),
+        });
+        assert!(matches!(resp, Response::Error { .. }), "{resp:?}");
+
+        let Response::Status(status) = call(Request::Status) else {
+            panic!("expected status");
+        };
+        assert_eq!(status.records, 3);
+        assert_eq!(status.searches, 1);
+        assert_eq!(status.pid, std::process::id());
+
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(&socket).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        let leftovers: Vec<_> = std::fs::read_dir(socket.parent().unwrap())
+            .unwrap()
+            .map(|e| e.unwrap().file_name())
+            .collect();
+        assert_eq!(leftovers, vec![std::ffi::OsString::from("agent.sock")]);
+
+        // 같은 소켓으로 두 번째 agent는 뜨지 않는다.
+        let mut other = StoreHandler {
+            store: LocalStore::open(":memory:").unwrap(),
+        };
+        let err = serve(
+            &socket,
+            &db_path,
+            false,
+            &mut other,
+            &AtomicBool::new(false),
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("already running"), "{err:#}");
+
+        stop.store(true, Ordering::SeqCst);
+        wake(&socket);
+        join.join().unwrap();
+        assert!(!socket.exists());
+    }
+
+    #[test]
+    fn record_without_db_path_goes_to_agent_serving_default_db() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("history.db");
+        let mut handler = StoreHandler {
+            store: LocalStore::open(db_path.to_str().unwrap()).unwrap(),
+        };
+        let mut status = AgentStatus {
+            pid: 1,
+            version: "0.1.0".to_string(),
+            db_path: db_path.display().to_string(),
+            socket_path: "agent.sock".to_string(),
+            started_at_unix: 0,
+            records: 0,
+            searches: 0,
+        };
+        let req = Request::Record {
+            db_path: None,
+            entry: entry("id-1", "ls"),
+        };
+
+        // 직렬화할 때 빈 db_path는 빠진다(설정을 읽지 않는 hook 경로의 요청).
+        let json = serde_json::to_value(&req).unwrap();
+        assert!(json.get("db_path").is_none(), "{json}");
+
+        let resp = handle_request(&mut handler, &db_path, true, &mut status, req);
+        assert!(
+            matches!(resp, Response::Recorded { recorded: true }),
+            "{resp:?}"
+        );
+        assert_eq!(status.records, 1);
+        assert_eq!(handler.store.list_recent(10).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn read_frame_rejects_oversized_length() {
+        let mut raw = ((MAX_FRAME_BYTES + 1) as u32).to_be_bytes().to_vec();
+        raw.extend_from_slice(b"{}");
+        let err = read_frame::<_, Request>(&mut raw.as_slice()).unwrap_err();
+        assert!(err.to_string().contains("too large"), "{err:#}");
+    }
+}
@@ -2,16 +2,23 @@ use anyhow::{Context, Result};
 use clap::{Parser, Subcommand};
 use rand::Rng;
 
-use crate::{config, history_import, hook, p2p, search, storage, tracker, transport};
+use crate::{
+    agent, config, content_key, entry_format, export, history_import, hook, import_sources, p2p,
+    pair, picker, redact, scheduler, search, storage, tracker, transport,
+};
 use std::time::{Duration, Instant};
 
-const DEFAULT_ASYNC_UPLOAD_INTERVAL_SEC: u64 = 15;
-const DEFAULT_ASYNC_UPLOAD_LIMIT: usize = 200;
-const DEFAULT_ASYNC_UPLOAD_MARKER_PATH: &str = "~/.config/rustory/async-upload.last";
-const DEFAULT_AUTO_PRUNE_DAYS: u64 = 180;
-const DEFAULT_AUTO_PRUNE_INTERVAL_SEC: u64 = 86_400;
-const DEFAULT_AUTO_PRUNE_KEEP_RECENT: usize = 0;
-const DEFAULT_AUTO_PRUNE_MARKER_PATH: &str = "~/.config/rustory/auto-prune.last";
+const DEFAULT_SCHEDULER_BACKOFF_BASE_SEC: u64 = 30;
+const DEFAULT_SCHEDULER_BACKOFF_CAP_SEC: u64 = 3_600;
+const DEFAULT_SCHEDULER_SYNC_INTERVAL_SEC: u64 = 60;
+const DEFAULT_SCHEDULER_SYNC_LIMIT: usize = 1000;
+const DEFAULT_SCHEDULER_PRUNE_INTERVAL_SEC: u64 = 86_400;
+const DEFAULT_SCHEDULER_PRUNE_DAYS: u64 = 180;
+const DEFAULT_SCHEDULER_TRACKER_REGISTER_INTERVAL_SEC: u64 = 300;
+const DEFAULT_SCHEDULER_PEER_BOOK_INTERVAL_SEC: u64 = 3_600;
+const DEFAULT_SCHEDULER_PEER_BOOK_MAX_AGE_DAYS: u64 = 30;
+// p2p-serve는 30초마다 자기 주소를 남긴다. 이보다 오래됐으면 멈춘 것으로 보고 재등록하지 않는다.
+const LOCAL_ADVERT_MAX_AGE_SEC: i64 = 120;
 
 #[derive(Parser)]
 #[command(name = "rr", version, about = "Rustory CLI")]
@@ -28,6 +35,17 @@ enum Command {
     Serve {
         #[arg(long, default_value = "0.0.0.0:8844")]
         bind: String,
+
+        /// 요청에 요구할 Bearer 토큰(env RUSTORY_HTTP_TOKEN, config http_token).
+        #[arg(long)]
+        token: Option<String>,
+
+        /// TLS 인증서 PEM. `--tls-key`와 함께 주면 HTTPS로 listen한다.
+        #[arg(long, requires = "tls_key")]
+        tls_cert: Option<String>,
+
+        #[arg(long, requires = "tls_cert")]
+        tls_key: Option<String>,
     },
     Sync {
         #[arg(long, value_delimiter = ',')]
@@ -35,6 +53,18 @@ enum Command {
 
         #[arg(long)]
         push: bool,
+
+        /// 피어에 보낼 Bearer 토큰(env RUSTORY_HTTP_TOKEN, config http_token).
+        #[arg(long)]
+        token: Option<String>,
+
+        /// https 피어 검증에 쓸 CA 인증서 PEM.
+        #[arg(long, conflicts_with = "cert_fingerprint")]
+        ca: Option<String>,
+
+        /// https 피어 인증서의 SHA-256 지문(self-signed 고정용).
+        #[arg(long)]
+        cert_fingerprint: Option<String>,
     },
     P2pServe {
         #[arg(long, default_value = "/ip4/0.0.0.0/tcp/0")]
@@ -54,6 +84,10 @@ enum Command {
 
         #[arg(long)]
         tracker_token: Option<String>,
+
+        /// mDNS로 LAN에 자신을 알리고 찾은 피어를 peer_book에 기록한다(env RUSTORY_P2P_MDNS, config p2p_mdns).
+        #[arg(long)]
+        mdns: bool,
     },
     P2pSync {
         #[arg(long, value_delimiter = ',')]
@@ -71,6 +105,14 @@ enum Command {
         #[arg(long, default_value_t = 60)]
         interval_sec: u64,
 
+        /// watch 모드에서 sync-notify 알림을 쓰지 않고 `--interval-sec`마다 polling만 한다.
+        #[arg(long)]
+        no_notify: bool,
+
+        /// watch 모드에서 알림을 받은 뒤 pull하기 전에 기다리는 시간(ms).
+        #[arg(long, default_value_t = 200)]
+        debounce_ms: u64,
+
         #[arg(long)]
         start_jitter_sec: Option<u64>,
 
@@ -86,6 +128,10 @@ enum Command {
         #[arg(long)]
         req_backoff_base_ms: Option<u64>,
 
+        /// 디바이스 identity 키. 서버의 peer allow-list(`rr peer-acl`)가 이 PeerId로 디바이스를 식별한다.
+        #[arg(long)]
+        identity_key: Option<String>,
+
         #[arg(long)]
         swarm_key: Option<String>,
 
@@ -97,42 +143,59 @@ enum Command {
 
         #[arg(long)]
         tracker_token: Option<String>,
+
+        /// `--peers`가 없을 때 mDNS로 LAN의 피어도 찾는다(env RUSTORY_P2P_MDNS, config p2p_mdns).
+        #[arg(long)]
+        mdns: bool,
+
+        /// `--push`와 함께 다른 디바이스의 엔트리도 version vector 기준으로 전달한다
+        /// (env RUSTORY_P2P_GOSSIP, config p2p_gossip).
+        #[arg(long)]
+        gossip: bool,
     },
     SwarmKey {
         #[arg(long)]
         swarm_key: Option<String>,
     },
+    /// cmd/cwd 종단간 암호화용 콘텐츠 키를 만들거나(없을 때) 지문을 출력한다.
+    ContentKey {
+        #[arg(long)]
+        content_key: Option<String>,
+    },
     Record {
+        #[command(flatten)]
+        args: RecordArgs,
+    },
+    Search {
         #[arg(long)]
-        cmd: String,
+        limit: Option<usize>,
 
+        /// cmd/cwd 전문 검색어(공백으로 구분된 term은 AND). 지정하면 picker에 넘기기 전에 후보를 걸러낸다.
         #[arg(long)]
-        cwd: Option<String>,
-
-        #[arg(long, default_value_t = 0)]
-        exit_code: i32,
-
-        #[arg(long, default_value_t = 0)]
-        duration_ms: i64,
+        query: Option<String>,
 
+        /// picker 구현: builtin(기본) | fzf
         #[arg(long)]
-        shell: Option<String>,
+        backend: Option<String>,
 
+        /// picker 시작 필터 모드: global | host | directory | session
         #[arg(long)]
-        hostname: Option<String>,
+        filter_mode: Option<String>,
 
+        /// directory 모드 기준 경로(기본: 현재 디렉터리). hook이 `$PWD`를 넘긴다.
         #[arg(long)]
-        user_id: Option<String>,
+        context_cwd: Option<String>,
 
+        /// host 모드 기준 hostname(기본: `$HOSTNAME`/`$HOST`)
         #[arg(long)]
-        device_id: Option<String>,
+        context_hostname: Option<String>,
 
-        #[arg(long, default_value_t = false)]
-        print_id: bool,
-    },
-    Search {
+        /// session 모드 기준 세션 id. hook이 셸 세션 id를 넘긴다.
         #[arg(long)]
-        limit: Option<usize>,
+        context_session_id: Option<String>,
+
+        #[command(flatten)]
+        filters: SearchFilterArgs,
     },
     Prune {
         #[arg(long)]
@@ -144,6 +207,23 @@ enum Command {
         #[arg(long, default_value_t = false)]
         dry_run: bool,
     },
+    /// 엔트리를 지우고 tombstone을 남긴다(동기화로 다른 디바이스에도 전파되어 다시 들어오지 않음).
+    Delete {
+        #[arg(required_unless_present = "match_regex")]
+        entry_ids: Vec<String>,
+
+        /// cmd가 이 정규식에 매칭되는 엔트리를 모두 지운다.
+        #[arg(long = "match")]
+        match_regex: Option<String>,
+
+        #[arg(long, default_value_t = false)]
+        dry_run: bool,
+    },
+    /// 저장된 엔트리의 cmd에서 비밀값을 가린다(기본은 개수만 보고, `--apply`로 실제 수정).
+    Redact {
+        #[arg(long, default_value_t = false)]
+        apply: bool,
+    },
     SyncStatus {
         #[arg(long)]
         peer: Option<String>,
@@ -167,6 +247,18 @@ enum Command {
 
         #[arg(long)]
         token: Option<String>,
+
+        /// 테넌트별 토큰/관리자 토큰을 정의한 TOML 파일(지정 시 `--token` 대신 사용).
+        #[arg(long, conflicts_with = "token")]
+        config: Option<String>,
+
+        /// 피어 레코드를 SQLite에 영속화한다(재시작 직후에도 목록 응답 가능).
+        #[arg(long)]
+        db: Option<String>,
+
+        /// 서명 없는 등록도 받는다(구버전 클라이언트 전환용). 서명으로 등록된 피어는 덮어쓸 수 없다.
+        #[arg(long)]
+        allow_unsigned: bool,
     },
     RelayServe {
         #[arg(long, default_value = "/ip4/0.0.0.0/tcp/4001")]
@@ -205,6 +297,11 @@ enum Command {
         #[arg(long, default_value = "zsh")]
         shell: String,
 
+        /// 셸 히스토리 대신 다른 히스토리 도구 DB나 `rr export` 덤프에서 가져온다
+        /// (atuin|mcfly|resh|hishtory|jsonl|csv).
+        #[arg(long, conflicts_with = "shell")]
+        from: Option<String>,
+
         #[arg(long)]
         path: Option<String>,
 
@@ -220,24 +317,165 @@ enum Command {
         #[arg(long)]
         hostname: Option<String>,
     },
+    /// 엔트리를 파일/표준출력으로 내보낸다(오래된 순).
+    Export {
+        /// jsonl(기본) | csv | zsh | bash
+        #[arg(long, default_value = "jsonl")]
+        format: String,
+
+        /// 출력 파일 경로(미지정 시 표준출력)
+        #[arg(long)]
+        output: Option<String>,
+
+        /// cmd/cwd 전문 검색어(`rr search --query`와 동일)
+        #[arg(long)]
+        query: Option<String>,
+
+        #[command(flatten)]
+        filters: SearchFilterArgs,
+    },
+    /// 히스토리를 비대화형으로 조회한다(스크립트/대시보드용).
+    History {
+        #[command(subcommand)]
+        cmd: HistoryCommand,
+    },
+    /// p2p-serve가 받아들일 피어(PeerId → user_id/device_id) 허용 목록을 관리한다.
+    PeerAcl {
+        #[command(subcommand)]
+        cmd: PeerAclCommand,
+    },
+    /// 서명된 초대 코드로 새 디바이스를 swarm에 합류시킨다.
+    Pair {
+        #[command(subcommand)]
+        cmd: PairCommand,
+    },
+    /// DB를 열어 둔 채 Unix 소켓으로 record/search를 받는 로컬 데몬. 하위 명령이 없으면 실행한다.
+    Agent {
+        /// env RUSTORY_AGENT_SOCKET, config agent_socket_path, 기본 ~/.rustory/agent.sock
+        #[arg(long, global = true)]
+        socket: Option<String>,
+
+        #[command(subcommand)]
+        cmd: Option<AgentCommand>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum AgentCommand {
+    /// 실행 중인 agent의 상태를 출력한다(없으면 실패).
+    Status {
+        #[arg(long, default_value_t = false)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum HistoryCommand {
+    /// `rr search`와 같은 필터로 엔트리를 최신순으로 출력한다.
+    List {
+        #[arg(long, default_value_t = 100)]
+        limit: usize,
+
+        /// cmd/cwd 전문 검색어(`rr search --query`와 동일)
+        #[arg(long)]
+        query: Option<String>,
+
+        /// table(기본) | json | ndjson | 템플릿(예: '{ts} {cmd}')
+        #[arg(long, default_value = "table")]
+        format: String,
+
+        #[command(flatten)]
+        filters: SearchFilterArgs,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum PeerAclCommand {
+    /// 피어를 허용 목록에 추가한다(이미 있으면 바인딩을 갱신한다).
+    Add {
+        #[arg(long)]
+        peer_id: String,
+
+        /// 미지정 시 이 디바이스의 user_id
+        #[arg(long)]
+        user_id: Option<String>,
+
+        #[arg(long)]
+        device_id: String,
+    },
+    Remove {
+        #[arg(long)]
+        peer_id: String,
+    },
+    List {
+        #[arg(long, default_value_t = false)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum PairCommand {
+    /// 새 디바이스용 초대 코드를 표준출력으로 낸다(PSK/tracker/relay 포함, identity 키로 서명).
+    Invite {
+        /// 합류할 디바이스의 device_id
+        #[arg(long)]
+        device_id: String,
+
+        /// 미지정 시 이 디바이스의 user_id
+        #[arg(long)]
+        user_id: Option<String>,
+
+        #[arg(long, default_value_t = 600)]
+        ttl_sec: u64,
+    },
+    /// 초대 코드를 검증하고 config/키 파일을 쓴 뒤, 초대한 디바이스에 돌려줄 수락 코드를 출력한다.
+    Join {
+        code: String,
+
+        /// 기존 config/키 파일을 덮어쓴다.
+        #[arg(long)]
+        force: bool,
+    },
+    /// `rr pair join`이 출력한 수락 코드를 검증하고, 합류한 디바이스를 허용 목록에 등록한다.
+    Accept { code: String },
 }
 
 pub fn run() -> Result<()> {
     let app = App::parse();
+    // hook이 프롬프트마다 부르는 경로라 설정 파일을 읽기 전에 처리한다.
+    if let Command::Record { args } = app.cmd {
+        return run_record(args, app.db_path);
+    }
     let cfg = config::load_default()?;
-
-    let db_path = normalize_opt_string(app.db_path)
-        .or_else(|| env_nonempty("RUSTORY_DB_PATH"))
-        .or_else(|| normalize_opt_string(cfg.db_path.clone()))
-        .unwrap_or_else(|| storage::DEFAULT_DB_PATH.to_string());
+    let db_path = resolve_db_path(app.db_path, &cfg);
 
     match app.cmd {
-        Command::Serve { bind } => {
-            transport::serve(&bind, &db_path)?;
+        Command::Serve {
+            bind,
+            token,
+            tls_cert,
+            tls_key,
+        } => {
+            let opts = resolve_http_serve_options(token, tls_cert, tls_key, &cfg)?;
+            transport::serve(&bind, &db_path, resolve_content_key(&cfg)?, &opts)?;
         }
-        Command::Sync { peers, push } => {
+        Command::Sync {
+            peers,
+            push,
+            token,
+            ca,
+            cert_fingerprint,
+        } => {
             let device_id = resolve_device_id(&cfg);
-            transport::sync(&peers, &db_path, push, Some(&device_id))?;
+            let client_opts = resolve_http_client_options(token, ca, cert_fingerprint, &cfg)?;
+            transport::sync(
+                &peers,
+                &db_path,
+                push,
+                Some(&device_id),
+                resolve_content_key(&cfg)?,
+                &client_opts,
+            )?;
         }
         Command::P2pServe {
             listen,
@@ -246,13 +484,18 @@ pub fn run() -> Result<()> {
             relay,
             trackers,
             tracker_token,
+            mdns,
         } => {
             let psk = resolve_swarm_psk(swarm_key, &cfg)?;
             let identity = resolve_p2p_identity(identity_key, &cfg)?;
             let relay_addr = resolve_relay_addr(relay, &cfg)?;
             let trackers = resolve_trackers(trackers, &cfg)?;
             let tracker_token = resolve_tracker_token(tracker_token, &cfg)?;
+            let mdns = resolve_p2p_mdns(mdns, &cfg)?;
             let meta = resolve_peer_meta(&cfg);
+            let content_key = resolve_content_key(&cfg)?;
+            // agent 스케줄러가 tracker 재등록을 맡으면 p2p-serve는 주소가 바뀔 때만 등록한다.
+            let periodic_register = !cfg.scheduler.tracker_register.enabled.unwrap_or(false);
 
             p2p::serve(
                 &listen,
@@ -264,6 +507,9 @@ pub fn run() -> Result<()> {
                     trackers,
                     tracker_token,
                     meta,
+                    content_key,
+                    mdns,
+                    periodic_register,
                 },
             )?;
         }
@@ -273,20 +519,31 @@ pub fn run() -> Result<()> {
             push,
             watch,
             interval_sec,
+            no_notify,
+            debounce_ms,
             start_jitter_sec,
             req_attempts,
             req_timeout_base_sec,
             req_timeout_cap_sec,
             req_backoff_base_ms,
+            identity_key,
             swarm_key,
             relay,
             trackers,
             tracker_token,
+            mdns,
+            gossip,
         } => {
             let psk = resolve_swarm_psk(swarm_key, &cfg)?;
+            let identity = resolve_p2p_identity(identity_key, &cfg)?;
             let relay_addr = resolve_relay_addr(relay, &cfg)?;
             let trackers = resolve_trackers(trackers, &cfg)?;
             let tracker_token = resolve_tracker_token(tracker_token, &cfg)?;
+            let mdns = resolve_p2p_mdns(mdns, &cfg)?;
+            let gossip = resolve_p2p_gossip(gossip, &cfg)?;
+            if gossip && !push {
+                anyhow::bail!("--gossip requires --push");
+            }
             let user_id = resolve_user_id(&cfg);
             let device_id = resolve_device_id(&cfg);
             let request_retry_policy = resolve_p2p_request_retry_policy(
@@ -305,14 +562,22 @@ pub fn run() -> Result<()> {
                 user_id: Some(user_id),
                 device_id: Some(device_id),
                 request_retry_policy,
+                content_key: resolve_content_key(&cfg)?,
+                identity: Some(identity),
+                mdns,
+                gossip,
             };
 
             if watch {
-                let interval = Duration::from_secs(interval_sec.max(1));
+                let watch_cfg = p2p::WatchConfig {
+                    interval: Duration::from_secs(interval_sec.max(1)),
+                    debounce: Duration::from_millis(debounce_ms),
+                    notify: !no_notify,
+                };
                 let start_jitter_sec = resolve_p2p_watch_start_jitter_sec(start_jitter_sec, &cfg)?;
                 eprintln!(
-                    "p2p-sync watch: interval={:?} start_jitter_sec={}",
-                    interval, start_jitter_sec
+                    "p2p-sync watch: interval={:?} notify={} debounce={:?} start_jitter_sec={}",
+                    watch_cfg.interval, watch_cfg.notify, watch_cfg.debounce, start_jitter_sec
                 );
                 let stop = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
                 {
@@ -341,13 +606,15 @@ pub fn run() -> Result<()> {
                     }
                 }
 
-                while !stop.load(std::sync::atomic::Ordering::SeqCst) {
-                    if let Err(err) = p2p::sync(&peers, limit, &db_path, sync_cfg.clone(), push) {
-                        eprintln!("warn: p2p-sync failed: {err:#}");
-                    }
-
-                    sleep_with_stop(interval, stop.as_ref());
-                }
+                p2p::watch(
+                    &peers,
+                    limit,
+                    &db_path,
+                    sync_cfg,
+                    push,
+                    watch_cfg,
+                    stop.as_ref(),
+                )?;
 
                 eprintln!("p2p-sync watch: shutting down");
                 return Ok(());
@@ -363,95 +630,121 @@ pub fn run() -> Result<()> {
             println!("swarm key path: {}", expanded.display());
             println!("swarm key fingerprint: {}", psk.fingerprint());
         }
-        Command::Record {
-            cmd,
-            cwd,
-            exit_code,
-            duration_ms,
-            shell,
-            hostname,
-            user_id,
-            device_id,
-            print_id,
-        } => {
-            let cmd = cmd.trim();
-            if cmd.is_empty() {
-                return Ok(());
-            }
-            if is_self_rr_command(cmd) {
-                return Ok(());
-            }
-            if let Some(pattern) = resolve_record_ignore_regex(&cfg) {
-                match should_ignore_record_command(cmd, &pattern) {
-                    Ok(true) => return Ok(()),
-                    Ok(false) => {}
-                    Err(err) => {
-                        // 훅은 stderr를 버릴 수 있으므로, 실패 시에도 안전하게(= 기록 스킵) 동작한다.
-                        eprintln!(
-                            "warn: invalid record ignore regex: {err} (skipping record for safety)"
-                        );
-                        return Ok(());
-                    }
+        Command::ContentKey { content_key } => {
+            let key = match env_nonempty("RUSTORY_CONTENT_PASSPHRASE") {
+                Some(passphrase) => {
+                    println!("content key: derived from RUSTORY_CONTENT_PASSPHRASE");
+                    content_key::ContentKey::from_passphrase(&passphrase, &resolve_user_id(&cfg))?
                 }
-            }
-
-            let store = storage::LocalStore::open(&db_path)?;
-            let cwd = normalize_opt_string(cwd).unwrap_or_else(default_cwd);
-
-            let hostname = normalize_opt_string(hostname)
-                .or_else(|| env_nonempty("HOSTNAME"))
-                .unwrap_or_else(|| "unknown".to_string());
-
-            let shell = normalize_opt_string(shell)
-                .or_else(default_shell)
-                .unwrap_or_else(|| "unknown".to_string());
-
-            let user_id = normalize_opt_string(user_id)
-                .or_else(|| env_nonempty("RUSTORY_USER_ID"))
-                .or_else(|| normalize_opt_string(cfg.user_id.clone()))
-                .or_else(|| env_nonempty("USER"))
-                .unwrap_or_else(|| "unknown".to_string());
-
-            let device_id = normalize_opt_string(device_id)
-                .or_else(|| env_nonempty("RUSTORY_DEVICE_ID"))
-                .or_else(|| normalize_opt_string(cfg.device_id.clone()))
-                .unwrap_or_else(|| hostname.clone());
+                None => {
+                    let path = resolve_content_key_path(content_key, &cfg);
+                    let key = config::load_or_generate_content_key(&path)?;
+                    let expanded = config::expand_home_path(&path)?;
+                    println!("content key path: {}", expanded.display());
+                    key
+                }
+            };
+            println!("content key fingerprint: {}", key.fingerprint());
+        }
+        Command::Record { .. } => unreachable!("rr record is handled before loading config"),
+        Command::Search {
+            limit,
+            query,
+            backend,
+            filter_mode,
+            context_cwd,
+            context_hostname,
+            context_session_id,
+            filters,
+        } => {
+            let limit = resolve_search_limit(limit, &cfg)?;
+            let backend = resolve_search_backend(backend, &cfg)?;
+            let mode = resolve_search_filter_mode(filter_mode, &cfg)?;
+            let now_unix = time::OffsetDateTime::now_utc().unix_timestamp();
+            let filters = filters.into_filters(now_unix)?;
 
-            let entry = crate::core::Entry::new(crate::core::EntryInput {
-                device_id,
-                user_id,
-                ts: time::OffsetDateTime::now_utc(),
-                cmd: cmd.to_string(),
-                cwd,
-                exit_code,
-                duration_ms,
-                shell,
-                hostname,
-            });
-
-            store.insert_entries(std::slice::from_ref(&entry))?;
-
-            if print_id {
-                println!("{}", entry.entry_id);
+            let query = normalize_opt_string(query);
+            let req = agent::Request::Search {
+                db_path: agent_db_path(&db_path),
+                query: query.clone(),
+                filters: filters.clone(),
+                limit,
+            };
+            let entries = match agent_request(&cfg, &req, AGENT_SEARCH_TIMEOUT) {
+                Some(agent::Response::Entries { entries }) => entries,
+                _ => {
+                    let store = storage::LocalStore::open(&db_path)?
+                        .with_content_key(resolve_content_key(&cfg)?);
+                    load_search_entries(&store, query.as_deref(), &filters, limit)?
+                }
+            };
+            let ctx = picker::PickerContext {
+                mode,
+                cwd: Some(normalize_opt_string(context_cwd).unwrap_or_else(default_cwd)),
+                hostname: normalize_opt_string(context_hostname).or_else(default_hostname),
+                session_id: normalize_opt_string(context_session_id)
+                    .or_else(|| env_nonempty("RUSTORY_SESSION_ID")),
+            };
+            if let Some(cmd) = search::select_command(&entries, backend, &ctx)? {
+                println!("{cmd}");
             }
+        }
+        Command::History {
+            cmd:
+                HistoryCommand::List {
+                    limit,
+                    query,
+                    format,
+                    filters,
+                },
+        } => {
+            let format = entry_format::OutputFormat::parse(&format)?;
+            let now_unix = time::OffsetDateTime::now_utc().unix_timestamp();
+            let filters = filters.into_filters(now_unix)?;
 
-            if let Err(err) = maybe_spawn_async_upload(&db_path) {
-                // 기록 성공을 우선하고, 비동기 업로드 트리거 실패는 경고로만 남긴다.
-                eprintln!("warn: async upload trigger failed: {err:#}");
-            }
+            let store =
+                storage::LocalStore::open(&db_path)?.with_content_key(resolve_content_key(&cfg)?);
+            let query = normalize_opt_string(query);
+            let entries = if query.is_none() && filters.is_empty() {
+                store.list_recent(limit)?
+            } else {
+                store.search(query.as_deref().unwrap_or(""), &filters, limit)?
+            };
 
-            if let Err(err) = maybe_run_auto_prune(&store) {
-                // 기록 성공을 우선하고, 자동 보관 실패는 경고로만 남긴다.
-                eprintln!("warn: auto prune failed: {err:#}");
-            }
+            let stdout = std::io::stdout();
+            entry_format::write_entries(&mut stdout.lock(), &entries, &format)?;
         }
-        Command::Search { limit } => {
-            let limit = resolve_search_limit(limit, &cfg)?;
-
-            let store = storage::LocalStore::open(&db_path)?;
-            let entries = store.list_recent(limit)?;
-            if let Some(cmd) = search::select_command(&entries)? {
-                println!("{cmd}");
+        Command::Export {
+            format,
+            output,
+            query,
+            filters,
+        } => {
+            let format = export::ExportFormat::parse(&format)?;
+            let now_unix = time::OffsetDateTime::now_utc().unix_timestamp();
+            let filters = filters.into_filters(now_unix)?;
+            let query = normalize_opt_string(query).unwrap_or_default();
+
+            let store =
+                storage::LocalStore::open(&db_path)?.with_content_key(resolve_content_key(&cfg)?);
+            match normalize_opt_string(output) {
+                Some(output) => {
+                    let path = config::expand_home_path(&output)?;
+                    let file = std::fs::File::create(&path)
+                        .with_context(|| format!("create export file: {}", path.display()))?;
+                    let mut out = std::io::BufWriter::new(file);
+                    let n = export::export_entries(&store, &query, &filters, format, &mut out)?;
+                    eprintln!(
+                        "export: path={} format={} entries={n}",
+                        path.display(),
+                        format.as_str()
+                    );
+                }
+                None => {
+                    let stdout = std::io::stdout();
+                    let mut out = std::io::BufWriter::new(stdout.lock());
+                    export::export_entries(&store, &query, &filters, format, &mut out)?;
+                }
             }
         }
         Command::Prune {
@@ -477,6 +770,132 @@ pub fn run() -> Result<()> {
                 );
             }
         }
+        Command::Delete {
+            mut entry_ids,
+            match_regex,
+            dry_run,
+        } => {
+            let store = storage::LocalStore::open(&db_path)?;
+            if let Some(pattern) = normalize_opt_string(match_regex) {
+                let re = regex::Regex::new(&pattern)
+                    .with_context(|| format!("invalid --match regex: {pattern}"))?;
+                entry_ids.extend(store.find_entry_ids_matching(&re)?);
+            }
+            entry_ids.sort();
+            entry_ids.dedup();
+            let stats = store.delete_entries(&entry_ids, dry_run)?;
+
+            let label = if dry_run { "delete dry-run" } else { "delete" };
+            println!(
+                "{label}: requested={} matched={} deleted={}",
+                entry_ids.len(),
+                stats.matched,
+                stats.deleted
+            );
+        }
+        Command::PeerAcl { cmd } => {
+            let store = storage::LocalStore::open(&db_path)?;
+            match cmd {
+                PeerAclCommand::Add {
+                    peer_id,
+                    user_id,
+                    device_id,
+                } => {
+                    let peer_id = peer_id.trim().to_string();
+                    peer_id
+                        .parse::<libp2p::PeerId>()
+                        .with_context(|| format!("invalid --peer-id: {peer_id}"))?;
+                    let device_id = device_id.trim().to_string();
+                    if device_id.is_empty() {
+                        anyhow::bail!("--device-id is empty");
+                    }
+                    let acl = storage::PeerAcl {
+                        peer_id,
+                        user_id: normalize_opt_string(user_id)
+                            .unwrap_or_else(|| resolve_user_id(&cfg)),
+                        device_id,
+                        added_at_unix: time::OffsetDateTime::now_utc().unix_timestamp(),
+                    };
+                    store.upsert_peer_acl(&acl)?;
+                    println!(
+                        "peer-acl add: peer_id={} user_id={} device_id={}",
+                        acl.peer_id, acl.user_id, acl.device_id
+                    );
+                }
+                PeerAclCommand::Remove { peer_id } => {
+                    let removed = store.remove_peer_acl(peer_id.trim())?;
+                    println!(
+                        "peer-acl remove: peer_id={} removed={removed}",
+                        peer_id.trim()
+                    );
+                }
+                PeerAclCommand::List { json } => {
+                    let peers = store.list_peer_acl()?;
+                    if json {
+                        println!("{}", serde_json::to_string_pretty(&peers)?);
+                    } else if peers.is_empty() {
+                        println!("peer-acl: empty (p2p-serve accepts any peer with the swarm key)");
+                    } else {
+                        for p in peers {
+                            println!(
+                                "{} user_id={} device_id={}",
+                                p.peer_id, p.user_id, p.device_id
+                            );
+                        }
+                    }
+                }
+            }
+        }
+        Command::Agent { socket, cmd } => {
+            let socket_path = config::expand_home_path(&resolve_agent_socket_path(socket, &cfg))?;
+            match cmd {
+                None => run_agent(&socket_path, &db_path, &cfg)?,
+                Some(AgentCommand::Status { json }) => {
+                    let resp = agent::request(
+                        &socket_path,
+                        &agent::Request::Status,
+                        AGENT_RECORD_TIMEOUT,
+                    )?;
+                    let Some(agent::Response::Status(status)) = resp else {
+                        anyhow::bail!("agent is not running: {}", socket_path.display());
+                    };
+                    if json {
+                        println!("{}", serde_json::to_string_pretty(&status)?);
+                    } else {
+                        println!(
+                            "agent: pid={} version={} db_path={} socket={} started_at_unix={} records={} searches={}",
+                            status.pid,
+                            status.version,
+                            status.db_path,
+                            status.socket_path,
+                            status.started_at_unix,
+                            status.records,
+                            status.searches
+                        );
+                    }
+                }
+            }
+        }
+        Command::Pair { cmd } => match cmd {
+            PairCommand::Invite {
+                device_id,
+                user_id,
+                ttl_sec,
+            } => run_pair_invite(&device_id, user_id, ttl_sec, &cfg, &db_path)?,
+            PairCommand::Join { code, force } => run_pair_join(&code, force, &cfg, &db_path)?,
+            PairCommand::Accept { code } => run_pair_accept(&code, &cfg, &db_path)?,
+        },
+        Command::Redact { apply } => {
+            let redactor = resolve_redactor(&cfg)?;
+            let store = storage::LocalStore::open(&db_path)?;
+            let stats = store.redact_entries(&redactor, apply)?;
+
+            let label = if apply { "redact" } else { "redact dry-run" };
+            println!(
+                "{label}: scanned={} matched={} updated={}",
+                stats.scanned, stats.matched, stats.updated
+            );
+        }
         Command::SyncStatus {
             peer,
             json,
@@ -495,10 +914,12 @@ pub fn run() -> Result<()> {
             } else {
                 None
             };
+            let scheduler_settings = resolve_scheduler_settings(&cfg)?;
             let report = build_sync_status_report(
                 &store,
                 &local_device_id,
                 peer.as_deref(),
+                &scheduler_settings,
                 tracker_status,
             )?;
 
@@ -541,6 +962,12 @@ pub fn run() -> Result<()> {
                 }
             }
 
+            report
+                .scheduler
+                .iter()
+                .filter(|job| job.enabled || job.last_run_unix.is_some())
+                .for_each(print_scheduler_job_report);
+
             if let Some(trackers) = report.tracker_status {
                 if trackers.is_empty() {
                     println!("tracker status: (none)");
@@ -574,8 +1001,27 @@ pub fn run() -> Result<()> {
             bind,
             ttl_sec,
             token,
+            config: tracker_config,
+            db,
+            allow_unsigned,
         } => {
-            tracker::serve(&bind, ttl_sec, token)?;
+            let auth = match normalize_opt_string(tracker_config) {
+                Some(path) => {
+                    let auth = tracker::TrackerAuth::from_config_file(&path)?;
+                    eprintln!("tracker config: {path} (tenants={})", auth.tenant_count());
+                    auth
+                }
+                None => tracker::TrackerAuth::single_token(token),
+            };
+            tracker::serve(
+                &bind,
+                tracker::TrackerOptions {
+                    ttl_sec,
+                    auth,
+                    allow_unsigned,
+                },
+                normalize_opt_string(db).as_deref(),
+            )?;
         }
         Command::RelayServe {
             listen,
@@ -612,32 +1058,13 @@ pub fn run() -> Result<()> {
         }
         Command::Import {
             shell,
+            from,
             path,
             limit,
             user_id,
             device_id,
             hostname,
         } => {
-            let shell = history_import::HistoryShell::parse(shell.as_str())?;
-            let path = normalize_opt_string(path)
-                .unwrap_or_else(|| shell.default_history_path().to_string());
-            let path = config::expand_home_path(&path)?;
-
-            let hostname = normalize_opt_string(hostname)
-                .or_else(|| env_nonempty("HOSTNAME"))
-                .unwrap_or_else(|| "unknown".to_string());
-
-            let user_id = normalize_opt_string(user_id)
-                .or_else(|| env_nonempty("RUSTORY_USER_ID"))
-                .or_else(|| normalize_opt_string(cfg.user_id.clone()))
-                .or_else(|| env_nonempty("USER"))
-                .unwrap_or_else(|| "unknown".to_string());
-
-            let device_id = normalize_opt_string(device_id)
-                .or_else(|| env_nonempty("RUSTORY_DEVICE_ID"))
-                .or_else(|| normalize_opt_string(cfg.device_id.clone()))
-                .unwrap_or_else(|| hostname.clone());
-
             let ignore_re = match resolve_record_ignore_regex(&cfg) {
                 Some(pattern) => match regex::Regex::new(&pattern) {
                     Ok(re) => Some(re),
@@ -650,26 +1077,98 @@ pub fn run() -> Result<()> {
                 },
                 None => None,
             };
+            let redactor = match resolve_redactor(&cfg) {
+                Ok(redactor) => redactor,
+                Err(err) => {
+                    eprintln!("warn: invalid redact config: {err:#} (skipping import for safety)");
+                    return Ok(());
+                }
+            };
 
-            let content = history_import::read_history_file(&path)?;
-            let store = storage::LocalStore::open(&db_path)?;
-            let stats = history_import::import_into_store(
-                &store,
-                history_import::ImportRequest {
-                    shell,
-                    content: &content,
+            let from = normalize_opt_string(from);
+            if let Some(format) = from.as_deref().and_then(export::ExportFormat::entry_dump) {
+                // rr export 덤프는 entry_id/device_id 등을 그대로 보존해 되돌린다.
+                let path = normalize_opt_string(path).with_context(|| {
+                    format!("--path is required for --from {}", format.as_str())
+                })?;
+                let path = config::expand_home_path(&path)?;
+                let entries =
+                    export::read_entries(format, &history_import::read_history_file(&path)?)?;
+                let store = storage::LocalStore::open(&db_path)?;
+                let stats = export::import_entries(
+                    &store,
+                    entries,
                     limit,
-                    user_id: &user_id,
+                    ignore_re.as_ref(),
+                    Some(&redactor),
+                )?;
+                println!(
+                    "import: path={} source={} received={} inserted={} ignored={} skipped={}",
+                    path.display(),
+                    format.as_str(),
+                    stats.received,
+                    stats.inserted,
+                    stats.ignored,
+                    stats.skipped
+                );
+                return Ok(());
+            }
+
+            // source: entry_id 네임스페이스, default_shell: 레코드에 셸 정보가 없을 때의 값.
+            let (source, default_shell, path, records) = match from {
+                Some(from) => {
+                    let source = import_sources::ImportSource::parse(&from)?;
+                    let path = normalize_opt_string(path)
+                        .unwrap_or_else(|| source.default_path().to_string());
+                    let path = config::expand_home_path(&path)?;
+                    let records = import_sources::load_records(source, &path)?;
+                    (source.as_str(), "unknown", path, records)
+                }
+                None => {
+                    let shell = history_import::HistoryShell::parse(shell.as_str())?;
+                    let path = normalize_opt_string(path)
+                        .unwrap_or_else(|| shell.default_history_path().to_string());
+                    let path = config::expand_home_path(&path)?;
+                    let records = history_import::load_history(shell, &path)?;
+                    (shell.as_str(), shell.as_str(), path, records)
+                }
+            };
+
+            let hostname = normalize_opt_string(hostname)
+                .or_else(default_hostname)
+                .unwrap_or_else(|| "unknown".to_string());
+
+            let user_id = normalize_opt_string(user_id)
+                .or_else(|| env_nonempty("RUSTORY_USER_ID"))
+                .or_else(|| normalize_opt_string(cfg.user_id.clone()))
+                .or_else(|| env_nonempty("USER"))
+                .unwrap_or_else(|| "unknown".to_string());
+
+            let device_id = normalize_opt_string(device_id)
+                .or_else(|| env_nonempty("RUSTORY_DEVICE_ID"))
+                .or_else(|| normalize_opt_string(cfg.device_id.clone()))
+                .unwrap_or_else(|| hostname.clone());
+
+            let store = storage::LocalStore::open(&db_path)?;
+            let stats = history_import::import_into_store(
+                &store,
+                records,
+                history_import::ImportRequest {
+                    source,
+                    default_shell,
+                    limit,
+                    user_id: &user_id,
                     device_id: &device_id,
                     hostname: &hostname,
                     ignore_regex: ignore_re.as_ref(),
+                    redactor: Some(&redactor),
                 },
             )?;
 
             println!(
-                "import: path={} shell={} received={} inserted={} ignored={} skipped={}",
+                "import: path={} source={} received={} inserted={} ignored={} skipped={}",
                 path.display(),
-                shell.as_str(),
+                source,
                 stats.received,
                 stats.inserted,
                 stats.ignored,
@@ -681,6 +1180,271 @@ pub fn run() -> Result<()> {
     Ok(())
 }
 
+fn run_record(args: RecordArgs, cli_db_path: Option<String>) -> Result<()> {
+    let Some(mut entry) = args.entry() else {
+        return Ok(());
+    };
+
+    // 설정을 읽지 않고 먼저 agent 소켓(env 또는 기본 경로)에 보낸다.
+    let explicit_db_path =
+        normalize_opt_string(cli_db_path.clone()).or_else(|| env_nonempty("RUSTORY_DB_PATH"));
+    if let Some(recorded) = record_via_agent_socket(&entry, explicit_db_path) {
+        if recorded && args.print_id {
+            println!("{}", entry.entry_id);
+        }
+        return Ok(());
+    }
+
+    let cfg = config::load_default()?;
+    let db_path = resolve_db_path(cli_db_path, &cfg);
+    fill_record_identity(&mut entry, &cfg);
+
+    // 설정의 소켓 경로/DB로 다시 시도한다. 같은 entry_id라 앞의 요청이 늦게 반영돼도 중복되지 않는다.
+    let req = agent::Request::Record {
+        db_path: Some(agent_db_path(&db_path)),
+        entry: entry.clone(),
+    };
+    if let Some(resp) = agent_request(&cfg, &req, AGENT_RECORD_TIMEOUT) {
+        if let agent::Response::Recorded { recorded } = resp {
+            if recorded && args.print_id {
+                println!("{}", entry.entry_id);
+            }
+            return Ok(());
+        }
+        eprintln!("warn: unexpected agent response: {resp:?} (recording directly)");
+    }
+
+    let Some(cmd) = RecordFilter::from_config(&cfg).apply(&entry.cmd) else {
+        return Ok(());
+    };
+    entry.cmd = cmd;
+
+    let store = storage::LocalStore::open(&db_path)?;
+    store.insert_local_entries(std::slice::from_ref(&entry))?;
+
+    if args.print_id {
+        println!("{}", entry.entry_id);
+    }
+    Ok(())
+}
+
+/// config.toml 없이 agent에 기록을 맡긴다. 소켓은 `RUSTORY_AGENT_SOCKET` 또는 기본 경로만 본다.
+/// agent가 없거나 받지 않으면 `None`이고, 설정을 읽는 경로에서 다시 시도하며 경고도 거기서 낸다.
+fn record_via_agent_socket(entry: &crate::core::Entry, db_path: Option<String>) -> Option<bool> {
+    let socket_path = env_nonempty("RUSTORY_AGENT_SOCKET")
+        .unwrap_or_else(|| agent::DEFAULT_SOCKET_PATH.to_string());
+    let socket_path = config::expand_home_path(&socket_path).ok()?;
+    let req = agent::Request::Record {
+        db_path: db_path.as_deref().map(agent_db_path),
+        entry: entry.clone(),
+    };
+    match agent::request(&socket_path, &req, AGENT_RECORD_TIMEOUT) {
+        Ok(Some(agent::Response::Recorded { recorded })) => Some(recorded),
+        _ => None,
+    }
+}
+
+/// 플래그/env로 정해지지 않은(비어 있는) user_id/device_id를 설정으로 채운다. agent도 같은 규칙을 쓴다.
+fn fill_record_identity(entry: &mut crate::core::Entry, cfg: &config::FileConfig) {
+    if entry.user_id.is_empty() {
+        entry.user_id = normalize_opt_string(cfg.user_id.clone())
+            .or_else(|| env_nonempty("USER"))
+            .unwrap_or_else(|| "unknown".to_string());
+    }
+    if entry.device_id.is_empty() {
+        entry.device_id =
+            normalize_opt_string(cfg.device_id.clone()).unwrap_or_else(|| entry.hostname.clone());
+    }
+}
+
+#[derive(Debug, Clone, clap::Args)]
+struct RecordArgs {
+    #[arg(long)]
+    cmd: String,
+
+    #[arg(long)]
+    cwd: Option<String>,
+
+    #[arg(long, default_value_t = 0)]
+    exit_code: i32,
+
+    #[arg(long, default_value_t = 0)]
+    duration_ms: i64,
+
+    #[arg(long)]
+    shell: Option<String>,
+
+    #[arg(long)]
+    hostname: Option<String>,
+
+    #[arg(long)]
+    user_id: Option<String>,
+
+    #[arg(long)]
+    device_id: Option<String>,
+
+    /// 셸 세션 식별자(hook이 셸마다 1회 생성해서 넘긴다)
+    #[arg(long)]
+    session_id: Option<String>,
+
+    #[arg(long, default_value_t = false)]
+    print_id: bool,
+}
+
+impl RecordArgs {
+    /// 기록할 엔트리. 빈 명령이나 `rr` 자신이면 `None`.
+    /// user_id/device_id는 플래그와 env만 보고, 없으면 비워 둔다([`fill_record_identity`]).
+    fn entry(&self) -> Option<crate::core::Entry> {
+        let cmd = self.cmd.trim();
+        if cmd.is_empty() || is_self_rr_command(cmd) {
+            return None;
+        }
+
+        let cwd = normalize_opt_string(self.cwd.clone()).unwrap_or_else(default_cwd);
+
+        let hostname = normalize_opt_string(self.hostname.clone())
+            .or_else(default_hostname)
+            .unwrap_or_else(|| "unknown".to_string());
+
+        let shell = normalize_opt_string(self.shell.clone())
+            .or_else(default_shell)
+            .unwrap_or_else(|| "unknown".to_string());
+
+        let user_id = normalize_opt_string(self.user_id.clone())
+            .or_else(|| env_nonempty("RUSTORY_USER_ID"))
+            .unwrap_or_default();
+
+        let device_id = normalize_opt_string(self.device_id.clone())
+            .or_else(|| env_nonempty("RUSTORY_DEVICE_ID"))
+            .unwrap_or_default();
+
+        let session_id = normalize_opt_string(self.session_id.clone())
+            .or_else(|| env_nonempty("RUSTORY_SESSION_ID"));
+
+        Some(crate::core::Entry::new(crate::core::EntryInput {
+            device_id,
+            user_id,
+            ts: time::OffsetDateTime::now_utc(),
+            cmd: cmd.to_string(),
+            cwd,
+            exit_code: self.exit_code,
+            duration_ms: self.duration_ms,
+            shell,
+            hostname,
+            session_id,
+        }))
+    }
+}
+
+/// `rr search` 등에서 공통으로 쓰는 구조화 필터 플래그.
+#[derive(Debug, Clone, Default, clap::Args)]
+struct SearchFilterArgs {
+    /// cwd가 정확히 일치하는 엔트리만
+    #[arg(long)]
+    cwd: Option<String>,
+
+    /// cwd가 해당 경로이거나 그 하위 경로인 엔트리만
+    #[arg(long)]
+    cwd_prefix: Option<String>,
+
+    #[arg(long, allow_negative_numbers = true, conflicts_with = "failed_only")]
+    exit_code: Option<i32>,
+
+    /// exit_code != 0 인 엔트리만
+    #[arg(long, default_value_t = false)]
+    failed_only: bool,
+
+    #[arg(long)]
+    hostname: Option<String>,
+
+    #[arg(long)]
+    device_id: Option<String>,
+
+    #[arg(long)]
+    shell: Option<String>,
+
+    #[arg(long)]
+    session_id: Option<String>,
+
+    /// 시작 시각(포함): unix seconds | YYYY-MM-DD(UTC) | 상대값(30m, 12h, 7d, 2w)
+    #[arg(long)]
+    since: Option<String>,
+
+    /// 끝 시각(미포함): `--since`와 같은 형식
+    #[arg(long)]
+    until: Option<String>,
+
+    #[arg(long)]
+    min_duration_ms: Option<i64>,
+}
+
+impl SearchFilterArgs {
+    fn into_filters(self, now_unix: i64) -> Result<storage::SearchFilters> {
+        let since_unix = normalize_opt_string(self.since)
+            .map(|v| parse_time_bound(&v, now_unix).context("invalid --since"))
+            .transpose()?;
+        let until_unix = normalize_opt_string(self.until)
+            .map(|v| parse_time_bound(&v, now_unix).context("invalid --until"))
+            .transpose()?;
+
+        Ok(storage::SearchFilters {
+            cwd: normalize_opt_string(self.cwd),
+            cwd_prefix: normalize_opt_string(self.cwd_prefix),
+            exit_code: self.exit_code,
+            failed_only: self.failed_only,
+            hostname: normalize_opt_string(self.hostname),
+            device_id: normalize_opt_string(self.device_id),
+            shell: normalize_opt_string(self.shell),
+            session_id: normalize_opt_string(self.session_id),
+            since_unix,
+            until_unix,
+            min_duration_ms: self.min_duration_ms,
+        })
+    }
+}
+
+/// `--since`/`--until` 값을 unix seconds로 변환한다.
+fn parse_time_bound(value: &str, now_unix: i64) -> Result<i64> {
+    let value = value.trim();
+
+    if let Ok(unix) = value.parse::<i64>() {
+        return Ok(unix);
+    }
+
+    let unit_sec = match value.chars().last() {
+        Some('s') => Some(1),
+        Some('m') => Some(60),
+        Some('h') => Some(3_600),
+        Some('d') => Some(86_400),
+        Some('w') => Some(7 * 86_400),
+        _ => None,
+    };
+    if let Some(unit_sec) = unit_sec {
+        let num = &value[..value.len() - 1];
+        if !num.is_empty() && num.chars().all(|c| c.is_ascii_digit()) {
+            let n: i64 = num.parse().context("parse relative time")?;
+            return n
+                .checked_mul(unit_sec)
+                .and_then(|offset| now_unix.checked_sub(offset))
+                .context("relative time is too large");
+        }
+    }
+
+    let parts: Vec<&str> = value.split('-').collect();
+    if let [y, m, d] = parts.as_slice() {
+        let year: i32 = y.parse().context("parse year")?;
+        let month: u8 = m.parse().context("parse month")?;
+        let day: u8 = d.parse().context("parse day")?;
+        let month = time::Month::try_from(month).context("invalid month")?;
+        let date = time::Date::from_calendar_date(year, month, day).context("invalid date")?;
+        return Ok(date.midnight().assume_utc().unix_timestamp());
+    }
+
+    anyhow::bail!(
+        "unsupported time value: {value:?} (expected unix seconds, YYYY-MM-DD, or 30m/12h/7d/2w)"
+    )
+}
+
 #[derive(Debug, Clone)]
 struct InitArgs {
     force: bool,
@@ -745,6 +1509,212 @@ fn run_init(args: InitArgs, cfg: &config::FileConfig, db_path: &str) -> Result<(
     Ok(())
 }
 
+fn run_pair_invite(
+    device_id: &str,
+    user_id: Option<String>,
+    ttl_sec: u64,
+    cfg: &config::FileConfig,
+    db_path: &str,
+) -> Result<()> {
+    let device_id = device_id.trim();
+    if device_id.is_empty() {
+        anyhow::bail!("--device-id is empty");
+    }
+    let user_id = normalize_opt_string(user_id).unwrap_or_else(|| resolve_user_id(cfg));
+    let inviter = resolve_p2p_identity(None, cfg)?;
+    let psk = resolve_swarm_psk(None, cfg)?;
+    let content_key = config::load_content_key(&resolve_content_key_path(None, cfg))?;
+
+    let invitation = pair::Invitation {
+        invite_id: uuid::Uuid::new_v4().to_string(),
+        user_id,
+        device_id: device_id.to_string(),
+        inviter_peer_id: String::new(),
+        inviter_device_id: resolve_device_id(cfg),
+        inviter_public_key: String::new(),
+        swarm_key: psk.to_string(),
+        content_key: content_key.map(|k| k.to_hex()),
+        trackers: resolve_trackers(Vec::new(), cfg)?,
+        tracker_token: resolve_tracker_token(None, cfg)?,
+        relay_addr: resolve_relay_addr(None, cfg)?.map(|a| a.to_string()),
+        expires_at_unix: time::OffsetDateTime::now_utc()
+            .unix_timestamp()
+            .saturating_add(ttl_sec.clamp(1, i64::MAX as u64) as i64),
+    };
+    // 수락 코드는 합류하는 쪽이 만들므로, 무엇을 초대했는지는 이 디바이스가 기억해 둔다.
+    storage::LocalStore::open(db_path)?.insert_pair_invite(&storage::PairInvite {
+        invite_id: invitation.invite_id.clone(),
+        user_id: invitation.user_id.clone(),
+        device_id: invitation.device_id.clone(),
+        expires_at_unix: invitation.expires_at_unix,
+    })?;
+    let code = pair::sign_invitation(&inviter, invitation)?;
+
+    // 표준출력에는 코드만 낸다(`| qrencode` 등으로 넘길 수 있게).
+    println!("{code}");
+    eprintln!(
+        "pair invite: device_id={device_id} expires_in={}s",
+        ttl_sec.max(1)
+    );
+    eprintln!("inviter peer id: {}", inviter.public().to_peer_id());
+    eprintln!(
+        "next: 새 디바이스에서 `rr pair join '<code>'`, 출력된 accept code로 여기서 `rr pair accept '<accept code>'`"
+    );
+    eprintln!("note: 코드에 swarm key가 들어 있으므로 신뢰할 수 있는 경로로만 전달한다.");
+    Ok(())
+}
+
+fn run_pair_join(code: &str, force: bool, cfg: &config::FileConfig, db_path: &str) -> Result<()> {
+    let invitation =
+        pair::verify_invitation(code, time::OffsetDateTime::now_utc().unix_timestamp())?;
+    let psk = invitation.swarm_psk()?;
+    let content_key = invitation
+        .content_key
+        .as_deref()
+        .map(content_key::ContentKey::parse)
+        .transpose()
+        .context("parse invitation content key")?;
+
+    let swarm_key_path = resolve_swarm_key_path(None, cfg);
+    let content_key_path = resolve_content_key_path(None, cfg);
+    if !force {
+        let mut targets = vec![config::DEFAULT_CONFIG_PATH, swarm_key_path.as_str()];
+        if content_key.is_some() {
+            targets.push(content_key_path.as_str());
+        }
+        for path in targets {
+            let abs = config::expand_home_path(path)?;
+            if std::fs::metadata(&abs).is_ok() {
+                anyhow::bail!(
+                    "{} already exists (use --force to overwrite)",
+                    abs.display()
+                );
+            }
+        }
+    }
+
+    let args = InitArgs {
+        force: true,
+        user_id: Some(invitation.user_id.clone()),
+        device_id: Some(invitation.device_id.clone()),
+        trackers: invitation.trackers.clone(),
+        relay: invitation.relay_addr.clone(),
+        tracker_token: invitation.tracker_token.clone(),
+    };
+    let rendered = render_config_toml(&args, cfg, db_path)?;
+    let cfg_path = config::expand_home_path(config::DEFAULT_CONFIG_PATH)?;
+    if let Some(parent) = cfg_path.parent()
+        && !parent.as_os_str().is_empty()
+    {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("create dir: {}", parent.display()))?;
+    }
+    std::fs::write(&cfg_path, rendered)
+        .with_context(|| format!("write config: {}", cfg_path.display()))?;
+    restrict_permissions_0600(&cfg_path)?;
+    println!("wrote config: {}", cfg_path.display());
+
+    config::write_swarm_key(&swarm_key_path, &psk)?;
+    println!("swarm key fingerprint: {}", psk.fingerprint());
+    // identity 키는 이 디바이스에서만 만든다(있으면 그대로 쓴다). 초대한 쪽에는 공개키만 돌려준다.
+    let identity = resolve_p2p_identity(None, cfg)?;
+    println!("p2p peer id: {}", identity.public().to_peer_id());
+    if let Some(key) = content_key.as_ref() {
+        config::write_content_key(&content_key_path, key)?;
+        println!("content key fingerprint: {}", key.fingerprint());
+    }
+
+    // 허용 목록이 비어 있어도 등록해 둔다(나중에 목록을 켜도 초대한 디바이스와는 계속 동기화된다).
+    let store = storage::LocalStore::open(db_path)?;
+    store.upsert_peer_acl(&storage::PeerAcl {
+        peer_id: invitation.inviter_peer_id.clone(),
+        user_id: invitation.user_id.clone(),
+        device_id: invitation.inviter_device_id.clone(),
+        added_at_unix: time::OffsetDateTime::now_utc().unix_timestamp(),
+    })?;
+    println!(
+        "peer-acl: registered {} as {}",
+        invitation.inviter_peer_id, invitation.inviter_device_id
+    );
+    if store.count_peer_acl()? == 1 {
+        println!("note: {PEER_ACL_ENABLED_NOTE}");
+    }
+
+    let acceptance = pair::sign_acceptance(
+        &identity,
+        pair::Acceptance {
+            invite_id: invitation.invite_id.clone(),
+            user_id: invitation.user_id.clone(),
+            device_id: invitation.device_id.clone(),
+            peer_id: String::new(),
+            public_key: String::new(),
+            inviter_peer_id: invitation.inviter_peer_id.clone(),
+            expires_at_unix: invitation.expires_at_unix,
+        },
+    )?;
+
+    println!(
+        "pair join: user_id={} device_id={} inviter={} ({})",
+        invitation.user_id,
+        invitation.device_id,
+        invitation.inviter_device_id,
+        invitation.inviter_peer_id
+    );
+    println!("accept code:");
+    println!("{acceptance}");
+    println!("next:");
+    println!(
+        "- 초대한 디바이스({})에서: rr pair accept '<accept code>'",
+        invitation.inviter_device_id
+    );
+    println!("- 설정 확인: rr doctor");
+    println!("- p2p 동기화: rr p2p-sync --watch --push");
+    Ok(())
+}
+
+fn run_pair_accept(code: &str, cfg: &config::FileConfig, db_path: &str) -> Result<()> {
+    let inviter = resolve_p2p_identity(None, cfg)?;
+    let now = time::OffsetDateTime::now_utc().unix_timestamp();
+    let acceptance =
+        pair::verify_acceptance(code, &inviter.public().to_peer_id().to_string(), now)?;
+
+    // user_id/device_id는 합류한 쪽이 적은 값이다. 이 디바이스가 발급한 초대와 맞을 때만 등록하고,
+    // 초대는 결과와 상관없이 소모한다.
+    let store = storage::LocalStore::open(db_path)?;
+    let Some(invite) = store.take_pair_invite(&acceptance.invite_id, now)? else {
+        anyhow::bail!(
+            "no pending invitation {} (unknown, expired, or already accepted)",
+            acceptance.invite_id
+        );
+    };
+    if invite.user_id != acceptance.user_id || invite.device_id != acceptance.device_id {
+        anyhow::bail!(
+            "acceptance is for user_id={} device_id={}, but the invitation was for user_id={} device_id={}",
+            acceptance.user_id,
+            acceptance.device_id,
+            invite.user_id,
+            invite.device_id
+        );
+    }
+    store.upsert_peer_acl(&storage::PeerAcl {
+        peer_id: acceptance.peer_id.clone(),
+        user_id: invite.user_id.clone(),
+        device_id: invite.device_id.clone(),
+        added_at_unix: now,
+    })?;
+    println!(
+        "peer-acl: registered {} as {} (user_id={})",
+        acceptance.peer_id, invite.device_id, invite.user_id
+    );
+    if store.count_peer_acl()? == 1 {
+        println!("note: {PEER_ACL_ENABLED_NOTE}");
+    }
+    Ok(())
+}
+
+/// 페어링으로 허용 목록의 첫 항목이 생겼을 때 알린다. 그때부터 목록에 없는 피어는 거부된다.
+const PEER_ACL_ENABLED_NOTE: &str = "peer-acl is now enabled; p2p-serve accepts only listed peers (add other devices with `rr peer-acl add`)";
+
 fn render_config_toml(args: &InitArgs, cfg: &config::FileConfig, db_path: &str) -> Result<String> {
     // 값 결정(가능하면 기존 config/입력값을 반영).
     let user_id = normalize_opt_string(args.user_id.clone())
@@ -753,8 +1723,7 @@ fn render_config_toml(args: &InitArgs, cfg: &config::FileConfig, db_path: &str)
 
     let device_id = normalize_opt_string(args.device_id.clone())
         .or_else(|| normalize_opt_string(cfg.device_id.clone()))
-        .or_else(|| env_nonempty("HOSTNAME"))
-        .or_else(|| env_nonempty("HOST"));
+        .or_else(default_hostname);
 
     let trackers = resolve_trackers(args.trackers.clone(), cfg)?;
 
@@ -821,6 +1790,9 @@ fn render_config_toml(args: &InitArgs, cfg: &config::FileConfig, db_path: &str)
     out.push('\n');
 
     out.push_str(&format!("swarm_key_path = {swarm_key_path:?}\n"));
+    out.push_str(
+        "# content_key_path = \"~/.config/rustory/content.key\" # optional, cmd/cwd E2E encryption\n",
+    );
     out.push_str(&format!(
         "p2p_identity_key_path = {p2p_identity_key_path:?}\n"
     ));
@@ -835,7 +1807,13 @@ fn render_config_toml(args: &InitArgs, cfg: &config::FileConfig, db_path: &str)
     out.push_str("# p2p_request_timeout_cap_sec = 30 # optional\n");
     out.push_str("# p2p_request_backoff_base_ms = 200 # optional\n");
     out.push_str("# search_limit_default = 100000 # optional\n");
+    out.push_str("# search_backend = \"builtin\" # optional: builtin|fzf\n");
+    out.push_str("# search_filter_mode = \"global\" # optional: global|host|directory|session\n");
     out.push_str("# record_ignore_regex = \"(?i)(password|token|secret)\" # optional\n");
+    out.push_str("# redact_builtin = true # optional: built-in secret detectors\n");
+    out.push_str(
+        "# redact_patterns = [\"corp_[A-Za-z0-9]{32}\", \"pin=(?P<secret>\\\\d+)\"] # optional\n",
+    );
 
     Ok(out)
 }
@@ -852,125 +1830,8 @@ fn restrict_permissions_0600(path: &std::path::Path) -> Result<()> {
     Ok(())
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
-struct AsyncUploadRuntimeSettings {
-    enabled: bool,
-    interval_sec: u64,
-    limit: usize,
-    marker_path: std::path::PathBuf,
-    last_trigger_unix: Option<i64>,
-}
-
-#[derive(Debug, Clone, PartialEq, Eq)]
-struct AutoPruneRuntimeSettings {
-    enabled: bool,
-    older_than_days: u64,
-    interval_sec: u64,
-    keep_recent: usize,
-    marker_path: std::path::PathBuf,
-    last_trigger_unix: Option<i64>,
-}
-
-#[derive(Debug, Clone, serde::Serialize, PartialEq, Eq)]
-struct AsyncUploadDoctorReport {
-    enabled: bool,
-    interval_sec: u64,
-    limit: usize,
-    marker_path: std::path::PathBuf,
-    last_trigger_unix: Option<i64>,
-    next_due_in_sec: u64,
-}
-
-#[derive(Debug, Clone, serde::Serialize, PartialEq, Eq)]
-struct AutoPruneDoctorReport {
-    enabled: bool,
-    older_than_days: u64,
-    interval_sec: u64,
-    keep_recent: usize,
-    marker_path: std::path::PathBuf,
-    last_trigger_unix: Option<i64>,
-    next_due_in_sec: u64,
-}
-
-fn load_async_upload_runtime_settings() -> Result<AsyncUploadRuntimeSettings> {
-    let marker_path_raw = resolve_async_upload_marker_path();
-    let marker_path = config::expand_home_path(&marker_path_raw)
-        .with_context(|| format!("expand async upload marker path: {marker_path_raw}"))?;
-
-    Ok(AsyncUploadRuntimeSettings {
-        enabled: resolve_async_upload_enabled()?,
-        interval_sec: resolve_async_upload_interval_sec()?,
-        limit: resolve_async_upload_limit()?,
-        last_trigger_unix: read_rate_limit_marker(&marker_path)?,
-        marker_path,
-    })
-}
-
-fn summarize_async_upload_runtime(
-    settings: AsyncUploadRuntimeSettings,
-    now_unix: i64,
-) -> AsyncUploadDoctorReport {
-    AsyncUploadDoctorReport {
-        enabled: settings.enabled,
-        interval_sec: settings.interval_sec,
-        limit: settings.limit,
-        marker_path: settings.marker_path,
-        last_trigger_unix: settings.last_trigger_unix,
-        next_due_in_sec: compute_next_due_in_sec(
-            now_unix,
-            settings.last_trigger_unix,
-            settings.interval_sec,
-        ),
-    }
-}
-
-fn load_auto_prune_runtime_settings() -> Result<AutoPruneRuntimeSettings> {
-    let marker_path_raw = resolve_auto_prune_marker_path();
-    let marker_path = config::expand_home_path(&marker_path_raw)
-        .with_context(|| format!("expand auto prune marker path: {marker_path_raw}"))?;
-
-    Ok(AutoPruneRuntimeSettings {
-        enabled: resolve_auto_prune_enabled()?,
-        older_than_days: resolve_auto_prune_days()?,
-        interval_sec: resolve_auto_prune_interval_sec()?,
-        keep_recent: resolve_auto_prune_keep_recent()?,
-        last_trigger_unix: read_rate_limit_marker(&marker_path)?,
-        marker_path,
-    })
-}
-
-fn summarize_auto_prune_runtime(
-    settings: AutoPruneRuntimeSettings,
-    now_unix: i64,
-) -> AutoPruneDoctorReport {
-    AutoPruneDoctorReport {
-        enabled: settings.enabled,
-        older_than_days: settings.older_than_days,
-        interval_sec: settings.interval_sec,
-        keep_recent: settings.keep_recent,
-        marker_path: settings.marker_path,
-        last_trigger_unix: settings.last_trigger_unix,
-        next_due_in_sec: compute_next_due_in_sec(
-            now_unix,
-            settings.last_trigger_unix,
-            settings.interval_sec,
-        ),
-    }
-}
-
-fn compute_next_due_in_sec(
-    now_unix: i64,
-    last_trigger_unix: Option<i64>,
-    interval_sec: u64,
-) -> u64 {
-    let Some(last) = last_trigger_unix else {
-        return 0;
-    };
-
-    let interval_i64 = i64::try_from(interval_sec).unwrap_or(i64::MAX);
-    let elapsed_i64 = now_unix.saturating_sub(last).max(0);
-    let remaining_i64 = interval_i64.saturating_sub(elapsed_i64).max(0);
-    u64::try_from(remaining_i64).unwrap_or(0)
+fn compute_next_due_in_sec(now_unix: i64, next_due_unix: i64) -> u64 {
+    u64::try_from(next_due_unix.saturating_sub(now_unix)).unwrap_or(0)
 }
 
 #[derive(Debug, Clone, serde::Serialize, PartialEq, Eq)]
@@ -982,9 +1843,11 @@ struct DoctorReport {
     device_id: String,
     p2p_request_retry: DoctorP2pRequestRetryReport,
     record_ignore_regex: DoctorRecordIgnoreRegexReport,
-    async_upload: DoctorAsyncUploadStatusReport,
-    auto_prune: DoctorAutoPruneStatusReport,
+    redact: DoctorRedactReport,
+    search_backend: DoctorSearchBackendReport,
+    scheduler: DoctorSchedulerReport,
     swarm_key: DoctorKeyStatusReport,
+    content_key: DoctorKeyStatusReport,
     p2p_identity_key: DoctorKeyStatusReport,
     relay_identity_key: DoctorKeyStatusReport,
     relay_addr: DoctorRelayAddrReport,
@@ -1008,25 +1871,25 @@ struct DoctorRecordIgnoreRegexReport {
 }
 
 #[derive(Debug, Clone, serde::Serialize, PartialEq, Eq)]
-struct DoctorAsyncUploadStatusReport {
-    enabled: Option<bool>,
-    interval_sec: Option<u64>,
-    limit: Option<usize>,
-    marker_path: Option<String>,
-    last_trigger_unix: Option<i64>,
-    next_due_in_sec: Option<u64>,
+struct DoctorRedactReport {
+    builtin: Option<bool>,
+    patterns: usize,
+    valid: bool,
     error: Option<String>,
 }
 
 #[derive(Debug, Clone, serde::Serialize, PartialEq, Eq)]
-struct DoctorAutoPruneStatusReport {
-    enabled: Option<bool>,
-    older_than_days: Option<u64>,
-    interval_sec: Option<u64>,
-    keep_recent: Option<usize>,
-    marker_path: Option<String>,
-    last_trigger_unix: Option<i64>,
-    next_due_in_sec: Option<u64>,
+struct DoctorSearchBackendReport {
+    value: Option<String>,
+    error: Option<String>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, PartialEq, Eq)]
+struct DoctorSchedulerReport {
+    /// 작업은 `rr agent` 안에서만 돈다.
+    agent_running: bool,
+    agent_socket_path: String,
+    jobs: Vec<SchedulerJobReport>,
     error: Option<String>,
 }
 
@@ -1105,56 +1968,21 @@ fn build_doctor_report(cfg: &config::FileConfig, db_path: &str) -> Result<Doctor
         },
     };
 
-    let async_upload = match load_async_upload_runtime_settings() {
-        Ok(settings) => {
-            let report = summarize_async_upload_runtime(settings, now_unix);
-            DoctorAsyncUploadStatusReport {
-                enabled: Some(report.enabled),
-                interval_sec: Some(report.interval_sec),
-                limit: Some(report.limit),
-                marker_path: Some(report.marker_path.display().to_string()),
-                last_trigger_unix: report.last_trigger_unix,
-                next_due_in_sec: Some(report.next_due_in_sec),
-                error: None,
-            }
-        }
-        Err(err) => DoctorAsyncUploadStatusReport {
-            enabled: None,
-            interval_sec: None,
-            limit: None,
-            marker_path: None,
-            last_trigger_unix: None,
-            next_due_in_sec: None,
-            error: Some(format!("{err:#}")),
-        },
-    };
+    let redact = summarize_redact_config(cfg);
 
-    let auto_prune = match load_auto_prune_runtime_settings() {
-        Ok(settings) => {
-            let report = summarize_auto_prune_runtime(settings, now_unix);
-            DoctorAutoPruneStatusReport {
-                enabled: Some(report.enabled),
-                older_than_days: Some(report.older_than_days),
-                interval_sec: Some(report.interval_sec),
-                keep_recent: Some(report.keep_recent),
-                marker_path: Some(report.marker_path.display().to_string()),
-                last_trigger_unix: report.last_trigger_unix,
-                next_due_in_sec: Some(report.next_due_in_sec),
-                error: None,
-            }
-        }
-        Err(err) => DoctorAutoPruneStatusReport {
-            enabled: None,
-            older_than_days: None,
-            interval_sec: None,
-            keep_recent: None,
-            marker_path: None,
-            last_trigger_unix: None,
-            next_due_in_sec: None,
+    let search_backend = match resolve_search_backend(None, cfg) {
+        Ok(backend) => DoctorSearchBackendReport {
+            value: Some(backend.as_str().to_string()),
+            error: None,
+        },
+        Err(err) => DoctorSearchBackendReport {
+            value: None,
             error: Some(format!("{err:#}")),
         },
     };
 
+    let scheduler = build_doctor_scheduler_report(cfg, db_path, now_unix);
+
     let swarm_key_path = resolve_swarm_key_path(None, cfg);
     let (swarm_value, swarm_load_error) = match config::load_swarm_key(&swarm_key_path) {
         Ok(value) => (
@@ -1165,6 +1993,14 @@ fn build_doctor_report(cfg: &config::FileConfig, db_path: &str) -> Result<Doctor
     };
     let swarm_key = build_key_status_report(&swarm_key_path, swarm_value, swarm_load_error)?;
 
+    let content_key_path = resolve_content_key_path(None, cfg);
+    let (content_value, content_load_error) = match config::load_content_key(&content_key_path) {
+        Ok(value) => (value.map(|key| key.fingerprint()), None::<String>),
+        Err(err) => (None, Some(format!("{err:#}"))),
+    };
+    let content_key =
+        build_key_status_report(&content_key_path, content_value, content_load_error)?;
+
     let p2p_identity_key_path = resolve_p2p_identity_key_path(None, cfg);
     let (p2p_identity_value, p2p_identity_load_error) =
         match config::load_identity_keypair(&p2p_identity_key_path) {
@@ -1222,9 +2058,11 @@ fn build_doctor_report(cfg: &config::FileConfig, db_path: &str) -> Result<Doctor
         device_id,
         p2p_request_retry,
         record_ignore_regex,
-        async_upload,
-        auto_prune,
+        redact,
+        search_backend,
+        scheduler,
         swarm_key,
+        content_key,
         p2p_identity_key,
         relay_identity_key,
         relay_addr,
@@ -1232,6 +2070,48 @@ fn build_doctor_report(cfg: &config::FileConfig, db_path: &str) -> Result<Doctor
     })
 }
 
+fn build_doctor_scheduler_report(
+    cfg: &config::FileConfig,
+    db_path: &str,
+    now_unix: i64,
+) -> DoctorSchedulerReport {
+    let socket_path = config::expand_home_path(&resolve_agent_socket_path(None, cfg))
+        .map(|p| p.display().to_string())
+        .unwrap_or_else(|_| resolve_agent_socket_path(None, cfg));
+    let agent_running = matches!(
+        agent::request(
+            std::path::Path::new(&socket_path),
+            &agent::Request::Status,
+            AGENT_RECORD_TIMEOUT,
+        ),
+        Ok(Some(agent::Response::Status(_)))
+    );
+
+    let result = resolve_scheduler_settings(cfg).and_then(|settings| {
+        let states = load_job_states_if_db_exists(db_path)?;
+        Ok(build_scheduler_job_reports(&settings, &states, now_unix))
+    });
+    let (jobs, error) = match result {
+        Ok(jobs) => (jobs, None),
+        Err(err) => (Vec::new(), Some(format!("{err:#}"))),
+    };
+
+    DoctorSchedulerReport {
+        agent_running,
+        agent_socket_path: socket_path,
+        jobs,
+        error,
+    }
+}
+
+/// doctor는 DB를 새로 만들지 않는다. 아직 없으면 작업 기록도 없는 것이다.
+fn load_job_states_if_db_exists(db_path: &str) -> Result<Vec<storage::JobState>> {
+    if db_path == ":memory:" || !config::expand_home_path(db_path)?.exists() {
+        return Ok(Vec::new());
+    }
+    storage::LocalStore::open(db_path)?.list_job_states()
+}
+
 fn build_key_status_report(
     path: &str,
     value: Option<String>,
@@ -1329,45 +2209,38 @@ fn run_doctor(cfg: &config::FileConfig, db_path: &str, json: bool) -> Result<()>
         },
         None => println!("record ignore regex: (none)"),
     }
+    let redact = summarize_redact_config(cfg);
+    match (redact.builtin, redact.error) {
+        (Some(builtin), None) => println!(
+            "redact: builtin={} patterns={}",
+            if builtin { "on" } else { "off" },
+            redact.patterns
+        ),
+        (_, err) => println!(
+            "redact: invalid: {} (skipping record for safety)",
+            err.unwrap_or_default()
+        ),
+    }
+    match resolve_search_backend(None, cfg) {
+        Ok(backend) => println!("search backend: {}", backend.as_str()),
+        Err(err) => println!("search backend: invalid: {err:#}"),
+    }
     let now_unix = time::OffsetDateTime::now_utc().unix_timestamp();
-    match load_async_upload_runtime_settings() {
-        Ok(settings) => {
-            let report = summarize_async_upload_runtime(settings, now_unix);
-            let last_trigger = report
-                .last_trigger_unix
-                .map(|value| value.to_string())
-                .unwrap_or_else(|| "-".to_string());
-            println!(
-                "async upload: enabled={} interval_sec={} limit={} marker_path={} last_trigger_unix={} next_due_in_sec={}",
-                report.enabled,
-                report.interval_sec,
-                report.limit,
-                report.marker_path.display(),
-                last_trigger,
-                report.next_due_in_sec,
-            );
-        }
-        Err(err) => println!("async upload: invalid: {err:#}"),
+    let scheduler = build_doctor_scheduler_report(cfg, db_path, now_unix);
+    println!(
+        "agent: running={} socket={}",
+        scheduler.agent_running, scheduler.agent_socket_path
+    );
+    match scheduler.error {
+        Some(err) => println!("scheduler: invalid: {err}"),
+        None => scheduler.jobs.iter().for_each(print_scheduler_job_report),
     }
-    match load_auto_prune_runtime_settings() {
-        Ok(settings) => {
-            let report = summarize_auto_prune_runtime(settings, now_unix);
-            let last_trigger = report
-                .last_trigger_unix
-                .map(|value| value.to_string())
-                .unwrap_or_else(|| "-".to_string());
+    for key in ["RUSTORY_ASYNC_UPLOAD", "RUSTORY_AUTO_PRUNE"] {
+        if env_nonempty(key).is_some() {
             println!(
-                "auto prune: enabled={} older_than_days={} interval_sec={} keep_recent={} marker_path={} last_trigger_unix={} next_due_in_sec={}",
-                report.enabled,
-                report.older_than_days,
-                report.interval_sec,
-                report.keep_recent,
-                report.marker_path.display(),
-                last_trigger,
-                report.next_due_in_sec,
+                "warn: {key} is no longer used; enable [scheduler.sync]/[scheduler.prune] in config.toml and run `rr agent`"
             );
         }
-        Err(err) => println!("auto prune: invalid: {err:#}"),
     }
 
     let swarm_key_path = resolve_swarm_key_path(None, cfg);
@@ -1385,8 +2258,20 @@ fn run_doctor(cfg: &config::FileConfig, db_path: &str, json: bool) -> Result<()>
         swarm_load_error.as_deref(),
     )?;
 
-    let p2p_identity_key_path = resolve_p2p_identity_key_path(None, cfg);
-    let (p2p_peer_id, p2p_load_error) = match config::load_identity_keypair(&p2p_identity_key_path)
+    let content_key_path = resolve_content_key_path(None, cfg);
+    let (content_fp, content_load_error) = match config::load_content_key(&content_key_path) {
+        Ok(value) => (value.map(|key| key.fingerprint()), None::<String>),
+        Err(err) => (None, Some(format!("{err:#}"))),
+    };
+    print_key_status(
+        "content key",
+        &content_key_path,
+        content_fp.as_deref(),
+        content_load_error.as_deref(),
+    )?;
+
+    let p2p_identity_key_path = resolve_p2p_identity_key_path(None, cfg);
+    let (p2p_peer_id, p2p_load_error) = match config::load_identity_keypair(&p2p_identity_key_path)
     {
         Ok(value) => (
             value.map(|key| key.public().to_peer_id().to_string()),
@@ -1554,6 +2439,8 @@ struct SyncStatusReport {
     local_head: i64,
     local_device_id: String,
     peers: Vec<SyncStatusPeerReport>,
+    /// `rr agent` 스케줄러 작업 상태.
+    scheduler: Vec<SchedulerJobReport>,
     #[serde(skip_serializing_if = "Option::is_none")]
     tracker_status: Option<Vec<SyncStatusTrackerReport>>,
 }
@@ -1566,6 +2453,7 @@ fn build_sync_status_report(
     store: &storage::LocalStore,
     local_device_id: &str,
     peer_filter: Option<&str>,
+    scheduler_settings: &SchedulerSettings,
     tracker_status: Option<Vec<SyncStatusTrackerReport>>,
 ) -> Result<SyncStatusReport> {
     let local_head = store.latest_ingest_seq()?;
@@ -1592,10 +2480,14 @@ fn build_sync_status_report(
         });
     }
 
+    let scheduler =
+        build_scheduler_job_reports(scheduler_settings, &store.list_job_states()?, now_unix);
+
     Ok(SyncStatusReport {
         local_head,
         local_device_id: local_device_id.to_string(),
         peers,
+        scheduler,
         tracker_status,
     })
 }
@@ -1630,6 +2522,31 @@ fn default_cwd() -> String {
         .unwrap_or_else(|| ".".to_string())
 }
 
+/// 셸이 export하지 않는 경우가 많은 `HOSTNAME`/`HOST` env 대신 OS에 직접 묻는다.
+/// bash `$HOSTNAME`, zsh `$HOST`, fish `$hostname`도 같은 값(gethostname)이다.
+fn default_hostname() -> Option<String> {
+    system_hostname()
+        .or_else(|| env_nonempty("HOSTNAME"))
+        .or_else(|| env_nonempty("HOST"))
+}
+
+#[cfg(unix)]
+fn system_hostname() -> Option<String> {
+    let mut buf = [0u8; 256];
+    // SAFETY: 버퍼 길이를 함께 넘기고, 결과는 NUL까지만 읽는다.
+    let rc = unsafe { libc::gethostname(buf.as_mut_ptr().cast(), buf.len()) };
+    if rc != 0 {
+        return None;
+    }
+    let len = buf.iter().position(|&b| b == 0).unwrap_or(buf.len());
+    normalize_opt_string(Some(String::from_utf8_lossy(&buf[..len]).into_owned()))
+}
+
+#[cfg(not(unix))]
+fn system_hostname() -> Option<String> {
+    env_nonempty("COMPUTERNAME")
+}
+
 fn default_shell() -> Option<String> {
     let shell = std::env::var("SHELL").ok()?;
     let name = std::path::Path::new(&shell)
@@ -1652,172 +2569,6 @@ fn env_nonempty(key: &str) -> Option<String> {
     normalize_opt_string(std::env::var(key).ok())
 }
 
-fn maybe_spawn_async_upload(db_path: &str) -> Result<()> {
-    if !resolve_async_upload_enabled()? {
-        return Ok(());
-    }
-
-    let min_interval_sec = resolve_async_upload_interval_sec()?;
-    let limit = resolve_async_upload_limit()?;
-    let marker_path = config::expand_home_path(&resolve_async_upload_marker_path())?;
-
-    let now_unix = time::OffsetDateTime::now_utc().unix_timestamp();
-    let last_trigger_unix = read_rate_limit_marker(&marker_path)?;
-    if !should_trigger_interval(now_unix, last_trigger_unix, min_interval_sec) {
-        return Ok(());
-    }
-    write_rate_limit_marker(&marker_path, now_unix)?;
-
-    let exe = std::env::current_exe().context("resolve current executable for async upload")?;
-    let mut cmd = std::process::Command::new(exe);
-    cmd.arg("--db-path")
-        .arg(db_path)
-        .arg("p2p-sync")
-        .arg("--push")
-        .arg("--limit")
-        .arg(limit.to_string())
-        .env("RUSTORY_ASYNC_UPLOAD", "0")
-        .stdin(std::process::Stdio::null())
-        .stdout(std::process::Stdio::null())
-        .stderr(std::process::Stdio::null());
-    cmd.spawn().context("spawn async upload p2p-sync")?;
-
-    Ok(())
-}
-
-fn maybe_run_auto_prune(store: &storage::LocalStore) -> Result<()> {
-    if !resolve_auto_prune_enabled()? {
-        return Ok(());
-    }
-
-    let older_than_days = resolve_auto_prune_days()?;
-    let keep_recent = resolve_auto_prune_keep_recent()?;
-    let min_interval_sec = resolve_auto_prune_interval_sec()?;
-    let marker_path = config::expand_home_path(&resolve_auto_prune_marker_path())?;
-
-    let now_unix = time::OffsetDateTime::now_utc().unix_timestamp();
-    let last_trigger_unix = read_rate_limit_marker(&marker_path)?;
-    if !should_trigger_interval(now_unix, last_trigger_unix, min_interval_sec) {
-        return Ok(());
-    }
-
-    let cutoff_unix = compute_prune_cutoff_unix(now_unix, older_than_days)?;
-    let stats = store.prune_entries_older_than(cutoff_unix, keep_recent, false)?;
-    write_rate_limit_marker(&marker_path, now_unix)?;
-
-    if stats.deleted > 0 {
-        eprintln!(
-            "info: auto prune deleted={} older_than_days={} keep_recent={} cutoff_unix={}",
-            stats.deleted, older_than_days, keep_recent, cutoff_unix
-        );
-    }
-
-    Ok(())
-}
-
-fn resolve_async_upload_enabled() -> Result<bool> {
-    let Some(raw) = env_nonempty("RUSTORY_ASYNC_UPLOAD") else {
-        return Ok(false);
-    };
-    parse_env_bool(&raw, "RUSTORY_ASYNC_UPLOAD")
-}
-
-fn resolve_async_upload_interval_sec() -> Result<u64> {
-    let Some(raw) = env_nonempty("RUSTORY_ASYNC_UPLOAD_INTERVAL_SEC") else {
-        return Ok(DEFAULT_ASYNC_UPLOAD_INTERVAL_SEC);
-    };
-
-    let parsed: u64 = raw.parse().map_err(|e| {
-        anyhow::anyhow!(
-            "invalid RUSTORY_ASYNC_UPLOAD_INTERVAL_SEC={:?}: {e}",
-            raw.trim()
-        )
-    })?;
-    if parsed == 0 {
-        anyhow::bail!("RUSTORY_ASYNC_UPLOAD_INTERVAL_SEC must be >= 1");
-    }
-
-    Ok(parsed)
-}
-
-fn resolve_async_upload_limit() -> Result<usize> {
-    let Some(raw) = env_nonempty("RUSTORY_ASYNC_UPLOAD_LIMIT") else {
-        return Ok(DEFAULT_ASYNC_UPLOAD_LIMIT);
-    };
-
-    let parsed: usize = raw
-        .parse()
-        .map_err(|e| anyhow::anyhow!("invalid RUSTORY_ASYNC_UPLOAD_LIMIT={:?}: {e}", raw.trim()))?;
-    if parsed == 0 {
-        anyhow::bail!("RUSTORY_ASYNC_UPLOAD_LIMIT must be >= 1");
-    }
-
-    Ok(parsed)
-}
-
-fn resolve_async_upload_marker_path() -> String {
-    env_nonempty("RUSTORY_ASYNC_UPLOAD_MARKER_PATH")
-        .unwrap_or_else(|| DEFAULT_ASYNC_UPLOAD_MARKER_PATH.to_string())
-}
-
-fn resolve_auto_prune_enabled() -> Result<bool> {
-    let Some(raw) = env_nonempty("RUSTORY_AUTO_PRUNE") else {
-        return Ok(false);
-    };
-    parse_env_bool(&raw, "RUSTORY_AUTO_PRUNE")
-}
-
-fn resolve_auto_prune_days() -> Result<u64> {
-    let Some(raw) = env_nonempty("RUSTORY_AUTO_PRUNE_DAYS") else {
-        return Ok(DEFAULT_AUTO_PRUNE_DAYS);
-    };
-
-    let parsed: u64 = raw
-        .parse()
-        .map_err(|e| anyhow::anyhow!("invalid RUSTORY_AUTO_PRUNE_DAYS={:?}: {e}", raw.trim()))?;
-    if parsed == 0 {
-        anyhow::bail!("RUSTORY_AUTO_PRUNE_DAYS must be >= 1");
-    }
-
-    Ok(parsed)
-}
-
-fn resolve_auto_prune_interval_sec() -> Result<u64> {
-    let Some(raw) = env_nonempty("RUSTORY_AUTO_PRUNE_INTERVAL_SEC") else {
-        return Ok(DEFAULT_AUTO_PRUNE_INTERVAL_SEC);
-    };
-
-    let parsed: u64 = raw.parse().map_err(|e| {
-        anyhow::anyhow!(
-            "invalid RUSTORY_AUTO_PRUNE_INTERVAL_SEC={:?}: {e}",
-            raw.trim()
-        )
-    })?;
-    if parsed == 0 {
-        anyhow::bail!("RUSTORY_AUTO_PRUNE_INTERVAL_SEC must be >= 1");
-    }
-
-    Ok(parsed)
-}
-
-fn resolve_auto_prune_keep_recent() -> Result<usize> {
-    let Some(raw) = env_nonempty("RUSTORY_AUTO_PRUNE_KEEP_RECENT") else {
-        return Ok(DEFAULT_AUTO_PRUNE_KEEP_RECENT);
-    };
-
-    raw.parse().map_err(|e| {
-        anyhow::anyhow!(
-            "invalid RUSTORY_AUTO_PRUNE_KEEP_RECENT={:?}: {e}",
-            raw.trim()
-        )
-    })
-}
-
-fn resolve_auto_prune_marker_path() -> String {
-    env_nonempty("RUSTORY_AUTO_PRUNE_MARKER_PATH")
-        .unwrap_or_else(|| DEFAULT_AUTO_PRUNE_MARKER_PATH.to_string())
-}
-
 fn parse_env_bool(value: &str, label: &str) -> Result<bool> {
     match value.trim().to_ascii_lowercase().as_str() {
         "1" | "true" | "yes" | "on" => Ok(true),
@@ -1828,50 +2579,6 @@ fn parse_env_bool(value: &str, label: &str) -> Result<bool> {
     }
 }
 
-fn read_rate_limit_marker(path: &std::path::Path) -> Result<Option<i64>> {
-    let content = match std::fs::read_to_string(path) {
-        Ok(content) => content,
-        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
-        Err(err) => {
-            return Err(err).with_context(|| format!("read rate limit marker: {}", path.display()));
-        }
-    };
-
-    let trimmed = content.trim();
-    if trimmed.is_empty() {
-        return Ok(None);
-    }
-
-    let parsed = trimmed
-        .parse::<i64>()
-        .map_err(|e| anyhow::anyhow!("invalid rate limit marker {}: {e}", path.display()))?;
-    Ok(Some(parsed))
-}
-
-fn write_rate_limit_marker(path: &std::path::Path, now_unix: i64) -> Result<()> {
-    if let Some(parent) = path.parent()
-        && !parent.as_os_str().is_empty()
-    {
-        std::fs::create_dir_all(parent)
-            .with_context(|| format!("create rate limit marker dir: {}", parent.display()))?;
-    }
-    std::fs::write(path, format!("{now_unix}\n"))
-        .with_context(|| format!("write rate limit marker: {}", path.display()))?;
-    Ok(())
-}
-
-fn should_trigger_interval(
-    now_unix: i64,
-    last_trigger_unix: Option<i64>,
-    min_interval_sec: u64,
-) -> bool {
-    let min_interval_sec = i64::try_from(min_interval_sec).unwrap_or(i64::MAX);
-    let Some(last) = last_trigger_unix else {
-        return true;
-    };
-    now_unix.saturating_sub(last) >= min_interval_sec
-}
-
 fn resolve_search_limit(cli: Option<usize>, cfg: &config::FileConfig) -> Result<usize> {
     if let Some(v) = cli {
         return Ok(v);
@@ -1891,6 +2598,34 @@ fn resolve_search_limit(cli: Option<usize>, cfg: &config::FileConfig) -> Result<
     Ok(100000)
 }
 
+fn resolve_search_backend(
+    cli: Option<String>,
+    cfg: &config::FileConfig,
+) -> Result<search::SearchBackend> {
+    let value = normalize_opt_string(cli)
+        .or_else(|| env_nonempty("RUSTORY_SEARCH_BACKEND"))
+        .or_else(|| normalize_opt_string(cfg.search_backend.clone()));
+
+    match value {
+        Some(v) => search::SearchBackend::parse(&v),
+        None => Ok(search::SearchBackend::default()),
+    }
+}
+
+fn resolve_search_filter_mode(
+    cli: Option<String>,
+    cfg: &config::FileConfig,
+) -> Result<picker::FilterMode> {
+    let value = normalize_opt_string(cli)
+        .or_else(|| env_nonempty("RUSTORY_SEARCH_FILTER_MODE"))
+        .or_else(|| normalize_opt_string(cfg.search_filter_mode.clone()));
+
+    match value {
+        Some(v) => picker::FilterMode::parse(&v),
+        None => Ok(picker::FilterMode::default()),
+    }
+}
+
 fn compute_prune_cutoff_unix(now_unix: i64, older_than_days: u64) -> Result<i64> {
     if older_than_days == 0 {
         anyhow::bail!("--older-than-days must be >= 1");
@@ -2013,120 +2748,712 @@ fn resolve_swarm_psk(
     config::load_or_generate_swarm_key(&path)
 }
 
-fn resolve_swarm_key_path(cli_path: Option<String>, cfg: &config::FileConfig) -> String {
-    normalize_opt_string(cli_path)
-        .or_else(|| env_nonempty("RUSTORY_SWARM_KEY_PATH"))
-        .or_else(|| normalize_opt_string(cfg.swarm_key_path.clone()))
-        .unwrap_or_else(|| config::DEFAULT_SWARM_KEY_PATH.to_string())
+/// 콘텐츠 암호화는 opt-in이다. passphrase(env)가 있으면 우선하고, 없으면 키 파일이 있을 때만 쓴다.
+fn resolve_content_key(cfg: &config::FileConfig) -> Result<Option<content_key::ContentKey>> {
+    if let Some(passphrase) = env_nonempty("RUSTORY_CONTENT_PASSPHRASE") {
+        return content_key::ContentKey::from_passphrase(&passphrase, &resolve_user_id(cfg))
+            .map(Some);
+    }
+    config::load_content_key(&resolve_content_key_path(None, cfg))
+}
+
+fn resolve_content_key_path(cli_path: Option<String>, cfg: &config::FileConfig) -> String {
+    normalize_opt_string(cli_path)
+        .or_else(|| env_nonempty("RUSTORY_CONTENT_KEY_PATH"))
+        .or_else(|| normalize_opt_string(cfg.content_key_path.clone()))
+        .unwrap_or_else(|| config::DEFAULT_CONTENT_KEY_PATH.to_string())
+}
+
+fn resolve_swarm_key_path(cli_path: Option<String>, cfg: &config::FileConfig) -> String {
+    normalize_opt_string(cli_path)
+        .or_else(|| env_nonempty("RUSTORY_SWARM_KEY_PATH"))
+        .or_else(|| normalize_opt_string(cfg.swarm_key_path.clone()))
+        .unwrap_or_else(|| config::DEFAULT_SWARM_KEY_PATH.to_string())
+}
+
+fn resolve_p2p_identity(
+    cli_path: Option<String>,
+    cfg: &config::FileConfig,
+) -> Result<libp2p::identity::Keypair> {
+    let path = resolve_p2p_identity_key_path(cli_path, cfg);
+    config::load_or_generate_identity_keypair(&path)
+}
+
+fn resolve_p2p_identity_key_path(cli_path: Option<String>, cfg: &config::FileConfig) -> String {
+    normalize_opt_string(cli_path)
+        .or_else(|| env_nonempty("RUSTORY_P2P_IDENTITY_KEY_PATH"))
+        .or_else(|| normalize_opt_string(cfg.p2p_identity_key_path.clone()))
+        .unwrap_or_else(|| config::DEFAULT_P2P_IDENTITY_KEY_PATH.to_string())
+}
+
+fn resolve_relay_identity(
+    cli_path: Option<String>,
+    cfg: &config::FileConfig,
+) -> Result<libp2p::identity::Keypair> {
+    let path = resolve_relay_identity_key_path(cli_path, cfg);
+    config::load_or_generate_identity_keypair(&path)
+}
+
+fn resolve_relay_identity_key_path(cli_path: Option<String>, cfg: &config::FileConfig) -> String {
+    normalize_opt_string(cli_path)
+        .or_else(|| env_nonempty("RUSTORY_RELAY_IDENTITY_KEY_PATH"))
+        .or_else(|| normalize_opt_string(cfg.relay_identity_key_path.clone()))
+        .unwrap_or_else(|| config::DEFAULT_RELAY_IDENTITY_KEY_PATH.to_string())
+}
+
+fn resolve_relay_addr(
+    cli: Option<String>,
+    cfg: &config::FileConfig,
+) -> Result<Option<libp2p::Multiaddr>> {
+    let raw = normalize_opt_string(cli)
+        .or_else(|| env_nonempty("RUSTORY_RELAY_ADDR"))
+        .or_else(|| normalize_opt_string(cfg.relay_addr.clone()));
+
+    let Some(raw) = raw else {
+        return Ok(None);
+    };
+    Ok(Some(raw.parse().context("parse relay_addr")?))
+}
+
+fn resolve_trackers(cli: Vec<String>, cfg: &config::FileConfig) -> Result<Vec<String>> {
+    let raw_list = if !cli.is_empty() {
+        cli
+    } else if let Some(env) = env_nonempty("RUSTORY_TRACKERS") {
+        env.split(',').map(|s| s.to_string()).collect()
+    } else {
+        cfg.trackers.clone().unwrap_or_default()
+    };
+
+    Ok(raw_list
+        .into_iter()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect())
+}
+
+fn resolve_tracker_token(cli: Option<String>, cfg: &config::FileConfig) -> Result<Option<String>> {
+    Ok(normalize_opt_string(cli)
+        .or_else(|| env_nonempty("RUSTORY_TRACKER_TOKEN"))
+        .or_else(|| normalize_opt_string(cfg.tracker_token.clone())))
+}
+
+/// mDNS는 opt-in이다(`--mdns` → env `RUSTORY_P2P_MDNS` → `p2p_mdns` → 기본 끔).
+fn resolve_p2p_mdns(cli: bool, cfg: &config::FileConfig) -> Result<bool> {
+    if cli {
+        return Ok(true);
+    }
+    match env_nonempty("RUSTORY_P2P_MDNS") {
+        Some(raw) => parse_env_bool(&raw, "RUSTORY_P2P_MDNS"),
+        None => Ok(cfg.p2p_mdns.unwrap_or(false)),
+    }
+}
+
+/// gossip도 opt-in이다(`--gossip` → env `RUSTORY_P2P_GOSSIP` → `p2p_gossip` → 기본 끔).
+fn resolve_p2p_gossip(cli: bool, cfg: &config::FileConfig) -> Result<bool> {
+    if cli {
+        return Ok(true);
+    }
+    match env_nonempty("RUSTORY_P2P_GOSSIP") {
+        Some(raw) => parse_env_bool(&raw, "RUSTORY_P2P_GOSSIP"),
+        None => Ok(cfg.p2p_gossip.unwrap_or(false)),
+    }
+}
+
+fn resolve_http_token(cli: Option<String>, cfg: &config::FileConfig) -> Option<String> {
+    normalize_opt_string(cli)
+        .or_else(|| env_nonempty("RUSTORY_HTTP_TOKEN"))
+        .or_else(|| normalize_opt_string(cfg.http_token.clone()))
+}
+
+fn resolve_http_serve_options(
+    token: Option<String>,
+    tls_cert: Option<String>,
+    tls_key: Option<String>,
+    cfg: &config::FileConfig,
+) -> Result<transport::ServeOptions> {
+    let cert = normalize_opt_string(tls_cert)
+        .or_else(|| env_nonempty("RUSTORY_HTTP_TLS_CERT_PATH"))
+        .or_else(|| normalize_opt_string(cfg.http_tls_cert_path.clone()));
+    let key = normalize_opt_string(tls_key)
+        .or_else(|| env_nonempty("RUSTORY_HTTP_TLS_KEY_PATH"))
+        .or_else(|| normalize_opt_string(cfg.http_tls_key_path.clone()));
+    let tls = match (cert, key) {
+        (Some(cert), Some(key)) => Some(transport::ServeTls {
+            cert_path: config::expand_home_path(&cert)?,
+            key_path: config::expand_home_path(&key)?,
+        }),
+        (None, None) => None,
+        _ => anyhow::bail!("http tls needs both cert and key paths"),
+    };
+
+    Ok(transport::ServeOptions {
+        token: resolve_http_token(token, cfg),
+        tls,
+    })
+}
+
+fn resolve_http_client_options(
+    token: Option<String>,
+    ca: Option<String>,
+    cert_fingerprint: Option<String>,
+    cfg: &config::FileConfig,
+) -> Result<transport::ClientOptions> {
+    let ca_path = normalize_opt_string(ca)
+        .or_else(|| env_nonempty("RUSTORY_HTTP_CA_PATH"))
+        .or_else(|| normalize_opt_string(cfg.http_ca_path.clone()))
+        .map(|path| config::expand_home_path(&path))
+        .transpose()?;
+    let cert_fingerprint = normalize_opt_string(cert_fingerprint)
+        .or_else(|| env_nonempty("RUSTORY_HTTP_CERT_FINGERPRINT"))
+        .or_else(|| normalize_opt_string(cfg.http_cert_fingerprint.clone()));
+
+    Ok(transport::ClientOptions {
+        token: resolve_http_token(token, cfg),
+        ca_path,
+        cert_fingerprint,
+    })
+}
+
+fn resolve_peer_meta(cfg: &config::FileConfig) -> crate::tracker::PeerMeta {
+    let hostname = default_hostname().unwrap_or_else(|| "unknown".to_string());
+    let user_id = resolve_user_id(cfg);
+    let device_id = resolve_device_id(cfg);
+
+    crate::tracker::PeerMeta {
+        device_id: Some(device_id),
+        hostname: Some(hostname),
+        user_id: Some(user_id),
+        version: Some(env!("CARGO_PKG_VERSION").to_string()),
+    }
+}
+
+fn resolve_db_path(cli: Option<String>, cfg: &config::FileConfig) -> String {
+    normalize_opt_string(cli)
+        .or_else(|| env_nonempty("RUSTORY_DB_PATH"))
+        .or_else(|| normalize_opt_string(cfg.db_path.clone()))
+        .unwrap_or_else(|| storage::DEFAULT_DB_PATH.to_string())
+}
+
+fn resolve_user_id(cfg: &config::FileConfig) -> String {
+    env_nonempty("RUSTORY_USER_ID")
+        .or_else(|| normalize_opt_string(cfg.user_id.clone()))
+        .or_else(|| env_nonempty("USER"))
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+fn resolve_device_id(cfg: &config::FileConfig) -> String {
+    env_nonempty("RUSTORY_DEVICE_ID")
+        .or_else(|| normalize_opt_string(cfg.device_id.clone()))
+        .or_else(default_hostname)
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+fn resolve_record_ignore_regex(cfg: &config::FileConfig) -> Option<String> {
+    env_nonempty("RUSTORY_RECORD_IGNORE_REGEX")
+        .or_else(|| normalize_opt_string(cfg.record_ignore_regex.clone()))
+}
+
+fn summarize_redact_config(cfg: &config::FileConfig) -> DoctorRedactReport {
+    let patterns = cfg.redact_patterns.as_ref().map_or(0, Vec::len);
+    let builtin = resolve_redact_builtin(cfg).ok();
+    match resolve_redactor(cfg) {
+        Ok(_) => DoctorRedactReport {
+            builtin,
+            patterns,
+            valid: true,
+            error: None,
+        },
+        Err(err) => DoctorRedactReport {
+            builtin,
+            patterns,
+            valid: false,
+            error: Some(format!("{err:#}")),
+        },
+    }
+}
+
+/// 내장 탐지기 사용 여부(env `RUSTORY_REDACT_BUILTIN` → `redact_builtin` → 기본 켬)와
+/// config의 `redact_patterns`로 redactor를 만든다.
+fn resolve_redactor(cfg: &config::FileConfig) -> Result<redact::Redactor> {
+    let builtin = resolve_redact_builtin(cfg)?;
+    let patterns = cfg.redact_patterns.clone().unwrap_or_default();
+    redact::Redactor::new(builtin, &patterns)
+}
+
+fn resolve_redact_builtin(cfg: &config::FileConfig) -> Result<bool> {
+    match env_nonempty("RUSTORY_REDACT_BUILTIN") {
+        Some(raw) => parse_env_bool(&raw, "RUSTORY_REDACT_BUILTIN"),
+        None => Ok(cfg.redact_builtin.unwrap_or(true)),
+    }
+}
+
+/// `rr record`의 기록 규칙(ignore 정규식 + redaction). agent는 시작할 때 한 번 만들어 재사용한다.
+struct RecordFilter {
+    ignore: Option<std::result::Result<regex::Regex, regex::Error>>,
+    redactor: Result<redact::Redactor>,
+}
+
+impl RecordFilter {
+    fn from_config(cfg: &config::FileConfig) -> Self {
+        Self::new(
+            resolve_record_ignore_regex(cfg).as_deref(),
+            resolve_redactor(cfg),
+        )
+    }
+
+    fn new(ignore_pattern: Option<&str>, redactor: Result<redact::Redactor>) -> Self {
+        Self {
+            ignore: ignore_pattern.map(regex::Regex::new),
+            redactor,
+        }
+    }
+
+    /// 저장할 cmd(비밀값을 가린 뒤)를 돌려준다. 기록하지 않을 명령이면 `None`.
+    fn apply(&self, cmd: &str) -> Option<String> {
+        match &self.ignore {
+            Some(Ok(re)) if re.is_match(cmd) => return None,
+            Some(Ok(_)) | None => {}
+            Some(Err(err)) => {
+                // 훅은 stderr를 버릴 수 있으므로, 실패 시에도 안전하게(= 기록 스킵) 동작한다.
+                eprintln!("warn: invalid record ignore regex: {err} (skipping record for safety)");
+                return None;
+            }
+        }
+        match &self.redactor {
+            Ok(redactor) => Some(redactor.redact_or_keep(cmd)),
+            Err(err) => {
+                eprintln!("warn: invalid redact config: {err:#} (skipping record for safety)");
+                None
+            }
+        }
+    }
+}
+
+fn load_search_entries(
+    store: &storage::LocalStore,
+    query: Option<&str>,
+    filters: &storage::SearchFilters,
+    limit: usize,
+) -> Result<Vec<crate::core::Entry>> {
+    if query.is_none() && filters.is_empty() {
+        store.list_recent(limit)
+    } else {
+        store.search(query.unwrap_or(""), filters, limit)
+    }
+}
+
+const AGENT_RECORD_TIMEOUT: Duration = Duration::from_secs(2);
+const AGENT_SEARCH_TIMEOUT: Duration = Duration::from_secs(30);
+
+fn resolve_agent_socket_path(cli: Option<String>, cfg: &config::FileConfig) -> String {
+    normalize_opt_string(cli)
+        .or_else(|| env_nonempty("RUSTORY_AGENT_SOCKET"))
+        .or_else(|| normalize_opt_string(cfg.agent_socket_path.clone()))
+        .unwrap_or_else(|| agent::DEFAULT_SOCKET_PATH.to_string())
+}
+
+/// agent는 다른 cwd에서 돌고 있으므로 상대 경로를 그대로 보내면 agent 기준으로 풀린다.
+/// 요청에 싣기 전에 클라이언트 cwd 기준의 절대 경로로 바꾼다.
+fn agent_db_path(db_path: &str) -> String {
+    match config::expand_home_path(db_path).map(|p| std::path::absolute(&p)) {
+        Ok(Ok(path)) => path.display().to_string(),
+        _ => db_path.to_string(),
+    }
+}
+
+/// agent가 없거나(소켓 없음) 요청이 실패하면 `None`을 돌려주고, 호출한 쪽은 SQLite를 직접 쓴다.
+fn agent_request(
+    cfg: &config::FileConfig,
+    req: &agent::Request,
+    timeout: Duration,
+) -> Option<agent::Response> {
+    let socket_path = config::expand_home_path(&resolve_agent_socket_path(None, cfg)).ok()?;
+    match agent::request(&socket_path, req, timeout) {
+        Ok(Some(agent::Response::Error { error })) => {
+            eprintln!("warn: agent rejected request: {error} (falling back to local db)");
+            None
+        }
+        Ok(resp) => resp,
+        Err(err) => {
+            eprintln!("warn: agent request failed: {err:#} (falling back to local db)");
+            None
+        }
+    }
+}
+
+/// `rr agent`가 열어 두는 상태. 기록 규칙은 시작 시 한 번만 컴파일한다(설정을 바꾸면 재시작).
+struct RecordAgent {
+    store: storage::LocalStore,
+    filter: RecordFilter,
+    cfg: config::FileConfig,
+}
+
+impl agent::Handler for RecordAgent {
+    fn record(&mut self, mut entry: crate::core::Entry) -> Result<bool> {
+        if is_self_rr_command(&entry.cmd) {
+            return Ok(false);
+        }
+        let Some(cmd) = self.filter.apply(&entry.cmd) else {
+            return Ok(false);
+        };
+        entry.cmd = cmd;
+        fill_record_identity(&mut entry, &self.cfg);
+        self.store
+            .insert_local_entries(std::slice::from_ref(&entry))?;
+        Ok(true)
+    }
+
+    fn search(
+        &mut self,
+        query: Option<&str>,
+        filters: &storage::SearchFilters,
+        limit: usize,
+    ) -> Result<Vec<crate::core::Entry>> {
+        load_search_entries(&self.store, query, filters, limit)
+    }
+}
+
+fn run_agent(socket_path: &std::path::Path, db_path: &str, cfg: &config::FileConfig) -> Result<()> {
+    let content_key = resolve_content_key(cfg)?;
+    let store = storage::LocalStore::open(db_path)?.with_content_key(content_key.clone());
+    let mut handler = RecordAgent {
+        store,
+        filter: RecordFilter::from_config(cfg),
+        cfg: cfg.clone(),
+    };
+    let settings = resolve_scheduler_settings(cfg)?;
+    let scheduler = settings.scheduler();
+
+    let stop = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+    {
+        let stop = stop.clone();
+        let socket_path = socket_path.to_path_buf();
+        ctrlc::set_handler(move || {
+            stop.store(true, std::sync::atomic::Ordering::SeqCst);
+            agent::wake(&socket_path);
+        })
+        .context("set Ctrl-C/SIGTERM handler")?;
+    }
+
+    if !scheduler.is_empty() {
+        let store = storage::LocalStore::open(db_path)?.with_content_key(content_key);
+        let mut jobs = AgentJobs {
+            cfg: cfg.clone(),
+            db_path: db_path.to_string(),
+            settings,
+        };
+        let stop = stop.clone();
+        // 작업이 도는 중에 종료되면 스레드를 기다리지 않는다. 각 작업의 DB 쓰기는 트랜잭션 단위다.
+        std::thread::Builder::new()
+            .name("rr-scheduler".to_string())
+            .spawn(move || scheduler.run(&store, &mut jobs, &stop))
+            .context("spawn scheduler thread")?;
+    }
+
+    let served_db = config::expand_home_path(db_path)?;
+    // `--db-path`로 다른 DB를 연 agent는 설정을 읽지 않는 hook 경로의 기록을 받지 않는다.
+    let default_db = config::expand_home_path(&resolve_db_path(None, cfg))?;
+    let serves_default_db =
+        agent::canonical_db_path(&default_db) == agent::canonical_db_path(&served_db);
+    eprintln!(
+        "rr agent: listening on {} (db: {})",
+        socket_path.display(),
+        served_db.display()
+    );
+    agent::serve(
+        socket_path,
+        &served_db,
+        serves_default_db,
+        &mut handler,
+        &stop,
+    )?;
+    eprintln!("rr agent: shutting down");
+    Ok(())
+}
+
+/// config.toml의 `[scheduler.*]`를 검증해 둔 값. 없는 값은 기본값으로 채운다.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct SchedulerSettings {
+    jobs: Vec<SchedulerJobSettings>,
+    sync_limit: usize,
+    sync_push: bool,
+    prune_older_than_days: u64,
+    prune_keep_recent: usize,
+    peer_book_max_age_days: u64,
 }
 
-fn resolve_p2p_identity(
-    cli_path: Option<String>,
-    cfg: &config::FileConfig,
-) -> Result<libp2p::identity::Keypair> {
-    let path = resolve_p2p_identity_key_path(cli_path, cfg);
-    config::load_or_generate_identity_keypair(&path)
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct SchedulerJobSettings {
+    job: scheduler::Job,
+    enabled: bool,
+    schedule: scheduler::JobSchedule,
 }
 
-fn resolve_p2p_identity_key_path(cli_path: Option<String>, cfg: &config::FileConfig) -> String {
-    normalize_opt_string(cli_path)
-        .or_else(|| env_nonempty("RUSTORY_P2P_IDENTITY_KEY_PATH"))
-        .or_else(|| normalize_opt_string(cfg.p2p_identity_key_path.clone()))
-        .unwrap_or_else(|| config::DEFAULT_P2P_IDENTITY_KEY_PATH.to_string())
+impl SchedulerSettings {
+    fn scheduler(&self) -> scheduler::Scheduler {
+        scheduler::Scheduler::new(
+            self.jobs
+                .iter()
+                .filter(|j| j.enabled)
+                .map(|j| (j.job, j.schedule.clone()))
+                .collect(),
+        )
+    }
 }
 
-fn resolve_relay_identity(
-    cli_path: Option<String>,
+fn scheduler_job_config(
     cfg: &config::FileConfig,
-) -> Result<libp2p::identity::Keypair> {
-    let path = resolve_relay_identity_key_path(cli_path, cfg);
-    config::load_or_generate_identity_keypair(&path)
+    job: scheduler::Job,
+) -> &config::SchedulerJobConfig {
+    match job {
+        scheduler::Job::Sync => &cfg.scheduler.sync,
+        scheduler::Job::Prune => &cfg.scheduler.prune,
+        scheduler::Job::TrackerRegister => &cfg.scheduler.tracker_register,
+        scheduler::Job::PeerBook => &cfg.scheduler.peer_book,
+    }
 }
 
-fn resolve_relay_identity_key_path(cli_path: Option<String>, cfg: &config::FileConfig) -> String {
-    normalize_opt_string(cli_path)
-        .or_else(|| env_nonempty("RUSTORY_RELAY_IDENTITY_KEY_PATH"))
-        .or_else(|| normalize_opt_string(cfg.relay_identity_key_path.clone()))
-        .unwrap_or_else(|| config::DEFAULT_RELAY_IDENTITY_KEY_PATH.to_string())
-}
+fn resolve_scheduler_settings(cfg: &config::FileConfig) -> Result<SchedulerSettings> {
+    fn positive<T: Copy + Default + PartialEq>(
+        value: Option<T>,
+        default: T,
+        job: scheduler::Job,
+        key: &str,
+    ) -> Result<T> {
+        let value = value.unwrap_or(default);
+        if value == T::default() {
+            anyhow::bail!("scheduler.{}.{key} must be >= 1", job.as_str());
+        }
+        Ok(value)
+    }
 
-fn resolve_relay_addr(
-    cli: Option<String>,
-    cfg: &config::FileConfig,
-) -> Result<Option<libp2p::Multiaddr>> {
-    let raw = normalize_opt_string(cli)
-        .or_else(|| env_nonempty("RUSTORY_RELAY_ADDR"))
-        .or_else(|| normalize_opt_string(cfg.relay_addr.clone()));
+    let mut jobs = Vec::with_capacity(scheduler::Job::ALL.len());
+    for job in scheduler::Job::ALL {
+        let c = scheduler_job_config(cfg, job);
+        let default_interval = match job {
+            scheduler::Job::Sync => DEFAULT_SCHEDULER_SYNC_INTERVAL_SEC,
+            scheduler::Job::Prune => DEFAULT_SCHEDULER_PRUNE_INTERVAL_SEC,
+            scheduler::Job::TrackerRegister => DEFAULT_SCHEDULER_TRACKER_REGISTER_INTERVAL_SEC,
+            scheduler::Job::PeerBook => DEFAULT_SCHEDULER_PEER_BOOK_INTERVAL_SEC,
+        };
+        let interval_sec = positive(c.interval_sec, default_interval, job, "interval_sec")?;
+        let backoff_base_sec = positive(
+            c.backoff_base_sec,
+            DEFAULT_SCHEDULER_BACKOFF_BASE_SEC,
+            job,
+            "backoff_base_sec",
+        )?;
+        let backoff_cap_sec = c
+            .backoff_cap_sec
+            .unwrap_or(DEFAULT_SCHEDULER_BACKOFF_CAP_SEC.max(backoff_base_sec));
+        if backoff_cap_sec < backoff_base_sec {
+            anyhow::bail!(
+                "scheduler.{}.backoff_cap_sec must be >= backoff_base_sec",
+                job.as_str()
+            );
+        }
+        jobs.push(SchedulerJobSettings {
+            job,
+            enabled: c.enabled.unwrap_or(false),
+            schedule: scheduler::JobSchedule {
+                interval: Duration::from_secs(interval_sec),
+                backoff_base: Duration::from_secs(backoff_base_sec),
+                backoff_cap: Duration::from_secs(backoff_cap_sec),
+            },
+        });
+    }
 
-    let Some(raw) = raw else {
-        return Ok(None);
-    };
-    Ok(Some(raw.parse().context("parse relay_addr")?))
+    let s = &cfg.scheduler;
+    Ok(SchedulerSettings {
+        jobs,
+        sync_limit: positive(
+            s.sync.limit,
+            DEFAULT_SCHEDULER_SYNC_LIMIT,
+            scheduler::Job::Sync,
+            "limit",
+        )?,
+        sync_push: s.sync.push.unwrap_or(true),
+        prune_older_than_days: positive(
+            s.prune.older_than_days,
+            DEFAULT_SCHEDULER_PRUNE_DAYS,
+            scheduler::Job::Prune,
+            "older_than_days",
+        )?,
+        prune_keep_recent: s.prune.keep_recent.unwrap_or(0),
+        peer_book_max_age_days: positive(
+            s.peer_book.max_age_days,
+            DEFAULT_SCHEDULER_PEER_BOOK_MAX_AGE_DAYS,
+            scheduler::Job::PeerBook,
+            "max_age_days",
+        )?,
+    })
 }
 
-fn resolve_trackers(cli: Vec<String>, cfg: &config::FileConfig) -> Result<Vec<String>> {
-    let raw_list = if !cli.is_empty() {
-        cli
-    } else if let Some(env) = env_nonempty("RUSTORY_TRACKERS") {
-        env.split(',').map(|s| s.to_string()).collect()
-    } else {
-        cfg.trackers.clone().unwrap_or_default()
-    };
-
-    Ok(raw_list
-        .into_iter()
-        .map(|s| s.trim().to_string())
-        .filter(|s| !s.is_empty())
-        .collect())
+/// `rr agent` 스케줄러 작업의 본문. 키/tracker 설정은 실행할 때마다 다시 읽어, 고친 뒤 재시작 없이 재시도된다.
+struct AgentJobs {
+    cfg: config::FileConfig,
+    db_path: String,
+    settings: SchedulerSettings,
 }
 
-fn resolve_tracker_token(cli: Option<String>, cfg: &config::FileConfig) -> Result<Option<String>> {
-    Ok(normalize_opt_string(cli)
-        .or_else(|| env_nonempty("RUSTORY_TRACKER_TOKEN"))
-        .or_else(|| normalize_opt_string(cfg.tracker_token.clone())))
+impl scheduler::JobRunner for AgentJobs {
+    fn run(&mut self, store: &storage::LocalStore, job: scheduler::Job) -> Result<String> {
+        let now_unix = time::OffsetDateTime::now_utc().unix_timestamp();
+        match job {
+            scheduler::Job::Sync => self.run_sync(),
+            scheduler::Job::Prune => {
+                let days = self.settings.prune_older_than_days;
+                let keep_recent = self.settings.prune_keep_recent;
+                let cutoff_unix = compute_prune_cutoff_unix(now_unix, days)?;
+                let stats = store.prune_entries_older_than(cutoff_unix, keep_recent, false)?;
+                Ok(format!(
+                    "deleted={} older_than_days={days} keep_recent={keep_recent}",
+                    stats.deleted
+                ))
+            }
+            scheduler::Job::TrackerRegister => self.run_tracker_register(store, now_unix),
+            scheduler::Job::PeerBook => {
+                let days = self.settings.peer_book_max_age_days;
+                let cutoff_unix = compute_prune_cutoff_unix(now_unix, days)?;
+                let deleted = store.prune_peer_book(cutoff_unix)?;
+                Ok(format!("deleted={deleted} max_age_days={days}"))
+            }
+        }
+    }
 }
 
-fn resolve_peer_meta(cfg: &config::FileConfig) -> crate::tracker::PeerMeta {
-    let hostname = env_nonempty("HOSTNAME").unwrap_or_else(|| "unknown".to_string());
-    let user_id = resolve_user_id(cfg);
-    let device_id = resolve_device_id(cfg);
+impl AgentJobs {
+    fn run_sync(&self) -> Result<String> {
+        let cfg = &self.cfg;
+        let sync_cfg = p2p::SyncConfig {
+            psk: resolve_swarm_psk(None, cfg)?,
+            relay_addr: resolve_relay_addr(None, cfg)?,
+            trackers: resolve_trackers(Vec::new(), cfg)?,
+            tracker_token: resolve_tracker_token(None, cfg)?,
+            user_id: Some(resolve_user_id(cfg)),
+            device_id: Some(resolve_device_id(cfg)),
+            request_retry_policy: resolve_p2p_request_retry_policy(None, None, None, None, cfg)?,
+            content_key: resolve_content_key(cfg)?,
+            identity: Some(resolve_p2p_identity(None, cfg)?),
+            mdns: resolve_p2p_mdns(false, cfg)?,
+            gossip: resolve_p2p_gossip(false, cfg)?,
+        };
+        let limit = self.settings.sync_limit;
+        let push = self.settings.sync_push;
+        p2p::sync(&[], limit, &self.db_path, sync_cfg, push)?;
+        Ok(format!("limit={limit} push={push}"))
+    }
 
-    crate::tracker::PeerMeta {
-        device_id: Some(device_id),
-        hostname: Some(hostname),
-        user_id: Some(user_id),
-        version: Some(env!("CARGO_PKG_VERSION").to_string()),
+    fn run_tracker_register(&self, store: &storage::LocalStore, now_unix: i64) -> Result<String> {
+        let cfg = &self.cfg;
+        let trackers = resolve_trackers(Vec::new(), cfg)?;
+        if trackers.is_empty() {
+            anyhow::bail!("no trackers configured");
+        }
+        let identity = resolve_p2p_identity(None, cfg)?;
+        let peer_id = identity.public().to_peer_id().to_string();
+        let Some(advert) = store.get_local_advert(&peer_id)? else {
+            return Ok("skipped: p2p-serve has not recorded its addresses".to_string());
+        };
+        let age_sec = now_unix.saturating_sub(advert.updated_at_unix);
+        if age_sec > LOCAL_ADVERT_MAX_AGE_SEC {
+            return Ok(format!(
+                "skipped: p2p-serve addresses are stale (age_sec={age_sec})"
+            ));
+        }
+
+        let tracker_token = resolve_tracker_token(None, cfg)?;
+        let req = tracker::RegisterRequest {
+            peer_id,
+            addrs: advert.addrs,
+            meta: Some(resolve_peer_meta(cfg)),
+            timestamp_ms: None,
+            signature: None,
+        };
+        let total = trackers.len();
+        let mut registered = 0;
+        let mut last_err = None;
+        for base_url in trackers {
+            let client = tracker::TrackerClient::new(base_url.clone(), tracker_token.clone())
+                .with_identity(identity.clone());
+            match client.register(&req) {
+                Ok(_) => registered += 1,
+                Err(err) => last_err = Some(err.context(format!("tracker {base_url}"))),
+            }
+        }
+        if let Some(err) = last_err
+            && registered == 0
+        {
+            return Err(err);
+        }
+        Ok(format!(
+            "registered={registered}/{total} addrs={}",
+            req.addrs.len()
+        ))
     }
 }
 
-fn resolve_user_id(cfg: &config::FileConfig) -> String {
-    env_nonempty("RUSTORY_USER_ID")
-        .or_else(|| normalize_opt_string(cfg.user_id.clone()))
-        .or_else(|| env_nonempty("USER"))
-        .unwrap_or_else(|| "unknown".to_string())
+#[derive(Debug, Clone, serde::Serialize, PartialEq, Eq)]
+struct SchedulerJobReport {
+    job: String,
+    enabled: bool,
+    interval_sec: u64,
+    last_run_unix: Option<i64>,
+    last_ok_unix: Option<i64>,
+    consecutive_failures: u32,
+    next_due_in_sec: Option<u64>,
+    last_summary: Option<String>,
+    last_error: Option<String>,
 }
 
-fn resolve_device_id(cfg: &config::FileConfig) -> String {
-    env_nonempty("RUSTORY_DEVICE_ID")
-        .or_else(|| normalize_opt_string(cfg.device_id.clone()))
-        .unwrap_or_else(|| {
-            env_nonempty("HOSTNAME")
-                .or_else(|| env_nonempty("HOST"))
-                .unwrap_or_else(|| "unknown".to_string())
+fn build_scheduler_job_reports(
+    settings: &SchedulerSettings,
+    states: &[storage::JobState],
+    now_unix: i64,
+) -> Vec<SchedulerJobReport> {
+    settings
+        .jobs
+        .iter()
+        .map(|j| {
+            let state = states.iter().find(|s| s.job == j.job.as_str());
+            SchedulerJobReport {
+                job: j.job.as_str().to_string(),
+                enabled: j.enabled,
+                interval_sec: j.schedule.interval.as_secs(),
+                last_run_unix: state.and_then(|s| s.last_run_unix),
+                last_ok_unix: state.and_then(|s| s.last_ok_unix),
+                consecutive_failures: state.map_or(0, |s| s.consecutive_failures),
+                next_due_in_sec: state
+                    .filter(|_| j.enabled)
+                    .map(|s| compute_next_due_in_sec(now_unix, s.next_due_unix)),
+                last_summary: state.and_then(|s| s.last_summary.clone()),
+                last_error: state.and_then(|s| s.last_error.clone()),
+            }
         })
+        .collect()
 }
 
-fn resolve_record_ignore_regex(cfg: &config::FileConfig) -> Option<String> {
-    env_nonempty("RUSTORY_RECORD_IGNORE_REGEX")
-        .or_else(|| normalize_opt_string(cfg.record_ignore_regex.clone()))
-}
-
-fn should_ignore_record_command(
-    cmd: &str,
-    pattern: &str,
-) -> std::result::Result<bool, regex::Error> {
-    let re = regex::Regex::new(pattern)?;
-    Ok(re.is_match(cmd))
+fn print_scheduler_job_report(report: &SchedulerJobReport) {
+    let opt = |v: Option<i64>| v.map_or_else(|| "-".to_string(), |v| v.to_string());
+    let mut line = format!(
+        "scheduler job={} enabled={} interval_sec={} last_run_unix={} last_ok_unix={} failures={} next_due_in_sec={}",
+        report.job,
+        report.enabled,
+        report.interval_sec,
+        opt(report.last_run_unix),
+        opt(report.last_ok_unix),
+        report.consecutive_failures,
+        report
+            .next_due_in_sec
+            .map_or_else(|| "-".to_string(), |v| v.to_string()),
+    );
+    if let Some(summary) = report.last_summary.as_deref() {
+        line.push_str(&format!(" last_summary={summary:?}"));
+    }
+    if let Some(error) = report.last_error.as_deref() {
+        line.push_str(&format!(" last_error={error:?}"));
+    }
+    println!("{line}");
 }
 
 fn is_self_rr_command(cmd: &str) -> bool {
@@ -2152,6 +3479,16 @@ mod tests {
         assert!(!is_self_rr_command("cargo run --bin rr -- serve"));
     }
 
+    #[cfg(unix)]
+    #[test]
+    fn system_hostname_does_not_depend_on_env() {
+        // 셸 변수인 HOSTNAME은 보통 export되지 않는다. 테스트 프로세스 env와 무관하게 값을 얻어야 한다.
+        let got = system_hostname().expect("gethostname");
+        assert!(!got.is_empty());
+        assert!(!got.contains('\0'));
+        assert_eq!(default_hostname().as_deref(), Some(got.as_str()));
+    }
+
     #[test]
     fn p2p_sync_watch_parses_flags() {
         let app = App::parse_from(["rr", "p2p-sync", "--watch", "--interval-sec", "5"]);
@@ -2160,11 +3497,15 @@ mod tests {
                 watch,
                 interval_sec,
                 start_jitter_sec,
+                no_notify,
+                debounce_ms,
                 ..
             } => {
                 assert!(watch);
                 assert_eq!(interval_sec, 5);
                 assert!(start_jitter_sec.is_none());
+                assert!(!no_notify);
+                assert_eq!(debounce_ms, 200);
             }
             _ => panic!("expected p2p-sync"),
         }
@@ -2232,6 +3573,225 @@ mod tests {
         }
     }
 
+    #[test]
+    fn search_parses_query() {
+        let app = App::parse_from(["rr", "search", "--query", "git st"]);
+        match app.cmd {
+            Command::Search {
+                limit,
+                query,
+                backend,
+                filters,
+                ..
+            } => {
+                assert_eq!(limit, None);
+                assert_eq!(query.as_deref(), Some("git st"));
+                assert_eq!(backend, None);
+                assert!(filters.into_filters(0).unwrap().is_empty());
+            }
+            _ => panic!("expected search command"),
+        }
+    }
+
+    #[test]
+    fn search_parses_picker_context() {
+        let app = App::parse_from([
+            "rr",
+            "search",
+            "--filter-mode",
+            "session",
+            "--context-cwd",
+            "/work",
+            "--context-session-id",
+            "s1",
+        ]);
+        match app.cmd {
+            Command::Search {
+                filter_mode,
+                context_cwd,
+                context_hostname,
+                context_session_id,
+                ..
+            } => {
+                assert_eq!(filter_mode.as_deref(), Some("session"));
+                assert_eq!(context_cwd.as_deref(), Some("/work"));
+                assert_eq!(context_hostname, None);
+                assert_eq!(context_session_id.as_deref(), Some("s1"));
+            }
+            _ => panic!("expected search command"),
+        }
+    }
+
+    #[test]
+    fn resolve_search_filter_mode_prefers_cli_over_config() {
+        let cfg = config::FileConfig {
+            search_filter_mode: Some("directory".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            resolve_search_filter_mode(Some("host".to_string()), &cfg).unwrap(),
+            picker::FilterMode::Host
+        );
+        assert_eq!(
+            resolve_search_filter_mode(None, &cfg).unwrap(),
+            picker::FilterMode::Directory
+        );
+        assert!(resolve_search_filter_mode(Some("nope".to_string()), &cfg).is_err());
+    }
+
+    #[test]
+    fn history_list_parses_format_and_filters() {
+        let app = App::parse_from([
+            "rr",
+            "history",
+            "list",
+            "--format",
+            "{ts} {cmd}",
+            "--failed-only",
+            "--limit",
+            "5",
+        ]);
+        match app.cmd {
+            Command::History {
+                cmd:
+                    HistoryCommand::List {
+                        limit,
+                        query,
+                        format,
+                        filters,
+                    },
+            } => {
+                assert_eq!(limit, 5);
+                assert_eq!(query, None);
+                assert_eq!(format, "{ts} {cmd}");
+                assert!(filters.into_filters(0).unwrap().failed_only);
+            }
+            _ => panic!("expected history list command"),
+        }
+
+        let app = App::parse_from(["rr", "history", "list"]);
+        match app.cmd {
+            Command::History {
+                cmd: HistoryCommand::List { limit, format, .. },
+            } => {
+                assert_eq!(limit, 100);
+                assert_eq!(format, "table");
+            }
+            _ => panic!("expected history list command"),
+        }
+    }
+
+    #[test]
+    fn record_parses_session_id() {
+        let app = App::parse_from(["rr", "record", "--cmd", "ls", "--session-id", "s1"]);
+        match app.cmd {
+            Command::Record { args } => {
+                assert_eq!(args.session_id.as_deref(), Some("s1"));
+            }
+            _ => panic!("expected record command"),
+        }
+    }
+
+    #[test]
+    fn fill_record_identity_keeps_explicit_ids_and_fills_from_config() {
+        let cfg = config::FileConfig {
+            user_id: Some("cfg-user".to_string()),
+            ..Default::default()
+        };
+        let input = |user_id: &str, device_id: &str| crate::core::EntryInput {
+            device_id: device_id.to_string(),
+            user_id: user_id.to_string(),
+            ts: time::OffsetDateTime::now_utc(),
+            cmd: "ls".to_string(),
+            cwd: "/tmp".to_string(),
+            exit_code: 0,
+            duration_ms: 0,
+            shell: "zsh".to_string(),
+            hostname: "host1".to_string(),
+            session_id: None,
+        };
+
+        let mut entry = crate::core::Entry::new(input("", ""));
+        fill_record_identity(&mut entry, &cfg);
+        assert_eq!(entry.user_id, "cfg-user");
+        assert_eq!(entry.device_id, "host1");
+
+        let mut entry = crate::core::Entry::new(input("flag-user", "flag-dev"));
+        fill_record_identity(&mut entry, &cfg);
+        assert_eq!(entry.user_id, "flag-user");
+        assert_eq!(entry.device_id, "flag-dev");
+    }
+
+    #[test]
+    fn agent_db_path_resolves_relative_paths_against_client_cwd() {
+        let cwd = std::env::current_dir().unwrap();
+        assert_eq!(
+            agent_db_path("history.db"),
+            cwd.join("history.db").display().to_string()
+        );
+        assert_eq!(agent_db_path("/var/db/h.db"), "/var/db/h.db");
+        assert!(!agent_db_path("~/h.db").starts_with('~'));
+    }
+
+    #[test]
+    fn search_parses_structured_filters() {
+        let app = App::parse_from([
+            "rr",
+            "search",
+            "--cwd-prefix",
+            "/work",
+            "--exit-code",
+            "-1",
+            "--hostname",
+            "laptop",
+            "--device-id",
+            "d1",
+            "--shell",
+            "zsh",
+            "--since",
+            "7d",
+            "--until",
+            "2024-01-02",
+            "--min-duration-ms",
+            "1000",
+        ]);
+        let Command::Search { filters, .. } = app.cmd else {
+            panic!("expected search command");
+        };
+        let now_unix = 1_000_000_000;
+        let filters = filters.into_filters(now_unix).unwrap();
+        assert_eq!(filters.cwd, None);
+        assert_eq!(filters.cwd_prefix.as_deref(), Some("/work"));
+        assert_eq!(filters.exit_code, Some(-1));
+        assert!(!filters.failed_only);
+        assert_eq!(filters.hostname.as_deref(), Some("laptop"));
+        assert_eq!(filters.device_id.as_deref(), Some("d1"));
+        assert_eq!(filters.shell.as_deref(), Some("zsh"));
+        assert_eq!(filters.since_unix, Some(now_unix - 7 * 86_400));
+        assert_eq!(filters.until_unix, Some(1_704_153_600));
+        assert_eq!(filters.min_duration_ms, Some(1000));
+    }
+
+    #[test]
+    fn search_rejects_exit_code_with_failed_only() {
+        let res = App::try_parse_from(["rr", "search", "--exit-code", "1", "--failed-only"]);
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn parse_time_bound_formats() {
+        assert_eq!(parse_time_bound("1700000000", 0).unwrap(), 1_700_000_000);
+        assert_eq!(parse_time_bound("30m", 10_000).unwrap(), 10_000 - 1_800);
+        assert_eq!(
+            parse_time_bound("2w", 2_000_000).unwrap(),
+            2_000_000 - 1_209_600
+        );
+        assert_eq!(parse_time_bound("1970-01-02", 0).unwrap(), 86_400);
+        assert!(parse_time_bound("yesterday", 0).is_err());
+        assert!(parse_time_bound("2024-13-01", 0).is_err());
+        assert!(parse_time_bound("d", 0).is_err());
+    }
+
     #[test]
     fn doctor_parses() {
         let app = App::parse_from(["rr", "doctor"]);
@@ -2262,8 +3822,9 @@ mod tests {
         assert!(report.p2p_request_retry.error.is_none());
 
         let json = serde_json::to_string(&report).unwrap();
-        assert!(json.contains("\"async_upload\""));
-        assert!(json.contains("\"auto_prune\""));
+        assert!(json.contains("\"scheduler\""));
+        assert!(report.scheduler.error.is_none());
+        assert_eq!(report.scheduler.jobs.len(), 4);
         assert!(json.contains("\"relay_addr\""));
     }
 
@@ -2399,6 +3960,9 @@ mod tests {
                 shell: "zsh".to_string(),
                 hostname: "host".to_string(),
                 version: "0.1.0".to_string(),
+                session_id: None,
+                origin_seq: None,
+                origin_id: None,
             }
         }
 
@@ -2424,7 +3988,21 @@ mod tests {
             })
             .unwrap();
 
-        let report = build_sync_status_report(&store, "dev-local", None, None).unwrap();
+        let scheduler_settings =
+            resolve_scheduler_settings(&config::FileConfig::default()).unwrap();
+        store
+            .set_job_state(&storage::JobState {
+                job: "sync".to_string(),
+                last_run_unix: Some(100),
+                last_ok_unix: None,
+                consecutive_failures: 1,
+                next_due_unix: i64::MAX,
+                last_summary: None,
+                last_error: Some("no peers found".to_string()),
+            })
+            .unwrap();
+        let report =
+            build_sync_status_report(&store, "dev-local", None, &scheduler_settings, None).unwrap();
         assert_eq!(report.local_head, 3);
         assert_eq!(report.local_device_id, "dev-local");
         assert_eq!(report.peers.len(), 2);
@@ -2450,7 +4028,21 @@ mod tests {
         assert_eq!(peer_b.last_seen_unix, None);
         assert_eq!(peer_b.last_seen_age_sec, None);
 
-        let filtered = build_sync_status_report(&store, "dev-local", Some("peer-a"), None).unwrap();
+        let sync_job = &report.scheduler[0];
+        assert_eq!(sync_job.job, "sync");
+        assert!(!sync_job.enabled);
+        assert_eq!(sync_job.consecutive_failures, 1);
+        assert_eq!(sync_job.last_error.as_deref(), Some("no peers found"));
+        assert_eq!(sync_job.next_due_in_sec, None);
+
+        let filtered = build_sync_status_report(
+            &store,
+            "dev-local",
+            Some("peer-a"),
+            &scheduler_settings,
+            None,
+        )
+        .unwrap();
         assert_eq!(filtered.peers.len(), 1);
         assert_eq!(filtered.peers[0].peer_id, "peer-a");
 
@@ -2496,72 +4088,46 @@ mod tests {
     }
 
     #[test]
-    fn should_trigger_interval_respects_interval() {
-        assert!(should_trigger_interval(100, None, 15));
-        assert!(should_trigger_interval(100, Some(80), 15));
-        assert!(!should_trigger_interval(100, Some(90), 15));
-        assert!(!should_trigger_interval(100, Some(110), 15));
-    }
-
-    #[test]
-    fn compute_next_due_in_sec_handles_missing_and_elapsed_markers() {
-        assert_eq!(compute_next_due_in_sec(100, None, 15), 0);
-        assert_eq!(compute_next_due_in_sec(100, Some(80), 15), 0);
-        assert_eq!(compute_next_due_in_sec(100, Some(90), 15), 5);
-        assert_eq!(compute_next_due_in_sec(100, Some(110), 15), 15);
-    }
-
-    #[test]
-    fn summarize_async_upload_runtime_reports_marker_and_next_due() {
-        let report = summarize_async_upload_runtime(
-            AsyncUploadRuntimeSettings {
-                enabled: true,
-                interval_sec: 15,
-                limit: 200,
-                marker_path: std::path::PathBuf::from("/tmp/async-upload.last"),
-                last_trigger_unix: Some(95),
-            },
-            100,
+    fn scheduler_settings_default_to_disabled_and_validate_config() {
+        let settings = resolve_scheduler_settings(&config::FileConfig::default()).unwrap();
+        assert!(settings.scheduler().is_empty());
+        assert_eq!(settings.sync_limit, DEFAULT_SCHEDULER_SYNC_LIMIT);
+        assert!(settings.sync_push);
+
+        let mut cfg = config::FileConfig::default();
+        cfg.scheduler.prune.enabled = Some(true);
+        cfg.scheduler.prune.interval_sec = Some(600);
+        cfg.scheduler.prune.backoff_base_sec = Some(5);
+        let settings = resolve_scheduler_settings(&cfg).unwrap();
+        let prune = settings
+            .jobs
+            .iter()
+            .find(|j| j.job == scheduler::Job::Prune)
+            .unwrap();
+        assert!(prune.enabled);
+        assert_eq!(prune.schedule.interval, Duration::from_secs(600));
+        assert_eq!(prune.schedule.backoff_base, Duration::from_secs(5));
+        assert_eq!(
+            prune.schedule.backoff_cap,
+            Duration::from_secs(DEFAULT_SCHEDULER_BACKOFF_CAP_SEC)
         );
+        assert!(!settings.scheduler().is_empty());
 
-        assert!(report.enabled);
-        assert_eq!(report.interval_sec, 15);
-        assert_eq!(report.limit, 200);
-        assert_eq!(report.last_trigger_unix, Some(95));
-        assert_eq!(report.next_due_in_sec, 10);
-    }
-
-    #[test]
-    fn summarize_auto_prune_runtime_reports_marker_and_next_due() {
-        let report = summarize_auto_prune_runtime(
-            AutoPruneRuntimeSettings {
-                enabled: true,
-                older_than_days: 180,
-                interval_sec: 86_400,
-                keep_recent: 5000,
-                marker_path: std::path::PathBuf::from("/tmp/auto-prune.last"),
-                last_trigger_unix: Some(1_000_000),
-            },
-            1_000_300,
-        );
+        cfg.scheduler.sync.interval_sec = Some(0);
+        let err = resolve_scheduler_settings(&cfg).unwrap_err();
+        assert!(err.to_string().contains("scheduler.sync.interval_sec"));
 
-        assert!(report.enabled);
-        assert_eq!(report.older_than_days, 180);
-        assert_eq!(report.interval_sec, 86_400);
-        assert_eq!(report.keep_recent, 5000);
-        assert_eq!(report.last_trigger_unix, Some(1_000_000));
-        assert_eq!(report.next_due_in_sec, 86_100);
+        cfg.scheduler.sync.interval_sec = None;
+        cfg.scheduler.prune.backoff_cap_sec = Some(1);
+        let err = resolve_scheduler_settings(&cfg).unwrap_err();
+        assert!(err.to_string().contains("backoff_cap_sec"));
     }
 
     #[test]
-    fn rate_limit_marker_roundtrip() {
-        let dir = tempfile::tempdir().unwrap();
-        let marker = dir.path().join("async-upload.last");
-
-        assert_eq!(read_rate_limit_marker(&marker).unwrap(), None);
-
-        write_rate_limit_marker(&marker, 1234).unwrap();
-        assert_eq!(read_rate_limit_marker(&marker).unwrap(), Some(1234));
+    fn compute_next_due_in_sec_clamps_overdue_jobs_to_zero() {
+        assert_eq!(compute_next_due_in_sec(100, 80), 0);
+        assert_eq!(compute_next_due_in_sec(100, 100), 0);
+        assert_eq!(compute_next_due_in_sec(100, 115), 15);
     }
 
     #[test]
@@ -2669,13 +4235,24 @@ mod tests {
 
     #[test]
     fn record_ignore_regex_matches_command() {
-        assert!(should_ignore_record_command("echo token=abc", "(?i)token").unwrap());
-        assert!(!should_ignore_record_command("echo hello", "(?i)token").unwrap());
+        let filter = RecordFilter::new(Some("(?i)token"), redact::Redactor::new(false, &[]));
+        assert_eq!(filter.apply("echo token=abc"), None);
+        assert_eq!(filter.apply("echo hello").as_deref(), Some("echo hello"));
     }
 
     #[test]
     fn record_ignore_regex_invalid_pattern_is_error() {
-        assert!(should_ignore_record_command("echo hello", "(").is_err());
+        // 잘못된 패턴이면 안전하게 기록을 건너뛴다.
+        let filter = RecordFilter::new(Some("("), redact::Redactor::new(false, &[]));
+        assert_eq!(filter.apply("echo hello"), None);
+
+        let filter = RecordFilter::new(None, redact::Redactor::new(true, &["(".to_string()]));
+        assert_eq!(filter.apply("echo hello"), None);
+        let filter = RecordFilter::new(None, redact::Redactor::new(true, &[]));
+        assert_eq!(
+            filter.apply("mysql --password=hunter2").as_deref(),
+            Some("mysql --password=[REDACTED]")
+        );
     }
 
     #[test]
@@ -2694,4 +4271,56 @@ mod tests {
             _ => panic!("expected import"),
         }
     }
+
+    #[test]
+    fn export_parses_format_and_filters() {
+        let app = App::parse_from([
+            "rr",
+            "export",
+            "--format",
+            "csv",
+            "--output",
+            "/tmp/out.csv",
+            "--cwd-prefix",
+            "/work",
+        ]);
+        match app.cmd {
+            Command::Export {
+                format,
+                output,
+                query,
+                filters,
+            } => {
+                assert_eq!(format, "csv");
+                assert_eq!(output.as_deref(), Some("/tmp/out.csv"));
+                assert_eq!(query, None);
+                assert_eq!(
+                    filters.into_filters(0).unwrap().cwd_prefix.as_deref(),
+                    Some("/work")
+                );
+            }
+            _ => panic!("expected export"),
+        }
+
+        match App::parse_from(["rr", "export"]).cmd {
+            Command::Export { format, .. } => assert_eq!(format, "jsonl"),
+            _ => panic!("expected export"),
+        }
+    }
+
+    #[test]
+    fn import_parses_from_source() {
+        let app = App::parse_from(["rr", "import", "--from", "atuin"]);
+        match app.cmd {
+            Command::Import { from, path, .. } => {
+                assert_eq!(from.as_deref(), Some("atuin"));
+                assert_eq!(path, None);
+            }
+            _ => panic!("expected import"),
+        }
+
+        assert!(
+            App::try_parse_from(["rr", "import", "--from", "atuin", "--shell", "bash"]).is_err()
+        );
+    }
 }
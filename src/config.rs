@@ -4,6 +4,7 @@ use std::path::{Path, PathBuf};
 
 pub const DEFAULT_CONFIG_PATH: &str = "~/.config/rustory/config.toml";
 pub const DEFAULT_SWARM_KEY_PATH: &str = "~/.config/rustory/swarm.key";
+pub const DEFAULT_CONTENT_KEY_PATH: &str = "~/.config/rustory/content.key";
 pub const DEFAULT_P2P_IDENTITY_KEY_PATH: &str = "~/.config/rustory/identity.key";
 pub const DEFAULT_RELAY_IDENTITY_KEY_PATH: &str = "~/.config/rustory/relay.key";
 
@@ -17,12 +18,26 @@ pub struct FileConfig {
     pub trackers: Option<Vec<String>>,
     pub tracker_token: Option<String>,
 
+    /// `rr serve`가 요구하고 `rr sync`가 보내는 Bearer 토큰.
+    pub http_token: Option<String>,
+    /// `rr serve` TLS 인증서/개인키(PEM). 둘 다 있으면 HTTPS로 listen한다.
+    pub http_tls_cert_path: Option<String>,
+    pub http_tls_key_path: Option<String>,
+    /// `rr sync`가 https 피어를 검증할 CA(PEM) 또는 서버 인증서 SHA-256 지문(둘 중 하나).
+    pub http_ca_path: Option<String>,
+    pub http_cert_fingerprint: Option<String>,
+
     pub relay_addr: Option<String>,
     pub swarm_key_path: Option<String>,
+    pub content_key_path: Option<String>,
 
     pub p2p_identity_key_path: Option<String>,
     pub relay_identity_key_path: Option<String>,
 
+    /// LAN mDNS 디스커버리 사용 여부(기본: 끔).
+    pub p2p_mdns: Option<bool>,
+    /// 다른 디바이스 엔트리까지 전달하는 gossip push 사용 여부(기본: 끔).
+    pub p2p_gossip: Option<bool>,
     pub p2p_watch_start_jitter_sec: Option<u64>,
     pub p2p_request_attempts: Option<u64>,
     pub p2p_request_timeout_base_sec: Option<u64>,
@@ -30,8 +45,49 @@ pub struct FileConfig {
     pub p2p_request_backoff_base_ms: Option<u64>,
 
     pub search_limit_default: Option<usize>,
+    pub search_backend: Option<String>,
+    pub search_filter_mode: Option<String>,
+
+    /// `rr agent` 소켓 경로. `rr record`/`rr search`는 이 소켓이 살아 있으면 agent에 위임한다.
+    pub agent_socket_path: Option<String>,
 
     pub record_ignore_regex: Option<String>,
+    /// 내장 비밀값 탐지기 사용 여부(기본: 켬).
+    pub redact_builtin: Option<bool>,
+    /// 추가로 가릴 정규식. `secret` 이름 그룹이 있으면 그 부분만 가린다.
+    pub redact_patterns: Option<Vec<String>>,
+
+    /// `rr agent`가 주기적으로 돌리는 작업(`[scheduler.<job>]`).
+    pub scheduler: SchedulerConfig,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct SchedulerConfig {
+    pub sync: SchedulerJobConfig,
+    pub prune: SchedulerJobConfig,
+    pub tracker_register: SchedulerJobConfig,
+    pub peer_book: SchedulerJobConfig,
+}
+
+/// 작업 하나의 설정. 공통 필드 외에는 해당 작업에서만 쓰인다.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct SchedulerJobConfig {
+    pub enabled: Option<bool>,
+    pub interval_sec: Option<u64>,
+    /// 연속 실패 시 재시도 간격: base에서 시작해 실패마다 2배, cap에서 멈춘다.
+    pub backoff_base_sec: Option<u64>,
+    pub backoff_cap_sec: Option<u64>,
+
+    /// sync: 피어당 pull/push 배치 크기, push 여부.
+    pub limit: Option<usize>,
+    pub push: Option<bool>,
+    /// prune: `rr prune --older-than-days`/`--keep-recent`와 같다.
+    pub older_than_days: Option<u64>,
+    pub keep_recent: Option<usize>,
+    /// peer_book: 이 기간 동안 보이지 않은 피어를 지운다.
+    pub max_age_days: Option<u64>,
 }
 
 pub fn load_default() -> Result<FileConfig> {
@@ -70,16 +126,42 @@ pub fn load_or_generate_swarm_key(path: &str) -> Result<libp2p::pnet::PreSharedK
             let mut raw = [0u8; 32];
             rand::rngs::OsRng.fill_bytes(&mut raw);
             let key = PreSharedKey::new(raw);
-
-            std::fs::write(&path, key.to_string())
-                .with_context(|| format!("write swarm key: {}", path.display()))?;
-            restrict_permissions(&path)?;
+            write_swarm_key_file(&path, &key)?;
             Ok(key)
         }
         Err(err) => Err(err).with_context(|| format!("read swarm key: {}", path.display())),
     }
 }
 
+pub fn load_or_generate_content_key(path: &str) -> Result<crate::content_key::ContentKey> {
+    use crate::content_key::ContentKey;
+
+    let path = expand_home_path(path)?;
+    match std::fs::read_to_string(&path) {
+        Ok(s) => ContentKey::parse(&s).context("parse content key"),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            ensure_parent_dir(&path)?;
+
+            let key = ContentKey::generate();
+            write_content_key_file(&path, &key)?;
+            Ok(key)
+        }
+        Err(err) => Err(err).with_context(|| format!("read content key: {}", path.display())),
+    }
+}
+
+/// 콘텐츠 암호화는 opt-in이므로 키 파일이 없으면 `None`이다(자동 생성하지 않음).
+pub fn load_content_key(path: &str) -> Result<Option<crate::content_key::ContentKey>> {
+    let path = expand_home_path(path)?;
+    match std::fs::read_to_string(&path) {
+        Ok(s) => Ok(Some(
+            crate::content_key::ContentKey::parse(&s).context("parse content key")?,
+        )),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err).with_context(|| format!("read content key: {}", path.display())),
+    }
+}
+
 pub fn load_swarm_key(path: &str) -> Result<Option<libp2p::pnet::PreSharedKey>> {
     use libp2p::pnet::PreSharedKey;
 
@@ -111,12 +193,7 @@ pub fn load_or_generate_identity_keypair(path: &str) -> Result<libp2p::identity:
         Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
             ensure_parent_dir(&path)?;
             let keypair = libp2p::identity::Keypair::generate_ed25519();
-            let bytes = keypair
-                .to_protobuf_encoding()
-                .context("encode identity keypair")?;
-            std::fs::write(&path, bytes)
-                .with_context(|| format!("write identity keypair: {}", path.display()))?;
-            restrict_permissions(&path)?;
+            write_identity_keypair_file(&path, &keypair)?;
             Ok(keypair)
         }
         Err(err) => Err(err).with_context(|| format!("read identity keypair: {}", path.display())),
@@ -140,6 +217,40 @@ pub fn load_identity_keypair(path: &str) -> Result<Option<libp2p::identity::Keyp
     }
 }
 
+/// 페어링(`rr pair join`)처럼 외부에서 받은 키를 그대로 저장할 때 쓴다. 기존 파일은 덮어쓴다.
+pub fn write_swarm_key(path: &str, key: &libp2p::pnet::PreSharedKey) -> Result<()> {
+    let path = expand_home_path(path)?;
+    ensure_parent_dir(&path)?;
+    write_swarm_key_file(&path, key)
+}
+
+pub fn write_content_key(path: &str, key: &crate::content_key::ContentKey) -> Result<()> {
+    let path = expand_home_path(path)?;
+    ensure_parent_dir(&path)?;
+    write_content_key_file(&path, key)
+}
+
+fn write_swarm_key_file(path: &Path, key: &libp2p::pnet::PreSharedKey) -> Result<()> {
+    std::fs::write(path, key.to_string())
+        .with_context(|| format!("write swarm key: {}", path.display()))?;
+    restrict_permissions(path)
+}
+
+fn write_content_key_file(path: &Path, key: &crate::content_key::ContentKey) -> Result<()> {
+    std::fs::write(path, format!("{}\n", key.to_hex()))
+        .with_context(|| format!("write content key: {}", path.display()))?;
+    restrict_permissions(path)
+}
+
+fn write_identity_keypair_file(path: &Path, keypair: &libp2p::identity::Keypair) -> Result<()> {
+    let bytes = keypair
+        .to_protobuf_encoding()
+        .context("encode identity keypair")?;
+    std::fs::write(path, bytes)
+        .with_context(|| format!("write identity keypair: {}", path.display()))?;
+    restrict_permissions(path)
+}
+
 pub fn expand_home_path(path: &str) -> Result<PathBuf> {
     if let Some(rest) = path.strip_prefix("~/") {
         let home = std::env::var_os("HOME").context("HOME env var not set")?;
@@ -201,6 +312,16 @@ p2p_request_timeout_base_sec = 6
 p2p_request_timeout_cap_sec = 40
 p2p_request_backoff_base_ms = 250
 record_ignore_regex = "(?i)token|password"
+redact_builtin = false
+redact_patterns = ["corp_[a-z0-9]+", 'pin=(?P<secret>\d+)']
+
+[scheduler.sync]
+enabled = true
+interval_sec = 120
+push = false
+
+[scheduler.peer_book]
+max_age_days = 7
 "#,
         )
         .unwrap();
@@ -217,6 +338,21 @@ record_ignore_regex = "(?i)token|password"
             cfg.record_ignore_regex.as_deref(),
             Some("(?i)token|password")
         );
+        assert_eq!(cfg.redact_builtin, Some(false));
+        assert_eq!(
+            cfg.redact_patterns.as_deref(),
+            Some(
+                &[
+                    "corp_[a-z0-9]+".to_string(),
+                    r"pin=(?P<secret>\d+)".to_string()
+                ][..]
+            )
+        );
+        assert_eq!(cfg.scheduler.sync.enabled, Some(true));
+        assert_eq!(cfg.scheduler.sync.interval_sec, Some(120));
+        assert_eq!(cfg.scheduler.sync.push, Some(false));
+        assert_eq!(cfg.scheduler.peer_book.max_age_days, Some(7));
+        assert_eq!(cfg.scheduler.prune.enabled, None);
     }
 
     #[test]
@@ -229,6 +365,18 @@ record_ignore_regex = "(?i)token|password"
         assert_eq!(k1, k2);
     }
 
+    #[test]
+    fn content_key_is_opt_in_and_stable_once_generated() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("content.key");
+        let path = path.to_str().unwrap();
+
+        assert!(load_content_key(path).unwrap().is_none());
+        let k1 = load_or_generate_content_key(path).unwrap();
+        let k2 = load_content_key(path).unwrap().unwrap();
+        assert_eq!(k1.fingerprint(), k2.fingerprint());
+    }
+
     #[test]
     fn load_or_generate_identity_keypair_creates_and_is_stable() {
         let dir = tempdir().unwrap();
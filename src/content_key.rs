@@ -0,0 +1,273 @@
+use crate::core::Entry;
+use anyhow::{Context, Result, bail};
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+
+/// 암호화된 필드 값의 접두사. 뒤에는 base64url(nonce 24B || ciphertext)이 온다.
+pub const SEALED_PREFIX: &str = "rre1:";
+const NONCE_LEN: usize = 24;
+
+/// passphrase에서 키를 만들 때 salt의 도메인 구분자. 실제 salt는 여기에 user_id를 묶어 만든다
+/// ([`passphrase_salt`]). 같은 사용자의 디바이스는 같은 키를 얻고, 사용자마다 salt가 달라 한 번의
+/// 사전 계산으로 모든 사용자를 공격할 수 없다.
+const PASSPHRASE_SALT_DOMAIN: &[u8] = b"rustory/content-key/v2";
+
+/// 사용자 단위 콘텐츠 키. 동기화로 나가는 엔트리의 `cmd`/`cwd`를 봉인(seal)한다.
+#[derive(Clone)]
+pub struct ContentKey {
+    key: [u8; 32],
+}
+
+impl std::fmt::Debug for ContentKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "ContentKey({})", self.fingerprint())
+    }
+}
+
+impl ContentKey {
+    pub fn generate() -> Self {
+        let mut key = [0u8; 32];
+        rand::rngs::OsRng.fill_bytes(&mut key);
+        Self { key }
+    }
+
+    /// Argon2id로 passphrase에서 키를 만든다. salt는 `user_id`에서 만든다.
+    pub fn from_passphrase(passphrase: &str, user_id: &str) -> Result<Self> {
+        if passphrase.is_empty() {
+            bail!("content passphrase is empty");
+        }
+        let mut key = [0u8; 32];
+        argon2::Argon2::default()
+            .hash_password_into(passphrase.as_bytes(), &passphrase_salt(user_id), &mut key)
+            .map_err(|err| anyhow::anyhow!("derive content key: {err}"))?;
+        Ok(Self { key })
+    }
+
+    /// 키 파일 형식(64자 hex 1줄)을 파싱한다.
+    pub fn parse(value: &str) -> Result<Self> {
+        let hex = value.trim();
+        if hex.len() != 64 || !hex.is_ascii() {
+            bail!("content key must be 64 hex characters");
+        }
+        let mut key = [0u8; 32];
+        for (i, byte) in key.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16)
+                .context("content key must be hex")?;
+        }
+        Ok(Self { key })
+    }
+
+    pub fn to_hex(&self) -> String {
+        self.key.iter().map(|b| format!("{b:02x}")).collect()
+    }
+
+    /// 키 자체를 드러내지 않고 디바이스 간 키 일치 여부를 비교하기 위한 짧은 지문.
+    pub fn fingerprint(&self) -> String {
+        let digest = Sha256::new()
+            .chain_update(b"rustory/content-key/fingerprint")
+            .chain_update(self.key)
+            .finalize();
+        digest[..8].iter().map(|b| format!("{b:02x}")).collect()
+    }
+
+    fn cipher(&self) -> XChaCha20Poly1305 {
+        XChaCha20Poly1305::new((&self.key).into())
+    }
+
+    /// 필드를 봉인한다. AAD에 user_id/device_id/entry_id/필드 이름을 묶어 다른 엔트리로 옮겨 붙이거나
+    /// 다른 디바이스의 기록으로 바꿔 달 수 없게 한다.
+    fn seal(&self, e: &Entry, field: &str, plaintext: &str) -> String {
+        let mut nonce = [0u8; NONCE_LEN];
+        rand::rngs::OsRng.fill_bytes(&mut nonce);
+        let aad = aad(e, field);
+        let ciphertext = self
+            .cipher()
+            .encrypt(
+                XNonce::from_slice(&nonce),
+                Payload {
+                    msg: plaintext.as_bytes(),
+                    aad: &aad,
+                },
+            )
+            .expect("xchacha20poly1305 encryption is infallible for in-memory buffers");
+
+        let mut raw = nonce.to_vec();
+        raw.extend_from_slice(&ciphertext);
+        format!("{SEALED_PREFIX}{}", URL_SAFE_NO_PAD.encode(raw))
+    }
+
+    fn open(&self, e: &Entry, field: &str, value: &str) -> Result<String> {
+        let encoded = value
+            .strip_prefix(SEALED_PREFIX)
+            .context("value is not sealed")?;
+        let raw = URL_SAFE_NO_PAD
+            .decode(encoded)
+            .context("decode sealed value")?;
+        if raw.len() < NONCE_LEN {
+            bail!("sealed value is too short");
+        }
+        let (nonce, ciphertext) = raw.split_at(NONCE_LEN);
+        let aad = aad(e, field);
+        let plaintext = self
+            .cipher()
+            .decrypt(
+                XNonce::from_slice(nonce),
+                Payload {
+                    msg: ciphertext,
+                    aad: &aad,
+                },
+            )
+            .map_err(|_| {
+                anyhow::anyhow!(
+                    "decrypt {field} of entry {} (wrong content key?)",
+                    e.entry_id
+                )
+            })?;
+        String::from_utf8(plaintext).context("sealed value is not utf-8")
+    }
+
+    /// 평문인 `cmd`/`cwd`를 봉인한다. 이미 봉인된 필드는 그대로 둔다.
+    pub fn seal_entry(&self, e: &mut Entry) {
+        if !is_sealed(&e.cmd) {
+            e.cmd = self.seal(e, "cmd", &e.cmd);
+        }
+        if !is_sealed(&e.cwd) {
+            e.cwd = self.seal(e, "cwd", &e.cwd);
+        }
+    }
+
+    /// 봉인된 `cmd`/`cwd`를 푼다. 하나라도 풀 수 없으면 엔트리를 바꾸지 않고 에러를 돌려준다.
+    pub fn open_entry(&self, e: &mut Entry) -> Result<()> {
+        let cmd = if is_sealed(&e.cmd) {
+            Some(self.open(e, "cmd", &e.cmd)?)
+        } else {
+            None
+        };
+        let cwd = if is_sealed(&e.cwd) {
+            Some(self.open(e, "cwd", &e.cwd)?)
+        } else {
+            None
+        };
+        if let Some(cmd) = cmd {
+            e.cmd = cmd;
+        }
+        if let Some(cwd) = cwd {
+            e.cwd = cwd;
+        }
+        Ok(())
+    }
+}
+
+fn aad(e: &Entry, field: &str) -> Vec<u8> {
+    format!(
+        "rustory:content\0{}\0{}\0{}\0{field}",
+        e.user_id, e.device_id, e.entry_id
+    )
+    .into_bytes()
+}
+
+fn passphrase_salt(user_id: &str) -> [u8; 32] {
+    Sha256::new()
+        .chain_update(PASSPHRASE_SALT_DOMAIN)
+        .chain_update([0])
+        .chain_update(user_id.as_bytes())
+        .finalize()
+        .into()
+}
+
+pub fn is_sealed(value: &str) -> bool {
+    value.starts_with(SEALED_PREFIX)
+}
+
+pub fn is_sealed_entry(e: &Entry) -> bool {
+    is_sealed(&e.cmd) || is_sealed(&e.cwd)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::OffsetDateTime;
+
+    fn entry(entry_id: &str, cmd: &str) -> Entry {
+        Entry {
+            entry_id: entry_id.to_string(),
+            device_id: "dev1".to_string(),
+            user_id: "user1".to_string(),
+            ts: OffsetDateTime::from_unix_timestamp(1).unwrap(),
+            cmd: cmd.to_string(),
+            cwd: "/home/alice/secret-project".to_string(),
+            exit_code: 0,
+            duration_ms: 12,
+            shell: "zsh".to_string(),
+            hostname: "host".to_string(),
+            version: "0.1.0".to_string(),
+            session_id: None,
+            origin_seq: None,
+            origin_id: None,
+        }
+    }
+
+    #[test]
+    fn seal_and_open_roundtrip() {
+        let key = ContentKey::generate();
+        let original = entry("id-1", "curl -H 'Authorization: Bearer abc' https://x");
+
+        let mut sealed = original.clone();
+        key.seal_entry(&mut sealed);
+        assert!(is_sealed(&sealed.cmd) && is_sealed(&sealed.cwd));
+        assert!(!sealed.cmd.contains("Bearer"));
+        assert_eq!(sealed.entry_id, original.entry_id);
+
+        // 이미 봉인된 엔트리는 다시 봉인하지 않는다.
+        let before = sealed.clone();
+        key.seal_entry(&mut sealed);
+        assert_eq!(sealed, before);
+
+        key.open_entry(&mut sealed).unwrap();
+        assert_eq!(sealed, original);
+    }
+
+    #[test]
+    fn open_rejects_wrong_key_and_moved_ciphertext() {
+        let key = ContentKey::generate();
+        let mut a = entry("id-1", "echo a");
+        key.seal_entry(&mut a);
+
+        let mut wrong = a.clone();
+        assert!(ContentKey::generate().open_entry(&mut wrong).is_err());
+        assert_eq!(wrong, a);
+
+        let mut moved = entry("id-2", "echo b");
+        moved.cmd = a.cmd.clone();
+        assert!(key.open_entry(&mut moved).is_err());
+
+        // 같은 entry_id라도 다른 디바이스/사용자의 기록으로 바꿔 달면 열리지 않는다.
+        let mut other_device = a.clone();
+        other_device.device_id = "dev2".to_string();
+        assert!(key.open_entry(&mut other_device).is_err());
+        let mut other_user = a.clone();
+        other_user.user_id = "user2".to_string();
+        assert!(key.open_entry(&mut other_user).is_err());
+    }
+
+    #[test]
+    fn key_file_and_passphrase_are_deterministic() {
+        let key = ContentKey::generate();
+        let parsed = ContentKey::parse(&format!("{}\n", key.to_hex())).unwrap();
+        assert_eq!(parsed.fingerprint(), key.fingerprint());
+        assert!(ContentKey::parse("zz").is_err());
+
+        let a = ContentKey::from_passphrase("correct horse", "user1").unwrap();
+        let b = ContentKey::from_passphrase("correct horse", "user1").unwrap();
+        assert_eq!(a.to_hex(), b.to_hex());
+        assert_ne!(a.to_hex(), key.to_hex());
+        // salt가 사용자마다 다르므로 같은 passphrase도 다른 키가 된다.
+        let c = ContentKey::from_passphrase("correct horse", "user2").unwrap();
+        assert_ne!(a.to_hex(), c.to_hex());
+        assert!(ContentKey::from_passphrase("", "user1").is_err());
+    }
+}
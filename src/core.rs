@@ -3,7 +3,7 @@ use time::OffsetDateTime;
 
 pub type EntryId = String;
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Entry {
     pub entry_id: EntryId,
     pub device_id: String,
@@ -16,6 +16,57 @@ pub struct Entry {
     pub shell: String,
     pub hostname: String,
     pub version: String,
+    /// 셸 세션 식별자. 세션 개념이 없는 경로(import, 구버전 피어)에서는 `None`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+    /// 기록한 origin([`Entry::origin_key`]) 안에서 1부터 매기는 순번. 저장할 때 그 디바이스의 DB가 붙이고,
+    /// 다른 디바이스를 거쳐 전달될 때도 그대로 유지된다(버전 벡터의 기준). 구버전 엔트리는 `None`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub origin_seq: Option<i64>,
+    /// 순번을 붙인 DB의 식별자. 같은 `device_id`라도 DB를 새로 만들거나 백업에서 복원하면 달라지므로
+    /// 순번이 겹치지 않는다. 구버전 엔트리는 `None`이고, 그때는 `device_id`가 origin이다.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub origin_id: Option<String>,
+}
+
+/// origin key([`Entry::origin_key`]) → 가지고 있는 가장 큰 `origin_seq`.
+pub type VersionVector = std::collections::BTreeMap<String, i64>;
+
+/// pull 협상에 쓰는 "지금까지 본 것" 요약. 피어/ingest_seq와 무관하게 빠진 것만 계산할 수 있다.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyncHeads {
+    /// 엔트리를 기록한 origin별 가장 큰 `origin_seq`.
+    #[serde(default)]
+    pub entries: VersionVector,
+    /// 삭제한 origin(`deleted_by`)별 가장 큰 `delete_seq`.
+    #[serde(default)]
+    pub deletes: VersionVector,
+}
+
+/// 삭제 기록. 같은 entry_id의 엔트리가 다시 들어오지 않게 막고, 동기화로 다른 디바이스에도 전파된다.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Tombstone {
+    pub entry_id: EntryId,
+    pub device_id: String,
+    pub user_id: String,
+    pub deleted_at: OffsetDateTime,
+    /// 지운 엔트리의 `origin_seq`/`origin_id`(알 때만).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub origin_seq: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub origin_id: Option<String>,
+    /// 삭제한 DB의 origin id(구버전은 device_id)와 그 안의 삭제 순번. 구버전 tombstone은 `None`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deleted_by: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delete_seq: Option<i64>,
+}
+
+impl Tombstone {
+    /// 지운 엔트리의 origin key. [`Entry::origin_key`]와 같다.
+    pub fn origin_key(&self) -> &str {
+        self.origin_id.as_deref().unwrap_or(&self.device_id)
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -29,6 +80,7 @@ pub struct EntryInput {
     pub duration_ms: i64,
     pub shell: String,
     pub hostname: String,
+    pub session_id: Option<String>,
 }
 
 impl Entry {
@@ -45,6 +97,9 @@ impl Entry {
             shell: input.shell,
             hostname: input.hostname,
             version: env!("CARGO_PKG_VERSION").to_string(),
+            session_id: input.session_id,
+            origin_seq: None,
+            origin_id: None,
         }
     }
 
@@ -61,8 +116,16 @@ impl Entry {
             shell: input.shell,
             hostname: input.hostname,
             version: env!("CARGO_PKG_VERSION").to_string(),
+            session_id: input.session_id,
+            origin_seq: None,
+            origin_id: None,
         }
     }
+
+    /// 버전 벡터/heads의 key. `origin_id`가 없으면 `device_id`.
+    pub fn origin_key(&self) -> &str {
+        self.origin_id.as_deref().unwrap_or(&self.device_id)
+    }
 }
 
 pub fn new_entry_id() -> EntryId {
@@ -87,6 +150,18 @@ pub fn import_entry_id(
     uuid::Uuid::new_v5(&uuid::Uuid::NAMESPACE_URL, name.as_bytes()).to_string()
 }
 
+pub fn import_entry_id_from_source(
+    user_id: &str,
+    device_id: &str,
+    source: &str,
+    source_id: &str,
+) -> EntryId {
+    // 외부 히스토리 도구(atuin 등)가 자체 레코드 id를 갖고 있으면, 그 id로 UUIDv5를 만든다.
+    // 파일 순서(`source_index`)에 의존하지 않으므로 원본 DB가 바뀌어도 재-import가 멱등이다.
+    let name = format!("rustory:import-source\0{user_id}\0{device_id}\0{source}\0{source_id}");
+    uuid::Uuid::new_v5(&uuid::Uuid::NAMESPACE_URL, name.as_bytes()).to_string()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -117,6 +192,7 @@ mod tests {
             duration_ms: 12,
             shell: "zsh".to_string(),
             hostname: "host".to_string(),
+            session_id: None,
         });
 
         let _uuid = Uuid::parse_str(&e.entry_id).unwrap();
@@ -136,4 +212,13 @@ mod tests {
         let d = import_entry_id("u1", "d1", "zsh", 1, "echo 2", 0);
         assert_ne!(a, d);
     }
+
+    #[test]
+    fn import_entry_id_from_source_is_deterministic_and_scoped() {
+        let a = import_entry_id_from_source("u1", "d1", "atuin", "abc");
+        assert_eq!(a, import_entry_id_from_source("u1", "d1", "atuin", "abc"));
+        assert_ne!(a, import_entry_id_from_source("u1", "d1", "mcfly", "abc"));
+        assert_ne!(a, import_entry_id_from_source("u2", "d1", "atuin", "abc"));
+        assert_ne!(a, import_entry_id_from_source("u1", "d1", "atuin", "abd"));
+    }
 }
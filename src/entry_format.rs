@@ -0,0 +1,390 @@
+use crate::core::Entry;
+use anyhow::{Context, Result, bail};
+use std::io::Write;
+
+/// `rr history list`의 출력 형식.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// 사람이 읽기 위한 정렬된 표.
+    Table,
+    /// `Entry` 배열 1개(JSON).
+    Json,
+    /// 한 줄에 `Entry` 1개(NDJSON).
+    Ndjson,
+    /// `{ts} {cmd}` 형태의 사용자 템플릿.
+    Template(Vec<TemplatePart>),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TemplatePart {
+    Literal(String),
+    Field(Field),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Field {
+    EntryId,
+    DeviceId,
+    UserId,
+    Ts,
+    Time,
+    Cmd,
+    Cwd,
+    ExitCode,
+    DurationMs,
+    Shell,
+    Hostname,
+    Version,
+    SessionId,
+}
+
+impl Field {
+    fn parse(name: &str) -> Option<Self> {
+        Some(match name {
+            "entry_id" => Self::EntryId,
+            "device_id" => Self::DeviceId,
+            "user_id" => Self::UserId,
+            "ts" => Self::Ts,
+            "time" => Self::Time,
+            "cmd" => Self::Cmd,
+            "cwd" => Self::Cwd,
+            "exit_code" => Self::ExitCode,
+            "duration_ms" => Self::DurationMs,
+            "shell" => Self::Shell,
+            "hostname" => Self::Hostname,
+            "version" => Self::Version,
+            "session_id" => Self::SessionId,
+            _ => return None,
+        })
+    }
+
+    fn render(self, e: &Entry) -> String {
+        match self {
+            Self::EntryId => e.entry_id.clone(),
+            Self::DeviceId => e.device_id.clone(),
+            Self::UserId => e.user_id.clone(),
+            Self::Ts => e.ts.unix_timestamp().to_string(),
+            Self::Time => format_ts(e.ts),
+            Self::Cmd => e.cmd.clone(),
+            Self::Cwd => e.cwd.clone(),
+            Self::ExitCode => e.exit_code.to_string(),
+            Self::DurationMs => e.duration_ms.to_string(),
+            Self::Shell => e.shell.clone(),
+            Self::Hostname => e.hostname.clone(),
+            Self::Version => e.version.clone(),
+            Self::SessionId => e.session_id.clone().unwrap_or_default(),
+        }
+    }
+}
+
+impl OutputFormat {
+    /// `table` | `json` | `ndjson` | `{field}`를 포함한 템플릿.
+    pub fn parse(value: &str) -> Result<Self> {
+        match value {
+            "table" => Ok(Self::Table),
+            "json" => Ok(Self::Json),
+            "ndjson" => Ok(Self::Ndjson),
+            other if other.contains('{') => Ok(Self::Template(parse_template(other)?)),
+            other => bail!(
+                "unsupported output format: {other:?} (expected table|json|ndjson or a template like '{{ts}} {{cmd}}')"
+            ),
+        }
+    }
+}
+
+/// `{field}` 치환 템플릿을 파싱한다. `{{`/`}}`는 중괄호 literal이다.
+fn parse_template(template: &str) -> Result<Vec<TemplatePart>> {
+    let mut parts = Vec::new();
+    let mut literal = String::new();
+    let mut chars = template.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                literal.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                literal.push('}');
+            }
+            '{' => {
+                let mut name = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => name.push(c),
+                        None => bail!("unclosed '{{' in template: {template:?}"),
+                    }
+                }
+                let field = Field::parse(name.trim())
+                    .with_context(|| format!("unknown template field: {{{name}}}"))?;
+                if !literal.is_empty() {
+                    parts.push(TemplatePart::Literal(std::mem::take(&mut literal)));
+                }
+                parts.push(TemplatePart::Field(field));
+            }
+            '}' => bail!("unmatched '}}' in template: {template:?}"),
+            c => literal.push(c),
+        }
+    }
+    if !literal.is_empty() {
+        parts.push(TemplatePart::Literal(literal));
+    }
+    Ok(parts)
+}
+
+pub fn write_entries(out: &mut impl Write, entries: &[Entry], format: &OutputFormat) -> Result<()> {
+    match format {
+        OutputFormat::Table => write_table(out, entries)?,
+        OutputFormat::Json => {
+            serde_json::to_writer_pretty(&mut *out, entries).context("serialize entries json")?;
+            writeln!(out)?;
+        }
+        OutputFormat::Ndjson => {
+            for e in entries {
+                serde_json::to_writer(&mut *out, e).context("serialize entry ndjson")?;
+                writeln!(out)?;
+            }
+        }
+        OutputFormat::Template(parts) => {
+            for e in entries {
+                let mut line = String::new();
+                for part in parts {
+                    match part {
+                        TemplatePart::Literal(s) => line.push_str(s),
+                        TemplatePart::Field(f) => line.push_str(&f.render(e)),
+                    }
+                }
+                writeln!(out, "{line}")?;
+            }
+        }
+    }
+    out.flush().context("flush output")?;
+    Ok(())
+}
+
+fn write_table(out: &mut impl Write, entries: &[Entry]) -> Result<()> {
+    const HEADER: [&str; 6] = ["TIME", "EXIT", "DURATION", "HOST", "CWD", "CMD"];
+
+    let rows: Vec<[String; 6]> = entries
+        .iter()
+        .map(|e| {
+            [
+                format_ts(e.ts),
+                e.exit_code.to_string(),
+                format_duration_ms(e.duration_ms),
+                e.hostname.clone(),
+                e.cwd.clone(),
+                escape_one_line(&e.cmd),
+            ]
+        })
+        .collect();
+
+    // 마지막 컬럼(cmd)은 폭을 맞추지 않는다.
+    let mut widths = HEADER.map(|h| h.chars().count());
+    for row in &rows {
+        for (w, cell) in widths.iter_mut().zip(row.iter()) {
+            *w = (*w).max(cell.chars().count());
+        }
+    }
+
+    let header = HEADER.map(str::to_string);
+    for row in std::iter::once(&header).chain(rows.iter()) {
+        let mut line = String::new();
+        for (i, cell) in row.iter().enumerate() {
+            if i + 1 == row.len() {
+                line.push_str(cell);
+            } else {
+                line.push_str(cell);
+                line.push_str(&" ".repeat(widths[i] - cell.chars().count() + 2));
+            }
+        }
+        writeln!(out, "{line}")?;
+    }
+    Ok(())
+}
+
+/// 여러 줄 명령을 한 줄로 표시한다. 개행/탭은 `\n`/`\r`/`\t`로 바꾸고,
+/// `\`는 뒤에 `\`/`n`/`r`/`t`/개행/탭이 오거나 맨 끝일 때만 `\\`로 바꾼다.
+/// 그래서 `echo a\ b` 같은 흔한 명령은 그대로 보이고, `unescape_one_line`으로 원문을 복원할 수 있다.
+pub fn escape_one_line(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if matches!(
+                chars.peek(),
+                None | Some('\\' | 'n' | 'r' | 't' | '\n' | '\r' | '\t')
+            ) =>
+            {
+                out.push_str("\\\\")
+            }
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+pub fn unescape_one_line(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.peek() {
+            Some('\\') => out.push('\\'),
+            Some('n') => out.push('\n'),
+            Some('r') => out.push('\r'),
+            Some('t') => out.push('\t'),
+            _ => {
+                out.push('\\');
+                continue;
+            }
+        }
+        chars.next();
+    }
+    out
+}
+
+pub fn format_ts(ts: time::OffsetDateTime) -> String {
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
+        ts.year(),
+        u8::from(ts.month()),
+        ts.day(),
+        ts.hour(),
+        ts.minute(),
+        ts.second()
+    )
+}
+
+pub fn format_duration_ms(ms: i64) -> String {
+    if ms < 1_000 {
+        format!("{ms}ms")
+    } else if ms < 60_000 {
+        format!("{:.1}s", ms as f64 / 1_000.0)
+    } else {
+        let total_sec = ms / 1_000;
+        format!("{}m{:02}s", total_sec / 60, total_sec % 60)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::OffsetDateTime;
+
+    fn entry(entry_id: &str, ts: i64, cmd: &str) -> Entry {
+        Entry {
+            entry_id: entry_id.to_string(),
+            device_id: "dev1".to_string(),
+            user_id: "user1".to_string(),
+            ts: OffsetDateTime::from_unix_timestamp(ts).unwrap(),
+            cmd: cmd.to_string(),
+            cwd: "/tmp".to_string(),
+            exit_code: 0,
+            duration_ms: 12,
+            shell: "zsh".to_string(),
+            hostname: "host".to_string(),
+            version: "0.1.0".to_string(),
+            session_id: None,
+            origin_seq: None,
+            origin_id: None,
+        }
+    }
+
+    fn render(entries: &[Entry], format: &str) -> String {
+        let mut out = Vec::new();
+        write_entries(&mut out, entries, &OutputFormat::parse(format).unwrap()).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn template_substitutes_fields_and_escapes_braces() {
+        let entries = vec![entry("id-1", 1, "echo 1"), entry("id-2", 2, "ls")];
+        assert_eq!(
+            render(&entries, "{ts} {cmd} {{x}} {exit_code}"),
+            "1 echo 1 {x} 0\n2 ls {x} 0\n"
+        );
+    }
+
+    #[test]
+    fn template_rejects_unknown_or_unclosed_fields() {
+        assert!(OutputFormat::parse("{nope}").is_err());
+        assert!(OutputFormat::parse("{cmd").is_err());
+        assert!(OutputFormat::parse("cmd}").is_err());
+        assert!(OutputFormat::parse("yaml").is_err());
+    }
+
+    #[test]
+    fn ndjson_and_json_roundtrip_entries() {
+        let entries = vec![entry("id-1", 1, "echo 1"), entry("id-2", 2, "ls")];
+
+        let ndjson = render(&entries, "ndjson");
+        let lines: Vec<Entry> = ndjson
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[1].entry_id, "id-2");
+
+        let json = render(&entries, "json");
+        let parsed: Vec<Entry> = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed[0].cmd, "echo 1");
+    }
+
+    #[test]
+    fn table_aligns_columns() {
+        let mut long = entry("id-2", 2, "ls");
+        long.hostname = "a-much-longer-host".to_string();
+        let out = render(&[entry("id-1", 1, "echo 1"), long], "table");
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("TIME"));
+        let col = lines[0].find("CWD").unwrap();
+        assert_eq!(lines[1].find("/tmp").unwrap(), col);
+        assert_eq!(lines[2].find("/tmp").unwrap(), col);
+        assert!(lines[1].ends_with("echo 1"));
+    }
+
+    #[test]
+    fn one_line_escape_roundtrips() {
+        for cmd in [
+            "for i in 1 2; do\n  echo $i\ndone",
+            r"echo a\ b",
+            r"printf 'x\n'",
+            r"trailing\",
+            "tab\there\r\n",
+            r"\\n",
+            "zsh \\\ncontinuation",
+        ] {
+            let escaped = escape_one_line(cmd);
+            assert!(!escaped.contains(['\n', '\r', '\t']), "{escaped:?}");
+            assert_eq!(unescape_one_line(&escaped), cmd);
+        }
+        assert_eq!(escape_one_line(r"echo a\ b"), r"echo a\ b");
+        assert_eq!(escape_one_line("a\nb"), r"a\nb");
+        assert_eq!(escape_one_line(r"a\nb"), r"a\\nb");
+    }
+
+    #[test]
+    fn table_escapes_multiline_cmd() {
+        let out = render(&[entry("id-1", 1, "echo 1\necho 2")], "table");
+        assert_eq!(out.lines().count(), 2);
+        assert!(out.lines().nth(1).unwrap().ends_with(r"echo 1\necho 2"));
+    }
+
+    #[test]
+    fn format_duration_ms_scales_units() {
+        assert_eq!(format_duration_ms(850), "850ms");
+        assert_eq!(format_duration_ms(1_200), "1.2s");
+        assert_eq!(format_duration_ms(184_000), "3m04s");
+    }
+}
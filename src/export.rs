@@ -0,0 +1,475 @@
+use crate::core::Entry;
+use crate::history_import::{BASH_LINES_MARKER, ImportStats};
+use crate::redact::Redactor;
+use crate::storage::{LocalStore, SearchFilters};
+use anyhow::{Context, Result, bail};
+use std::io::Write;
+use time::OffsetDateTime;
+
+/// `rr export`의 출력 형식.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// 한 줄에 `Entry` 1개. 모든 필드를 보존한다.
+    Jsonl,
+    /// 헤더 1줄 + RFC 4180 CSV. `origin_seq`(동기화용 순번)를 뺀 모든 필드를 보존한다.
+    Csv,
+    /// zsh EXTENDED_HISTORY(`: <epoch>:<duration>;cmd`). ts/duration(초)/cmd만 남는다.
+    Zsh,
+    /// bash HISTTIMEFORMAT(`#<epoch>` + cmd). ts/cmd만 남는다. 여러 줄 명령은 `#<epoch> rustory:lines=<n>`.
+    Bash,
+}
+
+const CSV_HEADER: [&str; 12] = [
+    "entry_id",
+    "device_id",
+    "user_id",
+    "ts",
+    "cmd",
+    "cwd",
+    "exit_code",
+    "duration_ms",
+    "shell",
+    "hostname",
+    "version",
+    "session_id",
+];
+
+impl ExportFormat {
+    pub fn parse(value: &str) -> Result<Self> {
+        match value.trim() {
+            "jsonl" | "ndjson" => Ok(Self::Jsonl),
+            "csv" => Ok(Self::Csv),
+            "zsh" => Ok(Self::Zsh),
+            "bash" => Ok(Self::Bash),
+            other => bail!("unsupported export format: {other} (expected jsonl|csv|zsh|bash)"),
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Jsonl => "jsonl",
+            Self::Csv => "csv",
+            Self::Zsh => "zsh",
+            Self::Bash => "bash",
+        }
+    }
+
+    /// `rr import --from`으로 그대로 되돌릴 수 있는(모든 필드를 담은) 덤프 형식이면 반환한다.
+    pub fn entry_dump(value: &str) -> Option<Self> {
+        match Self::parse(value) {
+            Ok(f @ (Self::Jsonl | Self::Csv)) => Some(f),
+            _ => None,
+        }
+    }
+}
+
+/// 조건에 맞는 엔트리를 오래된 순으로 `out`에 스트리밍한다.
+pub fn export_entries(
+    store: &LocalStore,
+    query: &str,
+    filters: &SearchFilters,
+    format: ExportFormat,
+    out: &mut impl Write,
+) -> Result<usize> {
+    if format == ExportFormat::Csv {
+        writeln!(out, "{}", CSV_HEADER.join(","))?;
+    }
+    let n = store.for_each_matching(query, filters, |e| write_entry(out, format, &e))?;
+    out.flush().context("flush export output")?;
+    Ok(n)
+}
+
+fn write_entry(out: &mut impl Write, format: ExportFormat, e: &Entry) -> Result<()> {
+    match format {
+        ExportFormat::Jsonl => {
+            serde_json::to_writer(&mut *out, e).context("serialize entry jsonl")?;
+            writeln!(out)?;
+        }
+        ExportFormat::Csv => {
+            let fields = [
+                e.entry_id.clone(),
+                e.device_id.clone(),
+                e.user_id.clone(),
+                e.ts.unix_timestamp().to_string(),
+                e.cmd.clone(),
+                e.cwd.clone(),
+                e.exit_code.to_string(),
+                e.duration_ms.to_string(),
+                e.shell.clone(),
+                e.hostname.clone(),
+                e.version.clone(),
+                e.session_id.clone().unwrap_or_default(),
+            ];
+            let line: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+            writeln!(out, "{}", line.join(","))?;
+        }
+        ExportFormat::Zsh => {
+            // zsh는 여러 줄 명령을 `\` + 개행으로 이어 쓴다.
+            writeln!(
+                out,
+                ": {}:{};{}",
+                e.ts.unix_timestamp(),
+                e.duration_ms.max(0) / 1000,
+                e.cmd.replace('\n', "\\\n")
+            )?;
+        }
+        ExportFormat::Bash => {
+            // 여러 줄 명령과 타임스탬프처럼 보이는 줄(`#<숫자>...`)이 있는 명령은 줄 수를 표시해 둔다.
+            // 표시 없는 줄은 읽을 때 각각 별개 명령이고, `#<숫자>` 줄은 타임스탬프로 읽힌다.
+            let lines = e.cmd.lines().count();
+            if lines > 1 || e.cmd.lines().any(looks_like_bash_timestamp) {
+                writeln!(
+                    out,
+                    "#{} {BASH_LINES_MARKER}{lines}\n{}",
+                    e.ts.unix_timestamp(),
+                    e.cmd
+                )?;
+            } else {
+                writeln!(out, "#{}\n{}", e.ts.unix_timestamp(), e.cmd)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// bash는 `#` 바로 뒤가 숫자인 줄을 모두 타임스탬프로 읽는다.
+fn looks_like_bash_timestamp(line: &str) -> bool {
+    line.strip_prefix('#')
+        .is_some_and(|rest| rest.starts_with(|c: char| c.is_ascii_digit()))
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// `rr export --format jsonl|csv` 결과를 `Entry`로 되돌린다.
+pub fn read_entries(format: ExportFormat, content: &str) -> Result<Vec<Entry>> {
+    match format {
+        ExportFormat::Jsonl => content
+            .lines()
+            .enumerate()
+            .filter(|(_, l)| !l.trim().is_empty())
+            .map(|(i, l)| {
+                serde_json::from_str(l).with_context(|| format!("parse jsonl line {}", i + 1))
+            })
+            .collect(),
+        ExportFormat::Csv => {
+            let mut rows = parse_csv(content)?.into_iter();
+            match rows.next() {
+                Some(header) if header == CSV_HEADER => {}
+                Some(header) => bail!("unexpected csv header: {}", header.join(",")),
+                None => return Ok(Vec::new()),
+            }
+            rows.enumerate()
+                .map(|(i, row)| {
+                    csv_row_to_entry(row).with_context(|| format!("parse csv record {}", i + 1))
+                })
+                .collect()
+        }
+        ExportFormat::Zsh | ExportFormat::Bash => {
+            bail!(
+                "{} export is not an entry dump (use rr import --shell)",
+                format.as_str()
+            )
+        }
+    }
+}
+
+fn csv_row_to_entry(row: Vec<String>) -> Result<Entry> {
+    let Ok(
+        [
+            entry_id,
+            device_id,
+            user_id,
+            ts,
+            cmd,
+            cwd,
+            exit_code,
+            duration_ms,
+            shell,
+            hostname,
+            version,
+            session_id,
+        ],
+    ) = <[String; 12]>::try_from(row)
+    else {
+        bail!("expected {} columns", CSV_HEADER.len());
+    };
+
+    let ts: i64 = ts.parse().with_context(|| format!("invalid ts: {ts}"))?;
+    Ok(Entry {
+        entry_id,
+        device_id,
+        user_id,
+        ts: OffsetDateTime::from_unix_timestamp(ts)
+            .map_err(|_| anyhow::anyhow!("invalid unix timestamp: {ts}"))?,
+        cmd,
+        cwd,
+        exit_code: exit_code
+            .parse()
+            .with_context(|| format!("invalid exit_code: {exit_code}"))?,
+        duration_ms: duration_ms
+            .parse()
+            .with_context(|| format!("invalid duration_ms: {duration_ms}"))?,
+        shell,
+        hostname,
+        version,
+        session_id: (!session_id.is_empty()).then_some(session_id),
+        origin_seq: None,
+        origin_id: None,
+    })
+}
+
+/// 따옴표 안의 `,`/개행/`""`를 처리하는 최소 RFC 4180 파서.
+fn parse_csv(content: &str) -> Result<Vec<Vec<String>>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = content.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' => in_quotes = false,
+                c => field.push(c),
+            }
+            continue;
+        }
+        match c {
+            '"' if field.is_empty() => in_quotes = true,
+            ',' => row.push(std::mem::take(&mut field)),
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            c => field.push(c),
+        }
+    }
+    if in_quotes {
+        bail!("unterminated quoted csv field");
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+    Ok(rows)
+}
+
+/// 덤프에서 읽은 엔트리를 entry_id 그대로 넣는다(다시 넣어도 중복되지 않음).
+pub fn import_entries(
+    store: &LocalStore,
+    mut entries: Vec<Entry>,
+    limit: Option<usize>,
+    ignore_regex: Option<&regex::Regex>,
+    redactor: Option<&Redactor>,
+) -> Result<ImportStats> {
+    if let Some(n) = limit
+        && entries.len() > n
+    {
+        entries = entries.split_off(entries.len() - n);
+    }
+
+    let received = entries.len();
+    entries.retain(|e| ignore_regex.is_none_or(|re| !re.is_match(&e.cmd)));
+    let skipped = received - entries.len();
+    if let Some(redactor) = redactor {
+        for e in &mut entries {
+            if let Some((cmd, _)) = redactor.redact(&e.cmd) {
+                e.cmd = cmd;
+            }
+        }
+    }
+
+    let s = store.insert_entries_with_stats(&entries)?;
+    Ok(ImportStats {
+        received,
+        inserted: s.inserted,
+        ignored: s.ignored,
+        skipped,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history_import::{parse_bash_history, parse_zsh_history};
+
+    fn entry(entry_id: &str, ts: i64, cmd: &str) -> Entry {
+        Entry {
+            entry_id: entry_id.to_string(),
+            device_id: "dev1".to_string(),
+            user_id: "user1".to_string(),
+            ts: OffsetDateTime::from_unix_timestamp(ts).unwrap(),
+            cmd: cmd.to_string(),
+            cwd: "/tmp".to_string(),
+            exit_code: 0,
+            duration_ms: 12,
+            shell: "zsh".to_string(),
+            hostname: "host".to_string(),
+            version: "0.1.0".to_string(),
+            session_id: None,
+            origin_seq: None,
+            origin_id: None,
+        }
+    }
+
+    fn fixture_store() -> (LocalStore, Vec<Entry>) {
+        let mut tricky = entry("id-2", 200, r#"echo "a, b" 'c'"#);
+        tricky.cwd = "/work, dir".to_string();
+        tricky.exit_code = -1;
+        tricky.duration_ms = 4_321;
+        tricky.session_id = Some("s-1".to_string());
+        let entries = vec![entry("id-1", 100, "ls -la"), tricky];
+
+        let store = LocalStore::open(":memory:").unwrap();
+        store.insert_entries(&entries).unwrap();
+        (store, entries)
+    }
+
+    fn export(store: &LocalStore, format: ExportFormat) -> String {
+        let mut out = Vec::new();
+        export_entries(store, "", &SearchFilters::default(), format, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn jsonl_and_csv_roundtrip_all_fields() {
+        let (store, entries) = fixture_store();
+
+        for format in [ExportFormat::Jsonl, ExportFormat::Csv] {
+            let dumped = export(&store, format);
+            assert_eq!(read_entries(format, &dumped).unwrap(), entries);
+
+            let other = LocalStore::open(":memory:").unwrap();
+            let got = read_entries(format, &dumped).unwrap();
+            assert_eq!(
+                import_entries(&other, got.clone(), None, None, None)
+                    .unwrap()
+                    .inserted,
+                2
+            );
+            assert_eq!(
+                import_entries(&other, got, None, None, None)
+                    .unwrap()
+                    .inserted,
+                0
+            );
+            assert_eq!(export(&other, format), dumped);
+        }
+    }
+
+    #[test]
+    fn zsh_export_roundtrips_through_zsh_parser() {
+        let (store, _) = fixture_store();
+        let out = export(&store, ExportFormat::Zsh);
+        assert_eq!(out, ": 100:0;ls -la\n: 200:4;echo \"a, b\" 'c'\n");
+
+        let records = parse_zsh_history(&out);
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[1].ts_unix, Some(200));
+        assert_eq!(records[1].duration_ms, 4_000);
+        assert_eq!(records[1].cmd, r#"echo "a, b" 'c'"#);
+    }
+
+    #[test]
+    fn bash_export_roundtrips_through_bash_parser() {
+        let (store, _) = fixture_store();
+        let out = export(&store, ExportFormat::Bash);
+        assert_eq!(out, "#100\nls -la\n#200\necho \"a, b\" 'c'\n");
+
+        let records = parse_bash_history(&out);
+        let got: Vec<(Option<i64>, &str)> = records
+            .iter()
+            .map(|r| (r.ts_unix, r.cmd.as_str()))
+            .collect();
+        assert_eq!(
+            got,
+            vec![(Some(100), "ls -la"), (Some(200), r#"echo "a, b" 'c'"#)]
+        );
+    }
+
+    #[test]
+    fn multiline_cmd_roundtrips_through_shell_formats() {
+        let store = LocalStore::open(":memory:").unwrap();
+        let cmd = "for i in 1 2; do\n  echo \"$i\" \\\n    | cat\ndone";
+        store.insert_entries(&[entry("id-1", 100, cmd)]).unwrap();
+
+        let zsh = parse_zsh_history(&export(&store, ExportFormat::Zsh));
+        assert_eq!(zsh.len(), 1);
+        assert_eq!(zsh[0].cmd, cmd);
+
+        let bash_out = export(&store, ExportFormat::Bash);
+        assert!(bash_out.starts_with("#100 rustory:lines=4\n"), "{bash_out}");
+        let bash = parse_bash_history(&bash_out);
+        assert_eq!(bash.len(), 1);
+        assert_eq!(bash[0].cmd, cmd);
+
+        for format in [ExportFormat::Jsonl, ExportFormat::Csv] {
+            let got = read_entries(format, &export(&store, format)).unwrap();
+            assert_eq!(got[0].cmd, cmd);
+        }
+    }
+
+    #[test]
+    fn bash_export_escapes_timestamp_like_commands() {
+        let store = LocalStore::open(":memory:").unwrap();
+        store
+            .insert_entries(&[entry("id-1", 100, "#1700000000"), entry("id-2", 200, "ls")])
+            .unwrap();
+
+        let out = export(&store, ExportFormat::Bash);
+        assert_eq!(out, "#100 rustory:lines=1\n#1700000000\n#200\nls\n");
+        let got: Vec<(Option<i64>, String)> = parse_bash_history(&out)
+            .into_iter()
+            .map(|r| (r.ts_unix, r.cmd))
+            .collect();
+        assert_eq!(
+            got,
+            vec![
+                (Some(100), "#1700000000".to_string()),
+                (Some(200), "ls".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn export_applies_search_filters() {
+        let (store, _) = fixture_store();
+        let mut out = Vec::new();
+        let filters = SearchFilters {
+            failed_only: true,
+            ..Default::default()
+        };
+        let n = export_entries(&store, "", &filters, ExportFormat::Bash, &mut out).unwrap();
+        assert_eq!(n, 1);
+        assert_eq!(String::from_utf8(out).unwrap(), "#200\necho \"a, b\" 'c'\n");
+    }
+
+    #[test]
+    fn csv_rejects_bad_header_and_unterminated_quotes() {
+        assert!(read_entries(ExportFormat::Csv, "id,cmd\n").is_err());
+        assert!(parse_csv("a,\"b\n").is_err());
+        assert_eq!(
+            parse_csv("a,\"b\"\"c\",\r\nd,e,f").unwrap(),
+            vec![vec!["a", "b\"c", ""], vec!["d", "e", "f"]]
+        );
+    }
+
+    #[test]
+    fn entry_dump_only_accepts_lossless_formats() {
+        assert_eq!(ExportFormat::entry_dump("jsonl"), Some(ExportFormat::Jsonl));
+        assert_eq!(ExportFormat::entry_dump("csv"), Some(ExportFormat::Csv));
+        assert_eq!(ExportFormat::entry_dump("zsh"), None);
+        assert_eq!(ExportFormat::entry_dump("atuin"), None);
+    }
+}
@@ -1,21 +1,33 @@
-use crate::{core, storage::LocalStore};
+use crate::{core, redact::Redactor, storage::LocalStore};
 use anyhow::{Context, Result};
 use std::path::Path;
 use time::{Duration, OffsetDateTime};
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub struct HistoryRecord {
     // 0-based index among "command records" in the source file.
     pub source_index: u64,
     pub ts_unix: Option<i64>,
     pub duration_ms: i64,
     pub cmd: String,
+
+    // 소스가 제공하는 경우에만 채워지는 메타데이터(없으면 import 기본값 사용).
+    pub cwd: Option<String>,
+    pub exit_code: Option<i32>,
+    pub hostname: Option<String>,
+    pub session_id: Option<String>,
+    pub shell: Option<String>,
+    // 소스 자체의 레코드 id(atuin/hishtory 등). 있으면 entry_id를 이 값에서 만든다.
+    pub source_id: Option<String>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum HistoryShell {
     Bash,
     Zsh,
+    Fish,
+    Nu,
+    Pwsh,
 }
 
 impl HistoryShell {
@@ -23,7 +35,10 @@ impl HistoryShell {
         match value {
             "bash" => Ok(Self::Bash),
             "zsh" => Ok(Self::Zsh),
-            _ => anyhow::bail!("unsupported shell: {value} (expected: bash|zsh)"),
+            "fish" => Ok(Self::Fish),
+            "nu" | "nushell" => Ok(Self::Nu),
+            "pwsh" | "powershell" => Ok(Self::Pwsh),
+            _ => anyhow::bail!("unsupported shell: {value} (expected: bash|zsh|fish|nu|pwsh)"),
         }
     }
 
@@ -31,6 +46,9 @@ impl HistoryShell {
         match self {
             Self::Bash => "bash",
             Self::Zsh => "zsh",
+            Self::Fish => "fish",
+            Self::Nu => "nu",
+            Self::Pwsh => "pwsh",
         }
     }
 
@@ -38,6 +56,10 @@ impl HistoryShell {
         match self {
             Self::Bash => "~/.bash_history",
             Self::Zsh => "~/.zsh_history",
+            Self::Fish => "~/.local/share/fish/fish_history",
+            // plaintext(기본) 포맷. sqlite 포맷은 `--path .../history.sqlite3`로 지정한다.
+            Self::Nu => "~/.config/nushell/history.txt",
+            Self::Pwsh => "~/.local/share/powershell/PSReadLine/ConsoleHost_history.txt",
         }
     }
 }
@@ -50,6 +72,29 @@ pub struct ImportStats {
     pub skipped: usize,
 }
 
+/// 히스토리 파일을 읽어 레코드로 변환한다. Nushell은 sqlite 포맷도 자동 판별한다.
+pub fn load_history(shell: HistoryShell, path: &Path) -> Result<Vec<HistoryRecord>> {
+    if shell == HistoryShell::Nu && is_sqlite_file(path)? {
+        return read_nu_sqlite_history(path);
+    }
+    let content = read_history_file(path)?;
+    Ok(parse_history(shell, &content))
+}
+
+fn is_sqlite_file(path: &Path) -> Result<bool> {
+    use std::io::Read;
+
+    const MAGIC: &[u8; 16] = b"SQLite format 3\0";
+    let mut file = std::fs::File::open(path)
+        .with_context(|| format!("open history file: {}", path.display()))?;
+    let mut head = [0u8; 16];
+    match file.read_exact(&mut head) {
+        Ok(()) => Ok(&head == MAGIC),
+        Err(err) if err.kind() == std::io::ErrorKind::UnexpectedEof => Ok(false),
+        Err(err) => Err(err).with_context(|| format!("read history file: {}", path.display())),
+    }
+}
+
 pub fn read_history_file(path: &Path) -> Result<String> {
     let bytes =
         std::fs::read(path).with_context(|| format!("read history file: {}", path.display()))?;
@@ -60,6 +105,9 @@ pub fn parse_history(shell: HistoryShell, content: &str) -> Vec<HistoryRecord> {
     match shell {
         HistoryShell::Bash => parse_bash_history(content),
         HistoryShell::Zsh => parse_zsh_history(content),
+        HistoryShell::Fish => parse_fish_history(content),
+        HistoryShell::Nu => parse_nu_plaintext_history(content),
+        HistoryShell::Pwsh => parse_pwsh_history(content),
     }
 }
 
@@ -67,17 +115,27 @@ pub fn parse_zsh_history(content: &str) -> Vec<HistoryRecord> {
     // zsh extended history format (when EXTENDED_HISTORY is enabled):
     //   : <epoch>:<duration>;command
     // If a line doesn't match the format, we treat it as "command without timestamp".
+    // Multi-line commands are stored with a trailing `\` before each embedded newline.
     let mut out = Vec::new();
     let mut cmd_index: u64 = 0;
-    for raw in content.lines() {
-        let line = raw.trim_end();
+    let mut lines = content.lines();
+    while let Some(raw) = lines.next() {
+        let mut line = raw.trim_end().to_string();
         if line.is_empty() {
             continue;
         }
+        while line.ends_with('\\') {
+            let Some(next) = lines.next() else {
+                break;
+            };
+            line.pop();
+            line.push('\n');
+            line.push_str(next.trim_end());
+        }
 
         let mut ts_unix: Option<i64> = None;
         let mut duration_ms: i64 = 0;
-        let mut cmd = line.to_string();
+        let mut cmd = line.clone();
 
         // Fast-path parse of ": <epoch>:<duration>;cmd"
         if let Some(rest) = line.strip_prefix(": ")
@@ -98,43 +156,264 @@ pub fn parse_zsh_history(content: &str) -> Vec<HistoryRecord> {
             ts_unix,
             duration_ms,
             cmd,
+            ..Default::default()
         });
         cmd_index += 1;
     }
     out
 }
 
+/// `rr export --format bash`가 여러 줄 명령의 타임스탬프 줄에 붙이는 표시(`#<epoch> rustory:lines=<n>`).
+/// bash는 `#` 뒤 숫자까지만 시각으로 읽으므로 그대로 불러와도 된다.
+pub const BASH_LINES_MARKER: &str = "rustory:lines=";
+
 pub fn parse_bash_history(content: &str) -> Vec<HistoryRecord> {
     // When HISTTIMEFORMAT is set, bash can write timestamps as:
     //   #<epoch>
     //   <command>
     // If a command line doesn't have a preceding timestamp, we treat it as "timestamp missing".
+    // Every other line is its own command: bash doesn't mark `lithist` multi-line entries, and
+    // files that mix timestamped and untimestamped sessions are common. Only an explicit
+    // `#<epoch> rustory:lines=<n>` header makes the next `n` lines one command.
     let mut out = Vec::new();
     let mut next_ts: Option<i64> = None;
+
+    let push = |out: &mut Vec<HistoryRecord>, ts_unix: Option<i64>, cmd: &str| {
+        out.push(HistoryRecord {
+            source_index: out.len() as u64,
+            ts_unix,
+            duration_ms: 0,
+            cmd: cmd.trim_end().to_string(),
+            ..Default::default()
+        });
+    };
+
+    let mut lines = content.lines();
+    while let Some(raw) = lines.next() {
+        let line = raw.trim_end();
+
+        if let Some(rest) = line.strip_prefix('#') {
+            let (ts_s, marker) = rest.split_once(' ').unwrap_or((rest, ""));
+            let count = match marker.strip_prefix(BASH_LINES_MARKER) {
+                Some(n) => n.parse::<usize>().ok(),
+                None if marker.is_empty() => Some(0),
+                None => None,
+            };
+            if let (Ok(ts), Some(count)) = (ts_s.parse::<i64>(), count) {
+                if count == 0 {
+                    next_ts = Some(ts);
+                } else {
+                    let cmd = lines.by_ref().take(count).collect::<Vec<_>>().join("\n");
+                    push(&mut out, Some(ts), &cmd);
+                    next_ts = None;
+                }
+                continue;
+            }
+        }
+
+        if line.is_empty() {
+            continue;
+        }
+        push(&mut out, next_ts.take(), line);
+    }
+
+    out
+}
+
+pub fn parse_fish_history(content: &str) -> Vec<HistoryRecord> {
+    // fish history는 YAML 비슷한 포맷이다:
+    //   - cmd: echo hello
+    //     when: 1700000000
+    //     paths:
+    //       - /tmp/foo
+    // cmd 안의 개행/역슬래시는 `\n`/`\\`로 escape 되어 한 줄에 저장된다.
+    // `paths`는 커맨드 인자로 쓰인 경로 목록(cwd 아님)이라 사용하지 않는다.
+    let mut out: Vec<HistoryRecord> = Vec::new();
     let mut cmd_index: u64 = 0;
 
     for raw in content.lines() {
         let line = raw.trim_end();
-        if line.is_empty() {
+
+        if let Some(cmd) = line.strip_prefix("- cmd:") {
+            out.push(HistoryRecord {
+                source_index: cmd_index,
+                ts_unix: None,
+                duration_ms: 0,
+                cmd: unescape_fish_cmd(cmd.strip_prefix(' ').unwrap_or(cmd)),
+                ..Default::default()
+            });
+            cmd_index += 1;
             continue;
         }
 
-        if let Some(rest) = line.strip_prefix('#')
-            && let Ok(ts) = rest.parse::<i64>()
+        if let Some(ts) = line.trim_start().strip_prefix("when:")
+            && let Some(last) = out.last_mut()
+            && let Ok(ts) = ts.trim().parse::<i64>()
         {
-            next_ts = Some(ts);
+            last.ts_unix = Some(ts);
+        }
+    }
+
+    out
+}
+
+fn unescape_fish_cmd(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some('\\') => out.push('\\'),
+            Some(other) => {
+                out.push('\\');
+                out.push(other);
+            }
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+pub fn parse_nu_plaintext_history(content: &str) -> Vec<HistoryRecord> {
+    // reedline(FileBackedHistory)는 한 줄에 커맨드 1개를 쓰고, 커맨드 안의 개행은 `<\n>`로 치환한다.
+    let mut out = Vec::new();
+    let mut cmd_index: u64 = 0;
+    for raw in content.lines() {
+        let line = raw.trim_end_matches('\r');
+        if line.trim().is_empty() {
             continue;
         }
 
         out.push(HistoryRecord {
             source_index: cmd_index,
-            ts_unix: next_ts.take(),
-            duration_ms: 0,
-            cmd: line.to_string(),
+            cmd: line.replace("<\\n>", "\n"),
+            ..Default::default()
+        });
+        cmd_index += 1;
+    }
+    out
+}
+
+/// Nushell sqlite 히스토리(`history.sqlite3`)의 `history` 테이블을 읽는다.
+pub fn read_nu_sqlite_history(path: &Path) -> Result<Vec<HistoryRecord>> {
+    let conn = rusqlite::Connection::open_with_flags(
+        path,
+        rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY | rusqlite::OpenFlags::SQLITE_OPEN_NO_MUTEX,
+    )
+    .with_context(|| format!("open nushell history: {}", path.display()))?;
+    read_nu_sqlite_history_from(&conn)
+}
+
+fn read_nu_sqlite_history_from(conn: &rusqlite::Connection) -> Result<Vec<HistoryRecord>> {
+    let mut stmt = conn
+        .prepare(
+            r#"
+SELECT
+  command_line,
+  start_timestamp,
+  duration_ms,
+  exit_status,
+  cwd,
+  hostname,
+  session_id
+FROM history
+ORDER BY id ASC
+"#,
+        )
+        .context("prepare nushell history query")?;
+
+    let rows = stmt
+        .query_map([], |row| {
+            let cmd: String = row.get(0)?;
+            let start_ms: Option<i64> = row.get(1)?;
+            let duration_ms: Option<i64> = row.get(2)?;
+            let exit_status: Option<i64> = row.get(3)?;
+            let cwd: Option<String> = row.get(4)?;
+            let hostname: Option<String> = row.get(5)?;
+            let session_id: Option<i64> = row.get(6)?;
+            Ok((
+                cmd,
+                start_ms,
+                duration_ms,
+                exit_status,
+                cwd,
+                hostname,
+                session_id,
+            ))
+        })
+        .context("query nushell history")?;
+
+    let mut out = Vec::new();
+    for (i, row) in rows.enumerate() {
+        let (cmd, start_ms, duration_ms, exit_status, cwd, hostname, session_id) = row?;
+        out.push(HistoryRecord {
+            source_index: i as u64,
+            ts_unix: start_ms.map(|ms| ms.div_euclid(1000)),
+            duration_ms: duration_ms.unwrap_or(0).max(0),
+            cmd,
+            cwd: cwd.filter(|v| !v.is_empty()),
+            exit_code: exit_status.and_then(|v| i32::try_from(v).ok()),
+            hostname: hostname.filter(|v| !v.is_empty()),
+            session_id: session_id.map(|v| format!("nu-{v}")),
+            ..Default::default()
+        });
+    }
+    Ok(out)
+}
+
+pub fn parse_pwsh_history(content: &str) -> Vec<HistoryRecord> {
+    // PSReadLine(ConsoleHost_history.txt)은 타임스탬프 없이 한 줄에 커맨드 1개를 쓴다.
+    // 여러 줄 커맨드는 줄 끝의 backtick(`)으로 이어 쓴다.
+    let mut out = Vec::new();
+    let mut cmd_index: u64 = 0;
+    let mut pending: Option<String> = None;
+
+    for raw in content.lines() {
+        let line = raw.trim_end_matches('\r');
+
+        let (text, continued) = match line.strip_suffix('`') {
+            Some(text) => (text, true),
+            None => (line, false),
+        };
+        let cmd = match pending.take() {
+            Some(mut prev) => {
+                prev.push('\n');
+                prev.push_str(text);
+                prev
+            }
+            None => text.to_string(),
+        };
+
+        if continued {
+            pending = Some(cmd);
+            continue;
+        }
+        if cmd.trim().is_empty() {
+            continue;
+        }
+
+        out.push(HistoryRecord {
+            source_index: cmd_index,
+            cmd,
+            ..Default::default()
         });
         cmd_index += 1;
     }
 
+    if let Some(cmd) = pending
+        && !cmd.trim().is_empty()
+    {
+        out.push(HistoryRecord {
+            source_index: cmd_index,
+            cmd,
+            ..Default::default()
+        });
+    }
+
     out
 }
 
@@ -149,9 +428,11 @@ fn filled_ts_unix(total: usize, index: usize, original: Option<i64>, now: Offset
     }
 }
 
-pub fn import_into_store(store: &LocalStore, req: ImportRequest<'_>) -> Result<ImportStats> {
-    let mut records = parse_history(req.shell, req.content);
-
+pub fn import_into_store(
+    store: &LocalStore,
+    mut records: Vec<HistoryRecord>,
+    req: ImportRequest<'_>,
+) -> Result<ImportStats> {
     // Keep only the last N commands, if requested.
     if let Some(n) = req.limit
         && records.len() > n
@@ -191,15 +472,19 @@ pub fn import_into_store(store: &LocalStore, req: ImportRequest<'_>) -> Result<I
         let ts = OffsetDateTime::from_unix_timestamp(ts_unix)
             .map_err(|_| anyhow::anyhow!("invalid unix timestamp: {ts_unix}"))?;
 
-        let id_ts_unix = r.ts_unix.unwrap_or(0);
-        let entry_id = core::import_entry_id(
-            req.user_id,
-            req.device_id,
-            req.shell.as_str(),
-            id_ts_unix,
-            cmd,
-            r.source_index,
-        );
+        let entry_id = match r.source_id.as_deref() {
+            Some(source_id) => {
+                core::import_entry_id_from_source(req.user_id, req.device_id, req.source, source_id)
+            }
+            None => core::import_entry_id(
+                req.user_id,
+                req.device_id,
+                req.source,
+                r.ts_unix.unwrap_or(0),
+                cmd,
+                r.source_index,
+            ),
+        };
 
         buf.push(core::Entry::new_with_id(
             entry_id,
@@ -207,17 +492,21 @@ pub fn import_into_store(store: &LocalStore, req: ImportRequest<'_>) -> Result<I
                 device_id: req.device_id.to_string(),
                 user_id: req.user_id.to_string(),
                 ts,
-                cmd: cmd.to_string(),
-                cwd: "unknown".to_string(),
-                exit_code: 0,
+                cmd: match req.redactor {
+                    Some(redactor) => redactor.redact_or_keep(cmd),
+                    None => cmd.to_string(),
+                },
+                cwd: r.cwd.unwrap_or_else(|| "unknown".to_string()),
+                exit_code: r.exit_code.unwrap_or(0),
                 duration_ms: r.duration_ms,
-                shell: req.shell.as_str().to_string(),
-                hostname: req.hostname.to_string(),
+                shell: r.shell.unwrap_or_else(|| req.default_shell.to_string()),
+                hostname: r.hostname.unwrap_or_else(|| req.hostname.to_string()),
+                session_id: r.session_id,
             },
         ));
 
         if buf.len() >= BATCH {
-            let s = store.insert_entries_with_stats(&buf)?;
+            let s = store.insert_local_entries(&buf)?;
             stats.inserted += s.inserted;
             stats.ignored += s.ignored;
             buf.clear();
@@ -225,7 +514,7 @@ pub fn import_into_store(store: &LocalStore, req: ImportRequest<'_>) -> Result<I
     }
 
     if !buf.is_empty() {
-        let s = store.insert_entries_with_stats(&buf)?;
+        let s = store.insert_local_entries(&buf)?;
         stats.inserted += s.inserted;
         stats.ignored += s.ignored;
     }
@@ -235,13 +524,17 @@ pub fn import_into_store(store: &LocalStore, req: ImportRequest<'_>) -> Result<I
 
 #[derive(Debug, Clone, Copy)]
 pub struct ImportRequest<'a> {
-    pub shell: HistoryShell,
-    pub content: &'a str,
+    /// entry_id 네임스페이스(셸 이름 또는 `atuin` 같은 외부 도구 이름).
+    pub source: &'a str,
+    /// 레코드에 shell 정보가 없을 때 쓰는 값.
+    pub default_shell: &'a str,
     pub limit: Option<usize>,
     pub user_id: &'a str,
     pub device_id: &'a str,
     pub hostname: &'a str,
     pub ignore_regex: Option<&'a regex::Regex>,
+    /// 저장 전에 cmd의 비밀값을 가린다. entry_id는 원문 기준이라 다시 import해도 중복되지 않는다.
+    pub redactor: Option<&'a Redactor>,
 }
 
 #[cfg(test)]
@@ -261,12 +554,14 @@ mod tests {
                     ts_unix: Some(1700000000),
                     duration_ms: 2000,
                     cmd: "echo hello".to_string(),
+                    ..Default::default()
                 },
                 HistoryRecord {
                     source_index: 1,
                     ts_unix: Some(1700000001),
                     duration_ms: 0,
                     cmd: "ls -la".to_string(),
+                    ..Default::default()
                 },
             ]
         );
@@ -284,17 +579,285 @@ mod tests {
                     ts_unix: Some(1700000000),
                     duration_ms: 0,
                     cmd: "echo a".to_string(),
+                    ..Default::default()
                 },
                 HistoryRecord {
                     source_index: 1,
                     ts_unix: Some(1700000001),
                     duration_ms: 0,
                     cmd: "ls".to_string(),
+                    ..Default::default()
                 },
             ]
         );
     }
 
+    #[test]
+    fn parse_zsh_history_joins_backslash_continuations() {
+        let content =
+            ": 1700000000:0;for i in 1 2; do\\\n  echo $i\\\n\\\ndone\n: 1700000001:0;ls\n";
+        let got = parse_zsh_history(content);
+        assert_eq!(got.len(), 2);
+        assert_eq!(got[0].cmd, "for i in 1 2; do\n  echo $i\n\ndone");
+        assert_eq!(got[0].ts_unix, Some(1700000000));
+        assert_eq!(got[1].cmd, "ls");
+        assert_eq!(got[1].source_index, 1);
+    }
+
+    #[test]
+    fn parse_bash_history_joins_only_marked_multiline_commands() {
+        // 타임스탬프가 있는 세션과 없는 세션이 섞인 파일: 표시가 없는 줄은 각각 커맨드 1개다.
+        let content = "pwd\n#1700000000\ngit status\necho plain\n\n\
+#1700000001 rustory:lines=4\nfor i in 1 2\ndo\n  echo $i\ndone\n#1700000002\nls\nmake\n";
+        let got: Vec<(Option<i64>, String)> = parse_bash_history(content)
+            .into_iter()
+            .map(|r| (r.ts_unix, r.cmd))
+            .collect();
+        assert_eq!(
+            got,
+            vec![
+                (None, "pwd".to_string()),
+                (Some(1700000000), "git status".to_string()),
+                (None, "echo plain".to_string()),
+                (
+                    Some(1700000001),
+                    "for i in 1 2\ndo\n  echo $i\ndone".to_string()
+                ),
+                (Some(1700000002), "ls".to_string()),
+                (None, "make".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_fish_history_with_when_and_paths() {
+        let content = "\
+- cmd: echo hello
+  when: 1700000000
+- cmd: cat /tmp/a.txt
+  when: 1700000001
+  paths:
+    - /tmp/a.txt
+- cmd: printf 'a\\nb' \\\\ done
+  when: 1700000002
+- cmd: no timestamp
+";
+        let got = parse_fish_history(content);
+        assert_eq!(
+            got,
+            vec![
+                HistoryRecord {
+                    source_index: 0,
+                    ts_unix: Some(1700000000),
+                    duration_ms: 0,
+                    cmd: "echo hello".to_string(),
+                    ..Default::default()
+                },
+                HistoryRecord {
+                    source_index: 1,
+                    ts_unix: Some(1700000001),
+                    duration_ms: 0,
+                    cmd: "cat /tmp/a.txt".to_string(),
+                    ..Default::default()
+                },
+                HistoryRecord {
+                    source_index: 2,
+                    ts_unix: Some(1700000002),
+                    duration_ms: 0,
+                    cmd: "printf 'a\nb' \\ done".to_string(),
+                    ..Default::default()
+                },
+                HistoryRecord {
+                    source_index: 3,
+                    ts_unix: None,
+                    duration_ms: 0,
+                    cmd: "no timestamp".to_string(),
+                    ..Default::default()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn fish_import_uses_deterministic_ids() {
+        let store = LocalStore::open(":memory:").unwrap();
+        let content = "- cmd: git status\n  when: 1700000000\n";
+        let req = ImportRequest {
+            source: "fish",
+            default_shell: "fish",
+            limit: None,
+            user_id: "u1",
+            device_id: "d1",
+            hostname: "host",
+            ignore_regex: None,
+            redactor: None,
+        };
+
+        let s1 = import_into_store(&store, parse_fish_history(content), req).unwrap();
+        assert_eq!(s1.inserted, 1);
+        let s2 = import_into_store(&store, parse_fish_history(content), req).unwrap();
+        assert_eq!(s2.ignored, 1);
+
+        let got = store.list_recent(10).unwrap();
+        assert_eq!(got.len(), 1);
+        assert_eq!(got[0].shell, "fish");
+        assert_eq!(
+            got[0].entry_id,
+            core::import_entry_id("u1", "d1", "fish", 1700000000, "git status", 0)
+        );
+    }
+
+    #[test]
+    fn parse_nu_plaintext_history_unescapes_newlines() {
+        let content = "ls | where size > 1kb\nlet x = 1<\\n>$x + 1\n\n";
+        let got = parse_nu_plaintext_history(content);
+        assert_eq!(
+            got,
+            vec![
+                HistoryRecord {
+                    source_index: 0,
+                    cmd: "ls | where size > 1kb".to_string(),
+                    ..Default::default()
+                },
+                HistoryRecord {
+                    source_index: 1,
+                    cmd: "let x = 1\n$x + 1".to_string(),
+                    ..Default::default()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn read_nu_sqlite_history_maps_metadata() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.sqlite3");
+        {
+            // reedline SqliteBackedHistory 스키마(필요한 컬럼만).
+            let conn = rusqlite::Connection::open(&path).unwrap();
+            conn.execute_batch(
+                r#"
+CREATE TABLE history (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  command_line TEXT NOT NULL,
+  start_timestamp INTEGER,
+  session_id INTEGER,
+  hostname TEXT,
+  cwd TEXT,
+  duration_ms INTEGER,
+  exit_status INTEGER,
+  more_info TEXT
+);
+INSERT INTO history (command_line, start_timestamp, session_id, hostname, cwd, duration_ms, exit_status)
+VALUES ('cargo build', 1700000000123, 42, 'laptop', '/work', 1500, 101);
+INSERT INTO history (command_line) VALUES ('ls');
+"#,
+            )
+            .unwrap();
+        }
+
+        let got = load_history(HistoryShell::Nu, &path).unwrap();
+        assert_eq!(
+            got,
+            vec![
+                HistoryRecord {
+                    source_index: 0,
+                    ts_unix: Some(1700000000),
+                    duration_ms: 1500,
+                    cmd: "cargo build".to_string(),
+                    cwd: Some("/work".to_string()),
+                    exit_code: Some(101),
+                    hostname: Some("laptop".to_string()),
+                    session_id: Some("nu-42".to_string()),
+                    ..Default::default()
+                },
+                HistoryRecord {
+                    source_index: 1,
+                    cmd: "ls".to_string(),
+                    ..Default::default()
+                },
+            ]
+        );
+
+        let store = LocalStore::open(":memory:").unwrap();
+        let stats = import_into_store(
+            &store,
+            got,
+            ImportRequest {
+                source: "nu",
+                default_shell: "nu",
+                limit: None,
+                user_id: "u1",
+                device_id: "d1",
+                hostname: "fallback-host",
+                ignore_regex: None,
+                redactor: None,
+            },
+        )
+        .unwrap();
+        assert_eq!(stats.inserted, 2);
+
+        let entries = store.list_recent(10).unwrap();
+        let build = entries.iter().find(|e| e.cmd == "cargo build").unwrap();
+        assert_eq!(build.cwd, "/work");
+        assert_eq!(build.exit_code, 101);
+        assert_eq!(build.hostname, "laptop");
+        assert_eq!(build.session_id.as_deref(), Some("nu-42"));
+        assert_eq!(build.shell, "nu");
+        let ls = entries.iter().find(|e| e.cmd == "ls").unwrap();
+        assert_eq!(ls.cwd, "unknown");
+        assert_eq!(ls.hostname, "fallback-host");
+    }
+
+    #[test]
+    fn load_history_reads_nu_plaintext_when_not_sqlite() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.txt");
+        std::fs::write(&path, "echo a\n").unwrap();
+
+        let got = load_history(HistoryShell::Nu, &path).unwrap();
+        assert_eq!(got.len(), 1);
+        assert_eq!(got[0].cmd, "echo a");
+    }
+
+    #[test]
+    fn parse_pwsh_history_joins_backtick_continuations() {
+        let content = "Get-ChildItem\r\nGet-Process `\r\n  | Sort-Object CPU\r\n\r\ngit status\n";
+        let got = parse_pwsh_history(content);
+        assert_eq!(
+            got,
+            vec![
+                HistoryRecord {
+                    source_index: 0,
+                    cmd: "Get-ChildItem".to_string(),
+                    ..Default::default()
+                },
+                HistoryRecord {
+                    source_index: 1,
+                    cmd: "Get-Process \n  | Sort-Object CPU".to_string(),
+                    ..Default::default()
+                },
+                HistoryRecord {
+                    source_index: 2,
+                    cmd: "git status".to_string(),
+                    ..Default::default()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn history_shell_parse_accepts_aliases() {
+        assert_eq!(HistoryShell::parse("nu").unwrap(), HistoryShell::Nu);
+        assert_eq!(HistoryShell::parse("nushell").unwrap(), HistoryShell::Nu);
+        assert_eq!(HistoryShell::parse("pwsh").unwrap(), HistoryShell::Pwsh);
+        assert_eq!(
+            HistoryShell::parse("powershell").unwrap(),
+            HistoryShell::Pwsh
+        );
+        assert!(HistoryShell::parse("tcsh").is_err());
+    }
+
     #[test]
     fn import_is_idempotent_for_same_content() {
         let store = LocalStore::open(":memory:").unwrap();
@@ -302,14 +865,16 @@ mod tests {
 
         let s1 = import_into_store(
             &store,
+            parse_history(HistoryShell::Zsh, content),
             ImportRequest {
-                shell: HistoryShell::Zsh,
-                content,
+                source: "zsh",
+                default_shell: "zsh",
                 limit: None,
                 user_id: "u1",
                 device_id: "d1",
                 hostname: "host",
                 ignore_regex: None,
+                redactor: None,
             },
         )
         .unwrap();
@@ -319,14 +884,16 @@ mod tests {
 
         let s2 = import_into_store(
             &store,
+            parse_history(HistoryShell::Zsh, content),
             ImportRequest {
-                shell: HistoryShell::Zsh,
-                content,
+                source: "zsh",
+                default_shell: "zsh",
                 limit: None,
                 user_id: "u1",
                 device_id: "d1",
                 hostname: "host",
                 ignore_regex: None,
+                redactor: None,
             },
         )
         .unwrap();
@@ -342,14 +909,16 @@ mod tests {
 
         let s1 = import_into_store(
             &store,
+            parse_history(HistoryShell::Bash, content),
             ImportRequest {
-                shell: HistoryShell::Bash,
-                content,
+                source: "bash",
+                default_shell: "bash",
                 limit: None,
                 user_id: "u1",
                 device_id: "d1",
                 hostname: "host",
                 ignore_regex: None,
+                redactor: None,
             },
         )
         .unwrap();
@@ -358,14 +927,16 @@ mod tests {
 
         let s2 = import_into_store(
             &store,
+            parse_history(HistoryShell::Bash, content),
             ImportRequest {
-                shell: HistoryShell::Bash,
-                content,
+                source: "bash",
+                default_shell: "bash",
                 limit: None,
                 user_id: "u1",
                 device_id: "d1",
                 hostname: "host",
                 ignore_regex: None,
+                redactor: None,
             },
         )
         .unwrap();
@@ -382,14 +953,16 @@ mod tests {
 
         let s = import_into_store(
             &store,
+            parse_history(HistoryShell::Zsh, content),
             ImportRequest {
-                shell: HistoryShell::Zsh,
-                content,
+                source: "zsh",
+                default_shell: "zsh",
                 limit: None,
                 user_id: "u1",
                 device_id: "d1",
                 hostname: "host",
                 ignore_regex: Some(&re),
+                redactor: None,
             },
         )
         .unwrap();
@@ -399,4 +972,42 @@ mod tests {
         assert_eq!(s.inserted, 1);
         assert_eq!(store.list_recent(10).unwrap().len(), 1);
     }
+
+    #[test]
+    fn import_masks_secrets_and_reimport_scrubs_existing_rows() {
+        let store = LocalStore::open(":memory:").unwrap();
+        let content = ": 1700000000:0;mysql --password=hunter2 db\n: 1700000001:0;echo ok\n";
+        let req = ImportRequest {
+            source: "zsh",
+            default_shell: "zsh",
+            limit: None,
+            user_id: "u1",
+            device_id: "d1",
+            hostname: "host",
+            ignore_regex: None,
+            redactor: None,
+        };
+
+        // redactor 없이 먼저 들어간 원문도, 다시 import하면 같은 entry_id로 가려진다.
+        import_into_store(&store, parse_history(HistoryShell::Zsh, content), req).unwrap();
+        let redactor = Redactor::new(true, &[]).unwrap();
+        let s = import_into_store(
+            &store,
+            parse_history(HistoryShell::Zsh, content),
+            ImportRequest {
+                redactor: Some(&redactor),
+                ..req
+            },
+        )
+        .unwrap();
+        assert_eq!(s.inserted, 0);
+
+        let cmds: Vec<String> = store
+            .list_recent(10)
+            .unwrap()
+            .into_iter()
+            .map(|e| e.cmd)
+            .collect();
+        assert_eq!(cmds, vec!["echo ok", "mysql --password=[REDACTED] db"]);
+    }
 }
@@ -4,6 +4,9 @@ use anyhow::{Result, bail};
 pub enum Shell {
     Bash,
     Zsh,
+    Fish,
+    Nu,
+    Pwsh,
 }
 
 impl Shell {
@@ -11,6 +14,9 @@ impl Shell {
         match value {
             "bash" => Ok(Self::Bash),
             "zsh" => Ok(Self::Zsh),
+            "fish" => Ok(Self::Fish),
+            "nu" | "nushell" => Ok(Self::Nu),
+            "pwsh" | "powershell" => Ok(Self::Pwsh),
             _ => bail!("unsupported shell: {value}"),
         }
     }
@@ -20,6 +26,9 @@ pub fn render_hook(shell: Shell) -> String {
     match shell {
         Shell::Bash => render_bash_hook(),
         Shell::Zsh => render_zsh_hook(),
+        Shell::Fish => render_fish_hook(),
+        Shell::Nu => render_nu_hook(),
+        Shell::Pwsh => render_pwsh_hook(),
     }
 }
 
@@ -32,6 +41,13 @@ if [[ -n "${__RUSTORY_HOOK_INSTALLED:-}" ]]; then
 fi
 __RUSTORY_HOOK_INSTALLED=1
 
+# 셸 세션 식별자(셸마다 1회 생성). ctrl+r의 session 모드에서 사용한다.
+if [[ -n "${EPOCHREALTIME:-}" ]]; then
+  __rustory_session_id="$$-${EPOCHREALTIME/./}-$RANDOM"
+else
+  __rustory_session_id="$$-$(date +%s)-$RANDOM"
+fi
+
 __rustory_last_histnum=""
 __rustory_last_start_histnum=""
 __rustory_last_start_us=""
@@ -67,7 +83,8 @@ __rustory_precmd() {
   __rustory_in_hook=1
 
   local line
-  line="$(HISTTIMEFORMAT= builtin history 1 | sed -e 's/^ *//')"
+  # 여러 줄 명령(cmdhist/lithist)의 들여쓰기를 지키도록 첫 줄만 다듬는다.
+  line="$(HISTTIMEFORMAT= builtin history 1 | sed -e '1s/^ *//')"
 
   local histnum="${line%% *}"
   local cmd="${line#* }"
@@ -104,7 +121,7 @@ __rustory_precmd() {
   __rustory_last_start_sec=""
   __rustory_in_hook=""
 
-  ( rr record --cmd "$cmd" --cwd "$PWD" --exit-code "$exit_code" --duration-ms "$duration_ms" --shell "bash" --hostname "${HOSTNAME:-}" >/dev/null 2>&1 ) &
+  ( rr record --cmd "$cmd" --cwd "$PWD" --exit-code "$exit_code" --duration-ms "$duration_ms" --shell "bash" --hostname "${HOSTNAME:-}" --session-id "$__rustory_session_id" >/dev/null 2>&1 ) &
 }
 
 # PROMPT_COMMAND에 1회만 주입
@@ -117,7 +134,7 @@ __rustory_ctrl_r() {
   [[ -n "${RUSTORY_HOOK_DISABLE:-}" ]] && return 0
   local limit="${RUSTORY_SEARCH_LIMIT:-100000}"
   local selected
-  selected="$(rr search --limit "$limit")" || return 0
+  selected="$(rr search --limit "$limit" --context-cwd "$PWD" --context-hostname "${HOSTNAME:-}" --context-session-id "$__rustory_session_id")" || return 0
   [[ -z "$selected" ]] && return 0
 
   READLINE_LINE="${READLINE_LINE:0:$READLINE_POINT}$selected${READLINE_LINE:$READLINE_POINT}"
@@ -140,6 +157,13 @@ typeset -g __RUSTORY_HOOK_INSTALLED=1
 
 autoload -Uz add-zsh-hook
 
+# 셸 세션 식별자(셸마다 1회 생성). ctrl+r의 session 모드에서 사용한다.
+if [[ -n "${EPOCHREALTIME:-}" ]]; then
+  typeset -g __rustory_session_id="$$-${EPOCHREALTIME/./}-$RANDOM"
+else
+  typeset -g __rustory_session_id="$$-$(date +%s)-$RANDOM"
+fi
+
 typeset -g __rustory_last_cmd=""
 typeset -g __rustory_last_start_us=""
 
@@ -182,7 +206,7 @@ __rustory_precmd() {
   __rustory_last_cmd=""
   __rustory_last_start_us=""
 
-  ( rr record --cmd "$cmd" --cwd "$PWD" --exit-code "$exit_code" --duration-ms "$duration_ms" --shell "zsh" --hostname "${HOST:-}" >/dev/null 2>&1 ) &!
+  ( rr record --cmd "$cmd" --cwd "$PWD" --exit-code "$exit_code" --duration-ms "$duration_ms" --shell "zsh" --hostname "${HOST:-}" --session-id "$__rustory_session_id" >/dev/null 2>&1 ) &!
 }
 
 add-zsh-hook preexec __rustory_preexec
@@ -192,7 +216,7 @@ __rustory_widget_ctrl_r() {
   [[ -n "${RUSTORY_HOOK_DISABLE:-}" ]] && return 0
   local limit="${RUSTORY_SEARCH_LIMIT:-100000}"
   local selected
-  selected="$(rr search --limit "$limit")" || return 0
+  selected="$(rr search --limit "$limit" --context-cwd "$PWD" --context-hostname "${HOST:-}" --context-session-id "$__rustory_session_id")" || return 0
   if [[ -n "$selected" ]]; then
     LBUFFER+="$selected"
   fi
@@ -205,6 +229,198 @@ bindkey '^R' __rustory_ctrl_r_widget
     .to_string()
 }
 
+fn render_fish_hook() -> String {
+    r#"# rustory (rr) fish hook
+# 설치(예): rr hook --shell fish | source
+
+if set -q __RUSTORY_HOOK_INSTALLED
+    exit 0
+end
+set -g __RUSTORY_HOOK_INSTALLED 1
+
+# 셸 세션 식별자(셸마다 1회 생성). ctrl+r의 session 모드에서 사용한다.
+set -g __rustory_session_id "$fish_pid-"(date +%s)"-"(random)
+set -g __rustory_last_cmd ""
+
+function __rustory_preexec --on-event fish_preexec
+    set -g __rustory_last_cmd $argv[1]
+end
+
+function __rustory_postexec --on-event fish_postexec
+    # fish_postexec 시점에는 $status/$CMD_DURATION(ms)이 방금 실행한 커맨드 기준이다.
+    set -l exit_code $status
+    set -l duration_ms $CMD_DURATION
+    test -n "$RUSTORY_HOOK_DISABLE"; and return 0
+
+    set -l cmd (string trim -- "$__rustory_last_cmd" | string collect)
+    set -g __rustory_last_cmd ""
+    test -z "$cmd"; and return 0
+
+    # rr 자체는 기록하지 않는다.
+    switch $cmd
+        case rr 'rr *'
+            return 0
+    end
+
+    test -n "$duration_ms"; or set duration_ms 0
+
+    command rr record --cmd "$cmd" --cwd "$PWD" --exit-code $exit_code --duration-ms $duration_ms --shell "fish" --hostname "$hostname" --session-id "$__rustory_session_id" >/dev/null 2>&1 &
+    disown 2>/dev/null
+end
+
+function __rustory_ctrl_r
+    test -n "$RUSTORY_HOOK_DISABLE"; and return 0
+    set -l limit 100000
+    test -n "$RUSTORY_SEARCH_LIMIT"; and set limit $RUSTORY_SEARCH_LIMIT
+
+    set -l selected (command rr search --limit "$limit" --context-cwd "$PWD" --context-hostname "$hostname" --context-session-id "$__rustory_session_id" | string collect)
+    if test -n "$selected"
+        commandline -i -- $selected
+    end
+    commandline -f repaint
+end
+
+bind \cr __rustory_ctrl_r
+bind -M insert \cr __rustory_ctrl_r
+"#
+    .to_string()
+}
+
+fn render_nu_hook() -> String {
+    r#"# rustory (rr) nushell hook
+# 설치(예): nushell은 동적 source를 지원하지 않으므로 파일로 저장한 뒤 config.nu에서 source 한다.
+#   rr hook --shell nu | save -f ~/.config/nushell/rustory.nu
+#   (config.nu) source ~/.config/nushell/rustory.nu
+
+# 셸 세션 식별자(셸마다 1회 생성). ctrl+r의 session 모드에서 사용한다.
+$env.__RUSTORY_SESSION_ID = (random uuid)
+$env.__RUSTORY_LAST_CMD = ""
+
+$env.config = ($env.config | upsert hooks.pre_execution (
+    ($env.config.hooks?.pre_execution? | default []) | append {||
+        $env.__RUSTORY_LAST_CMD = (commandline)
+    }
+))
+
+$env.config = ($env.config | upsert hooks.pre_prompt (
+    ($env.config.hooks?.pre_prompt? | default []) | append {||
+        # pre_prompt 시점에는 LAST_EXIT_CODE/CMD_DURATION_MS가 방금 실행한 커맨드 기준이다.
+        let exit_code = ($env.LAST_EXIT_CODE? | default 0)
+        let duration_ms = ($env.CMD_DURATION_MS? | default "0")
+        let cmd = ($env.__RUSTORY_LAST_CMD? | default "" | str trim)
+        $env.__RUSTORY_LAST_CMD = ""
+
+        if ($env.RUSTORY_HOOK_DISABLE? | is-not-empty) { return }
+        if ($cmd | is-empty) { return }
+        # rr 자체는 기록하지 않는다.
+        if ($cmd == "rr" or ($cmd | str starts-with "rr ")) { return }
+
+        # 프롬프트를 막지 않도록 sh로 백그라운드 실행한다.
+        ^sh -c 'rr "$@" >/dev/null 2>&1 &' rr record --cmd $cmd --cwd $env.PWD --exit-code $"($exit_code)" --duration-ms $"($duration_ms)" --shell "nu" --hostname (sys host | get hostname) --session-id $env.__RUSTORY_SESSION_ID
+    }
+))
+
+$env.config = ($env.config | upsert keybindings (
+    ($env.config.keybindings? | default []) | append {
+        name: rustory_ctrl_r
+        modifier: control
+        keycode: char_r
+        mode: [emacs, vi_normal, vi_insert]
+        event: {
+            send: executehostcommand
+            cmd: "if ($env.RUSTORY_HOOK_DISABLE? | is-empty) { commandline edit --insert (^rr search --limit ($env.RUSTORY_SEARCH_LIMIT? | default '100000') --context-cwd $env.PWD --context-hostname (sys host | get hostname) --context-session-id $env.__RUSTORY_SESSION_ID | str trim --right) }"
+        }
+    }
+))
+"#
+    .to_string()
+}
+
+fn render_pwsh_hook() -> String {
+    r#"# rustory (rr) PowerShell hook (PSReadLine)
+# 설치(예): rr hook --shell pwsh | Out-String | Invoke-Expression
+# 영구 설치는 $PROFILE에 같은 줄을 추가한다.
+
+if ($global:__RustoryHookInstalled) { return }
+$global:__RustoryHookInstalled = $true
+
+# 셸 세션 식별자(셸마다 1회 생성). ctrl+r의 session 모드에서 사용한다.
+$global:__RustorySessionId = [guid]::NewGuid().ToString()
+$global:__RustoryLastCmd = $null
+
+# 실행 직전: PSReadLine이 히스토리에 추가할 라인을 기억해 둔다(기존 handler가 있으면 그대로 위임).
+$global:__RustoryPrevAddToHistory = (Get-PSReadLineOption).AddToHistoryHandler
+Set-PSReadLineOption -AddToHistoryHandler {
+    param([string]$line)
+    $global:__RustoryLastCmd = $line
+    if ($global:__RustoryPrevAddToHistory) {
+        return & $global:__RustoryPrevAddToHistory $line
+    }
+    return $true
+}
+
+# 프롬프트를 막지 않도록 rr을 기다리지 않고 띄운다.
+# ArgumentList가 있으면(PowerShell 7+) 인자를 그대로 넘기고, 없으면(Windows PowerShell 5.1) job으로 돌린다.
+function global:__RustoryRecord([string[]]$rrArgs) {
+    $rr = (Get-Command rr -CommandType Application -ErrorAction SilentlyContinue | Select-Object -First 1).Source
+    if (-not $rr) { return }
+    $psi = [System.Diagnostics.ProcessStartInfo]::new($rr)
+    if ($psi.PSObject.Properties['ArgumentList']) {
+        foreach ($a in $rrArgs) { $psi.ArgumentList.Add($a) }
+        $psi.UseShellExecute = $false
+        $psi.CreateNoWindow = $true
+        $psi.RedirectStandardOutput = $true
+        $psi.RedirectStandardError = $true
+        try { [void][System.Diagnostics.Process]::Start($psi) } catch {}
+    } else {
+        Get-Job -Name __rustory_record -ErrorAction SilentlyContinue | Where-Object State -ne 'Running' | Remove-Job
+        [void](Start-Job -Name __rustory_record -ArgumentList (,$rrArgs) -ScriptBlock {
+            param($a)
+            & rr @a *> $null
+        })
+    }
+}
+
+# 실행 직후(프롬프트 렌더 시점): exit code/duration을 계산해 기록한다.
+$global:__RustoryPrevPrompt = $function:prompt
+function global:prompt {
+    $ok = $?
+    $nativeExit = $global:LASTEXITCODE
+
+    $cmd = $global:__RustoryLastCmd
+    $global:__RustoryLastCmd = $null
+
+    if (-not $env:RUSTORY_HOOK_DISABLE -and $cmd) {
+        $cmd = $cmd.Trim()
+        # rr 자체는 기록하지 않는다.
+        if ($cmd -and $cmd -ne 'rr' -and -not $cmd.StartsWith('rr ')) {
+            $exitCode = if ($ok) { 0 } elseif ($nativeExit) { $nativeExit } else { 1 }
+            $durationMs = 0
+            $last = Get-History -Count 1
+            if ($last -and $last.EndExecutionTime) {
+                $durationMs = [long]($last.EndExecutionTime - $last.StartExecutionTime).TotalMilliseconds
+            }
+            __RustoryRecord @('record', '--cmd', $cmd, '--cwd', $PWD.ProviderPath, '--exit-code', "$exitCode", '--duration-ms', "$durationMs", '--shell', 'pwsh', '--hostname', [System.Net.Dns]::GetHostName(), '--session-id', $global:__RustorySessionId)
+        }
+    }
+
+    $global:LASTEXITCODE = $nativeExit
+    & $global:__RustoryPrevPrompt
+}
+
+Set-PSReadLineKeyHandler -Chord Ctrl+r -BriefDescription 'rustory search' -ScriptBlock {
+    if ($env:RUSTORY_HOOK_DISABLE) { return }
+    $limit = if ($env:RUSTORY_SEARCH_LIMIT) { $env:RUSTORY_SEARCH_LIMIT } else { '100000' }
+    $selected = (& rr search --limit $limit --context-cwd $PWD.ProviderPath --context-hostname ([System.Net.Dns]::GetHostName()) --context-session-id $global:__RustorySessionId) -join "`n"
+    [Microsoft.PowerShell.PSConsoleReadLine]::InvokePrompt()
+    if ($selected) {
+        [Microsoft.PowerShell.PSConsoleReadLine]::Insert($selected)
+    }
+}
+"#
+    .to_string()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -217,6 +433,9 @@ mod tests {
         assert!(got.contains("bind -x '\"\\C-r\":__rustory_ctrl_r'"));
         assert!(got.contains("trap '__rustory_preexec' DEBUG"));
         assert!(got.contains("--duration-ms"));
+        assert!(got.contains("--session-id \"$__rustory_session_id\""));
+        assert!(got.contains("--context-cwd \"$PWD\""));
+        assert!(got.contains("--context-session-id \"$__rustory_session_id\""));
 
         // ensure we skip both `rr` and `rr ...`
         assert!(got.contains("case \"$cmd\" in"));
@@ -229,9 +448,72 @@ mod tests {
         assert!(got.contains("RUSTORY_HOOK_DISABLE"));
         assert!(got.contains("RUSTORY_SEARCH_LIMIT"));
         assert!(got.contains("bindkey '^R'"));
+        assert!(got.contains("--session-id \"$__rustory_session_id\""));
+        assert!(got.contains("--context-cwd \"$PWD\""));
+        assert!(got.contains("--context-session-id \"$__rustory_session_id\""));
 
         // ensure we skip both `rr` and `rr ...`
         assert!(got.contains("case \"$cmd\" in"));
         assert!(got.contains("rr|rr\\ *)"));
     }
+
+    #[test]
+    fn fish_hook_contains_events_and_ctrl_r_and_rr_filter() {
+        let got = render_hook(Shell::Fish);
+        assert!(got.contains("RUSTORY_HOOK_DISABLE"));
+        assert!(got.contains("RUSTORY_SEARCH_LIMIT"));
+        assert!(got.contains("--on-event fish_preexec"));
+        assert!(got.contains("--on-event fish_postexec"));
+        assert!(got.contains("$CMD_DURATION"));
+        assert!(got.contains("--exit-code $exit_code"));
+        assert!(got.contains("--shell \"fish\""));
+        assert!(got.contains("bind \\cr __rustory_ctrl_r"));
+        assert!(got.contains("--session-id \"$__rustory_session_id\""));
+
+        // ensure we skip both `rr` and `rr ...`
+        assert!(got.contains("case rr 'rr *'"));
+    }
+
+    #[test]
+    fn nu_hook_uses_pre_execution_and_pre_prompt() {
+        let got = render_hook(Shell::Nu);
+        assert!(got.contains("RUSTORY_HOOK_DISABLE"));
+        assert!(got.contains("RUSTORY_SEARCH_LIMIT"));
+        assert!(got.contains("upsert hooks.pre_execution"));
+        assert!(got.contains("upsert hooks.pre_prompt"));
+        assert!(got.contains("LAST_EXIT_CODE"));
+        assert!(got.contains("CMD_DURATION_MS"));
+        assert!(got.contains("keycode: char_r"));
+        assert!(got.contains("--shell \"nu\""));
+        assert!(got.contains("--session-id $env.__RUSTORY_SESSION_ID"));
+
+        // ensure we skip both `rr` and `rr ...`
+        assert!(got.contains("$cmd == \"rr\" or ($cmd | str starts-with \"rr \")"));
+    }
+
+    #[test]
+    fn pwsh_hook_uses_psreadline_add_to_history_handler() {
+        let got = render_hook(Shell::Pwsh);
+        assert!(got.contains("RUSTORY_HOOK_DISABLE"));
+        assert!(got.contains("RUSTORY_SEARCH_LIMIT"));
+        assert!(got.contains("Set-PSReadLineOption -AddToHistoryHandler"));
+        assert!(got.contains("function global:prompt"));
+        assert!(got.contains("Set-PSReadLineKeyHandler -Chord Ctrl+r"));
+        assert!(got.contains("'--shell', 'pwsh'"));
+        assert!(got.contains("'--session-id', $global:__RustorySessionId"));
+        // 기록은 프롬프트를 막지 않도록 백그라운드로 띄운다.
+        assert!(got.contains("[System.Diagnostics.Process]::Start($psi)"));
+        assert!(!got.contains("& rr record"));
+
+        // ensure we skip both `rr` and `rr ...`
+        assert!(got.contains("$cmd -ne 'rr' -and -not $cmd.StartsWith('rr ')"));
+    }
+
+    #[test]
+    fn shell_parse_accepts_all_supported_shells() {
+        for name in ["bash", "zsh", "fish", "nu", "nushell", "pwsh", "powershell"] {
+            assert!(Shell::parse(name).is_ok(), "{name}");
+        }
+        assert!(Shell::parse("tcsh").is_err());
+    }
 }
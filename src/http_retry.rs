@@ -1,4 +1,5 @@
 use anyhow::Result;
+use std::sync::Arc;
 use std::time::Duration;
 
 #[derive(Debug, Clone, Copy)]
@@ -36,9 +37,40 @@ impl RetryPolicy {
     }
 }
 
-pub fn request_with_retry<T, F>(policy: RetryPolicy, mut f: F) -> Result<T>
+pub fn request_with_retry<T, F>(policy: RetryPolicy, f: F) -> Result<T>
 where
     F: FnMut(&ureq::Agent) -> std::result::Result<T, ureq::Error>,
+{
+    request_with_retry_tls(policy, None, f)
+}
+
+/// `tls`가 있으면 기본 루트 대신 그 설정(핀 고정 CA/지문)으로 https 연결을 검증한다.
+pub fn request_with_retry_tls<T, F>(
+    policy: RetryPolicy,
+    tls: Option<&Arc<rustls::ClientConfig>>,
+    mut f: F,
+) -> Result<T>
+where
+    F: FnMut(&ureq::Agent) -> std::result::Result<T, ureq::Error>,
+{
+    request_with_retry_inner(policy, tls, |agent| Ok(f(agent)))
+}
+
+/// 요청을 만드는 단계(서명 등)가 실패할 수 있을 때 쓴다. 그 오류는 재시도하지 않고 그대로 반환한다.
+pub fn request_with_retry_fallible<T, F>(policy: RetryPolicy, f: F) -> Result<T>
+where
+    F: FnMut(&ureq::Agent) -> Result<std::result::Result<T, ureq::Error>>,
+{
+    request_with_retry_inner(policy, None, f)
+}
+
+fn request_with_retry_inner<T, F>(
+    policy: RetryPolicy,
+    tls: Option<&Arc<rustls::ClientConfig>>,
+    mut f: F,
+) -> Result<T>
+where
+    F: FnMut(&ureq::Agent) -> Result<std::result::Result<T, ureq::Error>>,
 {
     let attempts = policy.attempts.max(1);
     let mut last_err: Option<anyhow::Error> = None;
@@ -51,12 +83,15 @@ where
         );
         let read = exp_duration(policy.read_base, attempt as u32, Some(policy.read_cap));
 
-        let agent = ureq::AgentBuilder::new()
+        let mut builder = ureq::AgentBuilder::new()
             .timeout_connect(connect)
-            .timeout_read(read)
-            .build();
+            .timeout_read(read);
+        if let Some(tls) = tls {
+            builder = builder.tls_config(tls.clone());
+        }
+        let agent = builder.build();
 
-        match f(&agent) {
+        match f(&agent)? {
             Ok(v) => return Ok(v),
             Err(err) => {
                 let retryable = is_retryable_error(&err);
@@ -0,0 +1,569 @@
+use crate::history_import::HistoryRecord;
+use anyhow::{Context, Result, bail};
+use rusqlite::{Connection, OpenFlags};
+use std::path::Path;
+
+/// `rr import --from`으로 가져올 수 있는 외부 히스토리 도구.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportSource {
+    Atuin,
+    Mcfly,
+    Resh,
+    Hishtory,
+}
+
+impl ImportSource {
+    pub fn parse(value: &str) -> Result<Self> {
+        match value {
+            "atuin" => Ok(Self::Atuin),
+            "mcfly" => Ok(Self::Mcfly),
+            "resh" => Ok(Self::Resh),
+            "hishtory" => Ok(Self::Hishtory),
+            _ => bail!("unsupported import source: {value} (expected: atuin|mcfly|resh|hishtory)"),
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Atuin => "atuin",
+            Self::Mcfly => "mcfly",
+            Self::Resh => "resh",
+            Self::Hishtory => "hishtory",
+        }
+    }
+
+    pub fn default_path(self) -> &'static str {
+        match self {
+            Self::Atuin => "~/.local/share/atuin/history.db",
+            Self::Mcfly => "~/.local/share/mcfly/history.db",
+            Self::Resh => "~/.local/share/resh/history.reshjson",
+            Self::Hishtory => "~/.hishtory/.hishtory.db",
+        }
+    }
+}
+
+pub fn load_records(source: ImportSource, path: &Path) -> Result<Vec<HistoryRecord>> {
+    match source {
+        ImportSource::Atuin => read_atuin(&open_readonly(path)?),
+        ImportSource::Mcfly => read_mcfly(&open_readonly(path)?),
+        ImportSource::Hishtory => read_hishtory(&open_readonly(path)?),
+        ImportSource::Resh => {
+            let bytes = std::fs::read(path)
+                .with_context(|| format!("read resh history: {}", path.display()))?;
+            parse_resh(&String::from_utf8_lossy(&bytes))
+        }
+    }
+}
+
+fn open_readonly(path: &Path) -> Result<Connection> {
+    Connection::open_with_flags(
+        path,
+        OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+    )
+    .with_context(|| format!("open sqlite: {}", path.display()))
+}
+
+fn non_empty(value: Option<String>) -> Option<String> {
+    value.filter(|v| !v.trim().is_empty())
+}
+
+/// atuin `history` 테이블. timestamp/duration은 ns 단위이고, 값을 모르면 -1이다.
+fn read_atuin(conn: &Connection) -> Result<Vec<HistoryRecord>> {
+    let mut stmt = conn
+        .prepare(
+            r#"
+SELECT id, timestamp, duration, exit, command, cwd, session, hostname
+FROM history
+WHERE deleted_at IS NULL
+ORDER BY timestamp ASC, id ASC
+"#,
+        )
+        .context("prepare atuin history query")?;
+
+    let rows = stmt
+        .query_map([], |row| {
+            let id: String = row.get(0)?;
+            let ts_ns: i64 = row.get(1)?;
+            let duration_ns: i64 = row.get(2)?;
+            let exit: i64 = row.get(3)?;
+            let cmd: String = row.get(4)?;
+            let cwd: Option<String> = row.get(5)?;
+            let session: Option<String> = row.get(6)?;
+            let hostname: Option<String> = row.get(7)?;
+            Ok((id, ts_ns, duration_ns, exit, cmd, cwd, session, hostname))
+        })
+        .context("query atuin history")?;
+
+    let mut out = Vec::new();
+    for (i, row) in rows.enumerate() {
+        let (id, ts_ns, duration_ns, exit, cmd, cwd, session, hostname) = row?;
+        out.push(HistoryRecord {
+            source_index: i as u64,
+            ts_unix: Some(ts_ns.div_euclid(1_000_000_000)),
+            duration_ms: duration_ns.max(0) / 1_000_000,
+            cmd,
+            cwd: non_empty(cwd),
+            exit_code: (exit != -1).then(|| i32::try_from(exit).ok()).flatten(),
+            // atuin은 hostname을 "host:user" 형태로 저장한다.
+            hostname: non_empty(hostname).map(|h| {
+                h.split_once(':')
+                    .map_or(h.clone(), |(host, _)| host.to_string())
+            }),
+            session_id: non_empty(session),
+            shell: None,
+            source_id: Some(id),
+        });
+    }
+    Ok(out)
+}
+
+/// mcfly `commands` 테이블. hostname/duration은 저장하지 않는다.
+fn read_mcfly(conn: &Connection) -> Result<Vec<HistoryRecord>> {
+    let mut stmt = conn
+        .prepare(
+            r#"
+SELECT id, cmd, session_id, when_run, exit_code, dir
+FROM commands
+ORDER BY id ASC
+"#,
+        )
+        .context("prepare mcfly history query")?;
+
+    let rows = stmt
+        .query_map([], |row| {
+            let id: i64 = row.get(0)?;
+            let cmd: String = row.get(1)?;
+            let session: Option<String> = row.get(2)?;
+            let when_run: Option<i64> = row.get(3)?;
+            let exit_code: Option<i64> = row.get(4)?;
+            let dir: Option<String> = row.get(5)?;
+            Ok((id, cmd, session, when_run, exit_code, dir))
+        })
+        .context("query mcfly history")?;
+
+    let mut out = Vec::new();
+    for (i, row) in rows.enumerate() {
+        let (id, cmd, session, when_run, exit_code, dir) = row?;
+        out.push(HistoryRecord {
+            source_index: i as u64,
+            ts_unix: when_run,
+            duration_ms: 0,
+            cmd,
+            cwd: non_empty(dir),
+            exit_code: exit_code.and_then(|v| i32::try_from(v).ok()),
+            hostname: None,
+            session_id: non_empty(session),
+            shell: None,
+            source_id: Some(id.to_string()),
+        });
+    }
+    Ok(out)
+}
+
+/// hishtory `history_entries` 테이블. 시각은 Go sqlite 드라이버의 TEXT 형식이다.
+fn read_hishtory(conn: &Connection) -> Result<Vec<HistoryRecord>> {
+    let mut stmt = conn
+        .prepare(
+            r#"
+SELECT entry_id, command, current_working_directory, exit_code, hostname, start_time, end_time
+FROM history_entries
+ORDER BY start_time ASC
+"#,
+        )
+        .context("prepare hishtory query")?;
+
+    let rows = stmt
+        .query_map([], |row| {
+            let entry_id: Option<String> = row.get(0)?;
+            let cmd: String = row.get(1)?;
+            let cwd: Option<String> = row.get(2)?;
+            let exit_code: Option<i64> = row.get(3)?;
+            let hostname: Option<String> = row.get(4)?;
+            let start: Option<String> = row.get(5)?;
+            let end: Option<String> = row.get(6)?;
+            Ok((entry_id, cmd, cwd, exit_code, hostname, start, end))
+        })
+        .context("query hishtory")?;
+
+    let mut out = Vec::new();
+    for (i, row) in rows.enumerate() {
+        let (entry_id, cmd, cwd, exit_code, hostname, start, end) = row?;
+        let start_ms = start.as_deref().and_then(parse_go_datetime_ms);
+        let end_ms = end.as_deref().and_then(parse_go_datetime_ms);
+        let duration_ms = match (start_ms, end_ms) {
+            (Some(s), Some(e)) => (e - s).max(0),
+            _ => 0,
+        };
+        out.push(HistoryRecord {
+            source_index: i as u64,
+            ts_unix: start_ms.map(|ms| ms.div_euclid(1000)),
+            duration_ms,
+            cmd,
+            cwd: non_empty(cwd),
+            exit_code: exit_code.and_then(|v| i32::try_from(v).ok()),
+            hostname: non_empty(hostname),
+            session_id: None,
+            shell: None,
+            source_id: non_empty(entry_id),
+        });
+    }
+    Ok(out)
+}
+
+/// `2006-01-02 15:04:05.999999999-07:00` 형식(`T` 구분자, `Z` 오프셋 포함)을 unix ms로 변환한다.
+fn parse_go_datetime_ms(value: &str) -> Option<i64> {
+    let value = value.trim();
+    let (date, rest) = value.split_at_checked(10)?;
+    let rest = rest.strip_prefix([' ', 'T'])?;
+
+    let mut ymd = date.split('-');
+    let year: i32 = ymd.next()?.parse().ok()?;
+    let month: u8 = ymd.next()?.parse().ok()?;
+    let day: u8 = ymd.next()?.parse().ok()?;
+    let date =
+        time::Date::from_calendar_date(year, time::Month::try_from(month).ok()?, day).ok()?;
+
+    // 시각과 오프셋을 분리한다(오프셋이 없으면 UTC로 본다).
+    let offset_at = rest.find(['Z', '+', '-']).unwrap_or(rest.len());
+    let (clock, offset) = rest.split_at(offset_at);
+    let offset = offset.trim();
+
+    let (hms, frac) = clock.trim().split_once('.').unwrap_or((clock.trim(), ""));
+    let mut hms = hms.split(':');
+    let hour: u8 = hms.next()?.parse().ok()?;
+    let minute: u8 = hms.next()?.parse().ok()?;
+    let second: u8 = hms.next()?.parse().ok()?;
+    let nanos: u32 = if frac.is_empty() {
+        0
+    } else {
+        let digits: String = frac.chars().take(9).collect();
+        format!("{digits:0<9}").parse().ok()?
+    };
+    let clock = time::Time::from_hms_nano(hour, minute, second, nanos).ok()?;
+
+    let offset = match offset {
+        "" | "Z" => time::UtcOffset::UTC,
+        o => {
+            let sign: i8 = if o.starts_with('-') { -1 } else { 1 };
+            let (h, m) = o[1..].split_once(':').unwrap_or((&o[1..], "0"));
+            let h: i8 = h.parse().ok()?;
+            let m: i8 = m.parse().ok()?;
+            time::UtcOffset::from_hms(sign * h, sign * m, 0).ok()?
+        }
+    };
+
+    let ts = time::PrimitiveDateTime::new(date, clock).assume_offset(offset);
+    i64::try_from(ts.unix_timestamp_nanos() / 1_000_000).ok()
+}
+
+/// resh 히스토리(레코드당 JSON 1줄). v2(`~/.resh_history.json`)와 v3(`history.reshjson`) 필드를 모두 받는다.
+pub fn parse_resh(content: &str) -> Result<Vec<HistoryRecord>> {
+    let mut out = Vec::new();
+    for (line_no, raw) in content.lines().enumerate() {
+        let line = raw.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let v: serde_json::Value = serde_json::from_str(line)
+            .with_context(|| format!("parse resh record at line {}", line_no + 1))?;
+
+        let str_field = |keys: &[&str]| -> Option<String> {
+            keys.iter()
+                .find_map(|k| v.get(*k).and_then(|x| x.as_str()))
+                .map(str::to_string)
+                .filter(|s| !s.trim().is_empty())
+        };
+        // v3는 시각/소요 시간을 문자열("1700000000.123")로, v2는 숫자로 저장한다.
+        let num_field = |keys: &[&str]| -> Option<f64> {
+            keys.iter().find_map(|k| match v.get(*k)? {
+                serde_json::Value::Number(n) => n.as_f64(),
+                serde_json::Value::String(s) => s.trim().parse().ok(),
+                _ => None,
+            })
+        };
+
+        let Some(cmd) = str_field(&["cmdLine"]) else {
+            continue;
+        };
+
+        out.push(HistoryRecord {
+            source_index: out.len() as u64,
+            ts_unix: num_field(&["time", "realtimeBefore"]).map(|t| t.floor() as i64),
+            duration_ms: num_field(&["duration", "realtimeDuration"])
+                .map(|d| (d * 1000.0).round().max(0.0) as i64)
+                .unwrap_or(0),
+            cmd,
+            cwd: str_field(&["pwd", "realPwd"]),
+            exit_code: num_field(&["exitCode"]).map(|c| c as i32),
+            hostname: str_field(&["host", "hostname", "device"]),
+            session_id: str_field(&["sessionID", "sessionId"]),
+            shell: str_field(&["shell"]),
+            source_id: str_field(&["recordId"]),
+        });
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history_import::{ImportRequest, import_into_store};
+    use crate::storage::LocalStore;
+
+    fn fixture_db(sql: &str) -> (tempfile::TempDir, std::path::PathBuf) {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("source.db");
+        Connection::open(&path).unwrap().execute_batch(sql).unwrap();
+        (dir, path)
+    }
+
+    fn import(source: ImportSource, records: Vec<HistoryRecord>, store: &LocalStore) -> usize {
+        import_into_store(
+            store,
+            records,
+            ImportRequest {
+                source: source.as_str(),
+                default_shell: "unknown",
+                limit: None,
+                user_id: "u1",
+                device_id: "d1",
+                hostname: "fallback",
+                ignore_regex: None,
+                redactor: None,
+            },
+        )
+        .unwrap()
+        .inserted
+    }
+
+    #[test]
+    fn atuin_fixture_maps_fields_and_reimport_is_idempotent() {
+        let (_dir, path) = fixture_db(
+            r#"
+CREATE TABLE history (
+  id TEXT PRIMARY KEY,
+  timestamp INTEGER NOT NULL,
+  duration INTEGER NOT NULL,
+  exit INTEGER NOT NULL,
+  command TEXT NOT NULL,
+  cwd TEXT NOT NULL,
+  session TEXT NOT NULL,
+  hostname TEXT NOT NULL,
+  deleted_at INTEGER
+);
+INSERT INTO history VALUES ('a1', 1700000000500000000, 2500000000, 1, 'cargo test', '/work', 's-1', 'laptop:alice', NULL);
+INSERT INTO history VALUES ('a2', 1700000001000000000, -1, -1, 'ls', '/tmp', 's-1', 'laptop:alice', NULL);
+INSERT INTO history VALUES ('a3', 1700000002000000000, 0, 0, 'deleted', '/tmp', 's-1', 'laptop:alice', 1700000003000000000);
+"#,
+        );
+
+        let records = load_records(ImportSource::Atuin, &path).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(
+            records[0],
+            HistoryRecord {
+                source_index: 0,
+                ts_unix: Some(1700000000),
+                duration_ms: 2500,
+                cmd: "cargo test".to_string(),
+                cwd: Some("/work".to_string()),
+                exit_code: Some(1),
+                hostname: Some("laptop".to_string()),
+                session_id: Some("s-1".to_string()),
+                shell: None,
+                source_id: Some("a1".to_string()),
+            }
+        );
+        assert_eq!(records[1].exit_code, None);
+        assert_eq!(records[1].duration_ms, 0);
+
+        let store = LocalStore::open(":memory:").unwrap();
+        assert_eq!(import(ImportSource::Atuin, records.clone(), &store), 2);
+        assert_eq!(import(ImportSource::Atuin, records, &store), 0);
+
+        let got = store.list_recent(10).unwrap();
+        let e = got.iter().find(|e| e.cmd == "cargo test").unwrap();
+        assert_eq!(e.cwd, "/work");
+        assert_eq!(e.exit_code, 1);
+        assert_eq!(e.duration_ms, 2500);
+        assert_eq!(e.hostname, "laptop");
+        assert_eq!(e.session_id.as_deref(), Some("s-1"));
+        assert_eq!(e.shell, "unknown");
+        assert_eq!(
+            e.entry_id,
+            crate::core::import_entry_id_from_source("u1", "d1", "atuin", "a1")
+        );
+    }
+
+    #[test]
+    fn mcfly_fixture_maps_fields() {
+        let (_dir, path) = fixture_db(
+            r#"
+CREATE TABLE commands (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  cmd TEXT NOT NULL,
+  cmd_tpl TEXT,
+  session_id TEXT NOT NULL,
+  when_run INTEGER NOT NULL,
+  exit_code INTEGER NOT NULL,
+  selected INTEGER NOT NULL,
+  dir TEXT,
+  old_dir TEXT
+);
+INSERT INTO commands (cmd, cmd_tpl, session_id, when_run, exit_code, selected, dir, old_dir)
+VALUES ('make', 'make', 'm-1', 1700000000, 2, 0, '/src', '/src');
+"#,
+        );
+
+        let records = load_records(ImportSource::Mcfly, &path).unwrap();
+        assert_eq!(
+            records,
+            vec![HistoryRecord {
+                source_index: 0,
+                ts_unix: Some(1700000000),
+                duration_ms: 0,
+                cmd: "make".to_string(),
+                cwd: Some("/src".to_string()),
+                exit_code: Some(2),
+                hostname: None,
+                session_id: Some("m-1".to_string()),
+                shell: None,
+                source_id: Some("1".to_string()),
+            }]
+        );
+
+        let store = LocalStore::open(":memory:").unwrap();
+        assert_eq!(import(ImportSource::Mcfly, records.clone(), &store), 1);
+        assert_eq!(import(ImportSource::Mcfly, records, &store), 0);
+        assert_eq!(store.list_recent(1).unwrap()[0].hostname, "fallback");
+    }
+
+    #[test]
+    fn hishtory_fixture_maps_fields() {
+        let (_dir, path) = fixture_db(
+            r#"
+CREATE TABLE history_entries (
+  local_username TEXT,
+  hostname TEXT,
+  command TEXT,
+  current_working_directory TEXT,
+  home_directory TEXT,
+  exit_code INTEGER,
+  start_time DATETIME,
+  end_time DATETIME,
+  device_id TEXT,
+  entry_id TEXT
+);
+INSERT INTO history_entries VALUES
+  ('alice', 'desk', 'go test ./...', '/repo', '/home/alice', 0,
+   '2023-11-14 22:13:20.25+00:00', '2023-11-14 22:13:21.75+00:00', 'dev-x', 'h-1'),
+  ('alice', 'desk', 'false', '/repo', '/home/alice', 1,
+   '2023-11-15T07:13:20-09:00', '2023-11-15T07:13:20-09:00', 'dev-x', 'h-2');
+"#,
+        );
+
+        let records = load_records(ImportSource::Hishtory, &path).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(
+            records[0],
+            HistoryRecord {
+                source_index: 0,
+                ts_unix: Some(1700000000),
+                duration_ms: 1500,
+                cmd: "go test ./...".to_string(),
+                cwd: Some("/repo".to_string()),
+                exit_code: Some(0),
+                hostname: Some("desk".to_string()),
+                session_id: None,
+                shell: None,
+                source_id: Some("h-1".to_string()),
+            }
+        );
+        assert_eq!(records[1].ts_unix, Some(1700064800));
+        assert_eq!(records[1].exit_code, Some(1));
+
+        let store = LocalStore::open(":memory:").unwrap();
+        assert_eq!(import(ImportSource::Hishtory, records.clone(), &store), 2);
+        assert_eq!(import(ImportSource::Hishtory, records, &store), 0);
+    }
+
+    #[test]
+    fn resh_fixture_accepts_v2_and_v3_records() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.reshjson");
+        std::fs::write(
+            &path,
+            concat!(
+                r#"{"cmdLine":"git push","exitCode":0,"shell":"zsh","sessionID":"r-1","pwd":"/repo","host":"laptop","time":"1700000000.750","duration":"1.25"}"#,
+                "\n",
+                r#"{"cmdLine":"make","exitCode":2,"shell":"bash","sessionId":"r-2","pwd":"/src","hostname":"desk","realtimeBefore":1700000100.1,"realtimeDuration":0.5}"#,
+                "\n",
+                r#"{"exitCode":0}"#,
+                "\n",
+            ),
+        )
+        .unwrap();
+
+        let records = load_records(ImportSource::Resh, &path).unwrap();
+        assert_eq!(
+            records,
+            vec![
+                HistoryRecord {
+                    source_index: 0,
+                    ts_unix: Some(1700000000),
+                    duration_ms: 1250,
+                    cmd: "git push".to_string(),
+                    cwd: Some("/repo".to_string()),
+                    exit_code: Some(0),
+                    hostname: Some("laptop".to_string()),
+                    session_id: Some("r-1".to_string()),
+                    shell: Some("zsh".to_string()),
+                    source_id: None,
+                },
+                HistoryRecord {
+                    source_index: 1,
+                    ts_unix: Some(1700000100),
+                    duration_ms: 500,
+                    cmd: "make".to_string(),
+                    cwd: Some("/src".to_string()),
+                    exit_code: Some(2),
+                    hostname: Some("desk".to_string()),
+                    session_id: Some("r-2".to_string()),
+                    shell: Some("bash".to_string()),
+                    source_id: None,
+                },
+            ]
+        );
+
+        let store = LocalStore::open(":memory:").unwrap();
+        assert_eq!(import(ImportSource::Resh, records.clone(), &store), 2);
+        assert_eq!(import(ImportSource::Resh, records, &store), 0);
+        let got = store.list_recent(10).unwrap();
+        assert!(got.iter().any(|e| e.shell == "zsh" && e.cmd == "git push"));
+    }
+
+    #[test]
+    fn parse_go_datetime_handles_offsets_and_fractions() {
+        assert_eq!(
+            parse_go_datetime_ms("2023-11-14 22:13:20+00:00"),
+            Some(1_700_000_000_000)
+        );
+        assert_eq!(
+            parse_go_datetime_ms("2023-11-14T22:13:20.123456789Z"),
+            Some(1_700_000_000_123)
+        );
+        assert_eq!(
+            parse_go_datetime_ms("2023-11-15 07:43:20+09:30"),
+            Some(1_700_000_000_000)
+        );
+        assert_eq!(parse_go_datetime_ms("yesterday"), None);
+    }
+
+    #[test]
+    fn import_source_parse() {
+        for s in ["atuin", "mcfly", "resh", "hishtory"] {
+            assert_eq!(ImportSource::parse(s).unwrap().as_str(), s);
+        }
+        assert!(ImportSource::parse("zsh").is_err());
+    }
+}
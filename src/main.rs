@@ -1,11 +1,20 @@
+mod agent;
 mod cli;
 mod config;
+mod content_key;
 mod core;
+mod entry_format;
+mod export;
 mod history_import;
 mod hook;
 mod http_retry;
+mod import_sources;
 mod p2p;
 mod p2p_codec;
+mod pair;
+mod picker;
+mod redact;
+mod scheduler;
 mod search;
 mod storage;
 mod sync;
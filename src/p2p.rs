@@ -1,4 +1,5 @@
-use crate::storage::{LocalStore, PeerBookPeer, PullBatch};
+use crate::content_key::ContentKey;
+use crate::storage::{LocalStore, PeerAcl, PeerBookPeer, PullBatch};
 use anyhow::{Context, Result};
 use futures::StreamExt;
 use libp2p::core::transport::choice::OrTransport;
@@ -8,6 +9,7 @@ use libp2p::swarm::{SwarmEvent, dial_opts::DialOpts};
 use libp2p::{Multiaddr, PeerId, StreamProtocol, Swarm, Transport};
 use libp2p_request_response::ProtocolSupport;
 use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::Duration;
 use time::OffsetDateTime;
 
@@ -15,6 +17,8 @@ const SYNC_PULL_PROTOCOL_PLAIN: &str = "/rustory/sync-pull/1.0.0";
 const SYNC_PULL_PROTOCOL_ZSTD: &str = "/rustory/sync-pull/1.0.1";
 const ENTRIES_PUSH_PROTOCOL_PLAIN: &str = "/rustory/entries-push/1.0.0";
 const ENTRIES_PUSH_PROTOCOL_ZSTD: &str = "/rustory/entries-push/1.0.1";
+const SYNC_NOTIFY_PROTOCOL: &str = "/rustory/sync-notify/1.0.0";
+const VERSION_VECTOR_PROTOCOL: &str = "/rustory/version-vector/1.0.0";
 
 // request-response는 stream EOF까지 읽기 때문에, 크기 상한을 너무 작게 잡으면 “잘린 JSON 파싱 실패”로 보이기 쉽다.
 // PoC/MVP 범위에서는 "상한을 넉넉히" + "명확한 too-large 에러"를 우선한다.
@@ -22,6 +26,10 @@ const PULL_REQ_MAX_BYTES: u64 = 64 * 1024;
 const PULL_RESP_MAX_BYTES: u64 = 32 * 1024 * 1024;
 const PUSH_REQ_MAX_BYTES: u64 = 16 * 1024 * 1024;
 const PUSH_RESP_MAX_BYTES: u64 = 64 * 1024;
+const NOTIFY_REQ_MAX_BYTES: u64 = 4 * 1024;
+const NOTIFY_RESP_MAX_BYTES: u64 = 4 * 1024;
+const VERSION_VECTOR_REQ_MAX_BYTES: u64 = 4 * 1024;
+const VERSION_VECTOR_RESP_MAX_BYTES: u64 = 1024 * 1024;
 
 // zstd 프로토콜에서는 "wire 상한"과 별개로 decode(압축 해제 후 JSON bytes) 상한을 둔다.
 const DECODED_MAX_MULTIPLIER: u64 = 4;
@@ -36,6 +44,28 @@ const PUSH_RESP_DECODED_MAX_BYTES: u64 = PUSH_RESP_MAX_BYTES * DECODED_MAX_MULTI
 // 따라서 "충분히 큰 값"으로 두고, 실제 attempt timeout은 클라이언트 로직에서 결정한다.
 const REQUEST_RESPONSE_INTERNAL_TIMEOUT: Duration = Duration::from_secs(60 * 60);
 
+// p2p-sync가 mDNS 응답을 기다리고 PSK 연결로 확인하는 최대 시간. 같은 LAN이면 보통 1초 안에 끝난다.
+const MDNS_DISCOVERY_WINDOW: Duration = Duration::from_secs(3);
+// 찾은 피어가 모두 확인된 뒤 늦게 오는 다른 응답을 기다리는 시간. 이 동안 변화가 없으면 바로 끝낸다.
+const MDNS_SETTLE: Duration = Duration::from_millis(300);
+// dial 결과를 받지 못한 피어는 이 시간이 지나면 잊는다(다시 응답하면 다시 dial한다).
+const MDNS_PENDING_TTL: Duration = Duration::from_secs(30);
+// 확정한 피어는 이 시간이 지나면 다시 확인해 peer_book의 주소/last_seen을 갱신한다.
+const MDNS_CONFIRMED_TTL: Duration = Duration::from_secs(10 * 60);
+// LAN의 아무나 mDNS 응답을 보낼 수 있으므로 확인 중인 피어 수와 피어당 주소 수를 제한한다.
+const MDNS_MAX_PENDING: usize = 64;
+const MDNS_MAX_ADDRS_PER_PEER: usize = 8;
+
+// p2p-serve가 보류 중인 notify 요청에 대해 새 엔트리를 확인하는 주기. 다른 프로세스(`rr record`,
+// agent)가 DB에 쓴 것도 보이도록 DB를 직접 확인한다.
+const NOTIFY_POLL_INTERVAL: Duration = Duration::from_millis(250);
+// notify 요청 하나를 보류하는 최대 시간. 지나면 changed=false로 답하고 클라이언트가 다시 구독한다.
+const NOTIFY_MAX_WAIT: Duration = Duration::from_secs(300);
+// 서버가 보류 시간이 끝나도 답하지 않을 때(응답 유실 등) 클라이언트가 다시 구독하기까지의 여유.
+const NOTIFY_RESPONSE_GRACE: Duration = Duration::from_secs(10);
+// watch 대기 중 중지 신호/라운드 기한/로컬 새 기록을 확인하는 주기.
+const WATCH_TICK: Duration = Duration::from_millis(250);
+
 #[derive(Clone)]
 pub struct ServeConfig {
     pub identity: libp2p::identity::Keypair,
@@ -44,6 +74,11 @@ pub struct ServeConfig {
     pub trackers: Vec<String>,
     pub tracker_token: Option<String>,
     pub meta: crate::tracker::PeerMeta,
+    pub content_key: Option<ContentKey>,
+    /// LAN에서 mDNS로 자신을 알리고, 찾은 피어를 peer_book에 기록한다.
+    pub mdns: bool,
+    /// 30초마다 tracker에 다시 등록한다. agent의 `tracker_register` 작업을 켰으면 끈다.
+    pub periodic_register: bool,
 }
 
 #[derive(Clone)]
@@ -55,6 +90,14 @@ pub struct SyncConfig {
     pub user_id: Option<String>,
     pub device_id: Option<String>,
     pub request_retry_policy: RequestRetryPolicy,
+    pub content_key: Option<ContentKey>,
+    /// 디바이스 identity. 서버의 허용 목록(peer_acl)에 등록된 PeerId로 접속할 때 필요하다.
+    /// `None`이면 일회용 키를 쓴다.
+    pub identity: Option<libp2p::identity::Keypair>,
+    /// `--peers`가 없을 때 tracker와 함께 mDNS로 LAN의 피어도 찾는다.
+    pub mdns: bool,
+    /// push 때 다른 디바이스의 엔트리도 버전 벡터로 협상해 전달한다(다중 홉 복제). `user_id`가 필요하다.
+    pub gossip: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -76,6 +119,16 @@ impl Default for RequestRetryPolicy {
     }
 }
 
+#[derive(Debug, Clone)]
+pub struct WatchConfig {
+    /// 알림이 없어도 피어를 다시 찾고 전체 동기화를 도는 주기.
+    pub interval: Duration,
+    /// 알림을 받은 뒤 pull하기 전에 기다리는 시간. 연달아 들어오는 기록을 한 번에 가져온다.
+    pub debounce: Duration,
+    /// `false`면 sync-notify를 쓰지 않고 `interval`마다 polling만 한다.
+    pub notify: bool,
+}
+
 #[derive(Clone)]
 pub struct RelayServeConfig {
     pub identity: libp2p::identity::Keypair,
@@ -86,17 +139,30 @@ pub struct RelayServeConfig {
 struct SyncPull {
     cursor: i64,
     limit: usize,
+    /// 클라이언트가 이미 본 것. 있으면 서버는 빠진 것만 보내고 `cursor`는 구버전 행에만 쓴다.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    heads: Option<crate::core::SyncHeads>,
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 struct SyncBatch {
     entries: Vec<crate::core::Entry>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tombstones: Vec<crate::core::Tombstone>,
     next_cursor: Option<i64>,
+    /// `heads`로 답했는지. 구버전 서버는 이 필드 없이 커서 기준으로 답한다.
+    #[serde(default)]
+    by_heads: bool,
+    /// 서버가 요청을 거부한 이유(허용 목록 밖의 피어 등).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 struct EntriesPush {
     entries: Vec<crate::core::Entry>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tombstones: Vec<crate::core::Tombstone>,
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -104,6 +170,40 @@ struct PushAck {
     ok: bool,
     inserted: Option<usize>,
     ignored: Option<usize>,
+    #[serde(default)]
+    deleted: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    /// 허용 목록에 맞지 않아 건너뛴 엔트리/tombstone 수. 나머지는 그대로 반영한다.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rejected: Option<usize>,
+}
+
+/// `cursor` 이후에 이 피어가 pull할 수 있는 것이 생기면 알려 달라는 요청(long-poll).
+/// 서버는 보일 것이 생기거나 `wait_ms`(최대 `NOTIFY_MAX_WAIT`)가 지나면 답한다.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+struct NotifyWatch {
+    cursor: i64,
+    wait_ms: u64,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+struct NotifyHead {
+    /// `true`면 `cursor` 이후에 pull할 엔트리/tombstone이 있다. `false`는 보류 시간이 끝났다는 뜻이다.
+    changed: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// 서버의 버전 벡터(origin별 최대 `origin_seq`)를 묻는 요청. gossip push가 보낼 것을 고를 때 쓴다.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+struct VersionVectorQuery {}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+struct VersionVectorReply {
+    heads: crate::core::VersionVector,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
 }
 
 #[derive(libp2p::swarm::NetworkBehaviour)]
@@ -115,6 +215,12 @@ struct RustoryBehaviour {
     ping: libp2p::ping::Behaviour,
     sync: libp2p_request_response::Behaviour<crate::p2p_codec::JsonCodec<SyncPull, SyncBatch>>,
     push: libp2p_request_response::Behaviour<crate::p2p_codec::JsonCodec<EntriesPush, PushAck>>,
+    notify:
+        libp2p_request_response::Behaviour<crate::p2p_codec::JsonCodec<NotifyWatch, NotifyHead>>,
+    version_vector: libp2p_request_response::Behaviour<
+        crate::p2p_codec::JsonCodec<VersionVectorQuery, VersionVectorReply>,
+    >,
+    mdns: libp2p::swarm::behaviour::toggle::Toggle<libp2p::mdns::tokio::Behaviour>,
 }
 
 #[derive(libp2p::swarm::NetworkBehaviour)]
@@ -127,12 +233,13 @@ struct RelayServerBehaviour {
 
 fn build_rustory_swarm(psk: libp2p::pnet::PreSharedKey) -> Result<Swarm<RustoryBehaviour>> {
     let identity = libp2p::identity::Keypair::generate_ed25519();
-    build_rustory_swarm_with_identity(identity, psk)
+    build_rustory_swarm_with_identity(identity, psk, false)
 }
 
 fn build_rustory_swarm_with_identity(
     identity: libp2p::identity::Keypair,
     psk: libp2p::pnet::PreSharedKey,
+    mdns: bool,
 ) -> Result<Swarm<RustoryBehaviour>> {
     let local_public_key = identity.public();
     let local_peer_id = local_public_key.to_peer_id();
@@ -179,6 +286,38 @@ fn build_rustory_swarm_with_identity(
     let push_rr =
         libp2p_request_response::Behaviour::with_codec(push_codec, push_protocols, push_cfg);
 
+    // 메시지가 작아서 압축 버전은 두지 않는다. 구버전 피어는 UnsupportedProtocols로 답하고,
+    // 클라이언트는 interval polling으로 돌아간다.
+    let notify_cfg = libp2p_request_response::Config::default()
+        .with_request_timeout(REQUEST_RESPONSE_INTERNAL_TIMEOUT);
+    let notify_codec = crate::p2p_codec::JsonCodec::<NotifyWatch, NotifyHead>::new(
+        NOTIFY_REQ_MAX_BYTES,
+        NOTIFY_RESP_MAX_BYTES,
+    );
+    let notify_rr = libp2p_request_response::Behaviour::with_codec(
+        notify_codec,
+        [(
+            StreamProtocol::new(SYNC_NOTIFY_PROTOCOL),
+            ProtocolSupport::Full,
+        )],
+        notify_cfg,
+    );
+
+    let vv_cfg = libp2p_request_response::Config::default()
+        .with_request_timeout(REQUEST_RESPONSE_INTERNAL_TIMEOUT);
+    let vv_codec = crate::p2p_codec::JsonCodec::<VersionVectorQuery, VersionVectorReply>::new(
+        VERSION_VECTOR_REQ_MAX_BYTES,
+        VERSION_VECTOR_RESP_MAX_BYTES,
+    );
+    let vv_rr = libp2p_request_response::Behaviour::with_codec(
+        vv_codec,
+        [(
+            StreamProtocol::new(VERSION_VECTOR_PROTOCOL),
+            ProtocolSupport::Full,
+        )],
+        vv_cfg,
+    );
+
     let (relay_transport, relay_behaviour) = libp2p::relay::client::new(local_peer_id);
     let tcp_transport = libp2p::tcp::tokio::Transport::default();
     let transport = OrTransport::new(relay_transport, tcp_transport);
@@ -197,6 +336,15 @@ fn build_rustory_swarm_with_identity(
     let identify_cfg = libp2p::identify::Config::new("rustory/0.1.0".to_string(), local_public_key)
         .with_agent_version(format!("rustory/{}", env!("CARGO_PKG_VERSION")));
 
+    let mdns = if mdns {
+        Some(
+            libp2p::mdns::tokio::Behaviour::new(libp2p::mdns::Config::default(), local_peer_id)
+                .context("mdns behaviour")?,
+        )
+    } else {
+        None
+    };
+
     let behaviour = RustoryBehaviour {
         relay: relay_behaviour,
         identify: libp2p::identify::Behaviour::new(identify_cfg),
@@ -204,6 +352,9 @@ fn build_rustory_swarm_with_identity(
         ping: libp2p::ping::Behaviour::new(libp2p::ping::Config::new()),
         sync: rr,
         push: push_rr,
+        notify: notify_rr,
+        version_vector: vv_rr,
+        mdns: mdns.into(),
     };
 
     Ok(Swarm::new(
@@ -317,10 +468,14 @@ async fn serve_async(listen: Multiaddr, db_path: &str, cfg: ServeConfig) -> Resu
         trackers,
         tracker_token,
         meta,
+        content_key,
+        mdns,
+        periodic_register,
     } = cfg;
 
-    let store = LocalStore::open(db_path)?;
-    let mut swarm = build_rustory_swarm_with_identity(identity, psk)?;
+    let store = LocalStore::open(db_path)?.with_content_key(content_key);
+    let tracker_identity = identity.clone();
+    let mut swarm = build_rustory_swarm_with_identity(identity, psk, mdns)?;
 
     swarm.listen_on(listen).context("listen_on")?;
 
@@ -333,16 +488,37 @@ async fn serve_async(listen: Multiaddr, db_path: &str, cfg: ServeConfig) -> Resu
 
     let trackers = trackers
         .into_iter()
-        .map(|base_url| crate::tracker::TrackerClient::new(base_url, tracker_token.clone()))
+        .map(|base_url| {
+            crate::tracker::TrackerClient::new(base_url, tracker_token.clone())
+                .with_identity(tracker_identity.clone())
+        })
         .collect::<Vec<_>>();
 
     let mut known_addrs: HashSet<String> = HashSet::new();
+    let mut mdns_peers = MdnsPeers::default();
     let mut next_register = tokio::time::interval(Duration::from_secs(30));
+    let mut notify_waiters: Vec<
+        NotifyWaiter<libp2p_request_response::ResponseChannel<NotifyHead>>,
+    > = Vec::new();
+    let mut notify_poll = tokio::time::interval(NOTIFY_POLL_INTERVAL);
+    notify_poll.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
 
     loop {
         tokio::select! {
+            _ = notify_poll.tick(), if !notify_waiters.is_empty() => {
+                // 연결이 끊긴 구독은 답할 곳이 없으므로 버린다.
+                notify_waiters.retain(|w| w.channel.is_open());
+                for (channel, resp) in
+                    take_ready_notify_waiters(&store, &mut notify_waiters, tokio::time::Instant::now())
+                {
+                    let _ = swarm.behaviour_mut().notify.send_response(channel, resp);
+                }
+            }
             _ = next_register.tick() => {
-                if !trackers.is_empty() && !known_addrs.is_empty() {
+                if !known_addrs.is_empty() {
+                    record_local_advert(&store, local_peer_id, &known_addrs);
+                }
+                if periodic_register && !trackers.is_empty() && !known_addrs.is_empty() {
                     spawn_register_all(trackers.clone(), local_peer_id, known_addrs.iter().cloned().collect(), meta.clone());
                 }
             }
@@ -352,6 +528,7 @@ async fn serve_async(listen: Multiaddr, db_path: &str, cfg: ServeConfig) -> Resu
                         let full = ensure_p2p_suffix(address, local_peer_id);
                         println!("p2p listen: {}", full);
                         known_addrs.insert(full.to_string());
+                        record_local_advert(&store, local_peer_id, &known_addrs);
 
                         // 주소를 1개 이상 확보한 시점에 tracker에 즉시 등록한다.
                         if !trackers.is_empty() {
@@ -399,13 +576,9 @@ async fn serve_async(listen: Multiaddr, db_path: &str, cfg: ServeConfig) -> Resu
                         }
                     }
                     SwarmEvent::Behaviour(RustoryBehaviourEvent::Sync(event)) => match event {
-                        libp2p_request_response::Event::Message { message, .. } => match message {
+                        libp2p_request_response::Event::Message { peer, message, .. } => match message {
                             libp2p_request_response::Message::Request { request, channel, .. } => {
-                                let batch = store.pull_since_cursor(request.cursor, request.limit)?;
-                                let resp = SyncBatch {
-                                    entries: batch.entries,
-                                    next_cursor: batch.next_cursor,
-                                };
+                                let resp = handle_sync_pull(&store, &peer, &request)?;
                                 let _ = swarm.behaviour_mut().sync.send_response(channel, resp);
                             }
                             libp2p_request_response::Message::Response { .. } => {}
@@ -415,24 +588,9 @@ async fn serve_async(listen: Multiaddr, db_path: &str, cfg: ServeConfig) -> Resu
                         libp2p_request_response::Event::ResponseSent { .. } => {}
                     },
                     SwarmEvent::Behaviour(RustoryBehaviourEvent::Push(event)) => match event {
-                        libp2p_request_response::Event::Message { message, .. } => match message {
+                        libp2p_request_response::Event::Message { peer, message, .. } => match message {
                             libp2p_request_response::Message::Request { request, channel, .. } => {
-                                let resp = match store.insert_entries_with_stats(&request.entries) {
-                                    Ok(stats) => PushAck {
-                                        ok: true,
-                                        inserted: Some(stats.inserted),
-                                        ignored: Some(stats.ignored),
-                                    },
-                                    Err(err) => {
-                                        eprintln!("warn: p2p push insert failed: {err:#}");
-                                        PushAck {
-                                            ok: false,
-                                            inserted: None,
-                                            ignored: None,
-                                        }
-                                    }
-                                };
-
+                                let resp = handle_entries_push(&store, &peer, &request);
                                 let _ = swarm.behaviour_mut().push.send_response(channel, resp);
                             }
                             libp2p_request_response::Message::Response { .. } => {}
@@ -441,6 +599,40 @@ async fn serve_async(listen: Multiaddr, db_path: &str, cfg: ServeConfig) -> Resu
                         libp2p_request_response::Event::InboundFailure { .. } => {}
                         libp2p_request_response::Event::ResponseSent { .. } => {}
                     },
+                    SwarmEvent::Behaviour(RustoryBehaviourEvent::VersionVector(event)) => match event {
+                        libp2p_request_response::Event::Message { peer, message, .. } => match message {
+                            libp2p_request_response::Message::Request { channel, .. } => {
+                                let resp = handle_version_vector(&store, &peer);
+                                let _ = swarm.behaviour_mut().version_vector.send_response(channel, resp);
+                            }
+                            libp2p_request_response::Message::Response { .. } => {}
+                        },
+                        libp2p_request_response::Event::OutboundFailure { .. } => {}
+                        libp2p_request_response::Event::InboundFailure { .. } => {}
+                        libp2p_request_response::Event::ResponseSent { .. } => {}
+                    },
+                    SwarmEvent::Behaviour(RustoryBehaviourEvent::Notify(event)) => match event {
+                        libp2p_request_response::Event::Message { peer, message, .. } => match message {
+                            libp2p_request_response::Message::Request { request, channel, .. } => {
+                                let wait = Duration::from_millis(request.wait_ms).min(NOTIFY_MAX_WAIT);
+                                let now = tokio::time::Instant::now();
+                                notify_waiters.push(NotifyWaiter {
+                                    peer,
+                                    cursor: request.cursor,
+                                    deadline: now + wait,
+                                    channel,
+                                });
+                                // 이미 보일 것이 있으면 다음 poll까지 기다리지 않고 바로 답한다.
+                                for (channel, resp) in take_ready_notify_waiters(&store, &mut notify_waiters, now) {
+                                    let _ = swarm.behaviour_mut().notify.send_response(channel, resp);
+                                }
+                            }
+                            libp2p_request_response::Message::Response { .. } => {}
+                        },
+                        libp2p_request_response::Event::OutboundFailure { .. } => {}
+                        libp2p_request_response::Event::InboundFailure { .. } => {}
+                        libp2p_request_response::Event::ResponseSent { .. } => {}
+                    },
                     SwarmEvent::Behaviour(RustoryBehaviourEvent::Dcutr(event)) => {
                         match &event.result {
                             Ok(connection_id) => {
@@ -457,6 +649,25 @@ async fn serve_async(listen: Multiaddr, db_path: &str, cfg: ServeConfig) -> Resu
                             }
                         }
                     }
+                    SwarmEvent::Behaviour(RustoryBehaviourEvent::Mdns(
+                        libp2p::mdns::Event::Discovered(list),
+                    )) => {
+                        let now = std::time::Instant::now();
+                        for (peer_id, addrs) in mdns_peers.note_discovered(local_peer_id, list, now) {
+                            // 이미 연결된 피어(다시 확인할 때)는 dial하지 않고 바로 확정한다.
+                            if swarm.is_connected(&peer_id) {
+                                confirm_mdns_peer(&store, &mut mdns_peers, peer_id, now);
+                            } else if swarm.dial(DialOpts::peer_id(peer_id).addresses(addrs).build()).is_err() {
+                                mdns_peers.dial_failed(peer_id);
+                            }
+                        }
+                    }
+                    SwarmEvent::ConnectionEstablished { peer_id, .. } => {
+                        confirm_mdns_peer(&store, &mut mdns_peers, peer_id, std::time::Instant::now());
+                    }
+                    SwarmEvent::OutgoingConnectionError { peer_id: Some(peer_id), .. } => {
+                        mdns_peers.dial_failed(peer_id);
+                    }
                     _ => {}
                 }
             }
@@ -464,6 +675,119 @@ async fn serve_async(listen: Multiaddr, db_path: &str, cfg: ServeConfig) -> Resu
     }
 }
 
+fn confirm_mdns_peer(
+    store: &LocalStore,
+    peers: &mut MdnsPeers,
+    peer_id: PeerId,
+    now: std::time::Instant,
+) {
+    if let Some(addrs) = peers.confirm(peer_id, now) {
+        eprintln!("mdns: peer confirmed: {peer_id}");
+        if let Err(err) = store.upsert_peer_book(&mdns_peer_book_entry(peer_id, &addrs)) {
+            eprintln!("warn: mdns peer_book upsert failed: {peer_id}: {err:#}");
+        }
+    }
+}
+
+/// mDNS로 찾은 피어. mDNS 응답은 LAN의 누구나 보낼 수 있으므로, 같은 PSK로 연결이
+/// 맺어진 뒤(`ConnectionEstablished`)에만 확정한다.
+/// 오래 도는 p2p-serve에서도 커지지 않도록 보류/확정 항목은 TTL이 지나면 잊고, 보류 수는 제한한다.
+#[derive(Debug, Default)]
+struct MdnsPeers {
+    pending: HashMap<PeerId, (std::time::Instant, Vec<Multiaddr>)>,
+    confirmed: HashMap<PeerId, (std::time::Instant, Vec<Multiaddr>)>,
+}
+
+impl MdnsPeers {
+    /// 새로 찾은(아직 dial하지 않은) 피어와 주소를 돌려준다. 자기 자신과 아직 유효하게 확정된 피어는 뺀다.
+    fn note_discovered(
+        &mut self,
+        local_peer_id: PeerId,
+        list: Vec<(PeerId, Multiaddr)>,
+        now: std::time::Instant,
+    ) -> Vec<(PeerId, Vec<Multiaddr>)> {
+        self.expire(now);
+        let mut fresh: Vec<PeerId> = Vec::new();
+        for (peer_id, addr) in list {
+            if peer_id == local_peer_id || self.confirmed.contains_key(&peer_id) {
+                continue;
+            }
+            if !self.pending.contains_key(&peer_id) && self.pending.len() >= MDNS_MAX_PENDING {
+                continue;
+            }
+            let (_, addrs) = self.pending.entry(peer_id).or_insert((now, Vec::new()));
+            if addrs.is_empty() && !fresh.contains(&peer_id) {
+                fresh.push(peer_id);
+            }
+            if !addrs.contains(&addr) && addrs.len() < MDNS_MAX_ADDRS_PER_PEER {
+                addrs.push(addr);
+            }
+        }
+        fresh
+            .into_iter()
+            .map(|peer_id| (peer_id, self.pending[&peer_id].1.clone()))
+            .collect()
+    }
+
+    fn confirm(&mut self, peer_id: PeerId, now: std::time::Instant) -> Option<Vec<Multiaddr>> {
+        let (_, addrs) = self.pending.remove(&peer_id)?;
+        self.confirmed.insert(peer_id, (now, addrs.clone()));
+        Some(addrs)
+    }
+
+    /// 연결하지 못한 피어(PSK 불일치 등)는 보류 목록에서 뺀다.
+    fn dial_failed(&mut self, peer_id: PeerId) {
+        self.pending.remove(&peer_id);
+    }
+
+    fn expire(&mut self, now: std::time::Instant) {
+        self.pending
+            .retain(|_, (at, _)| now.saturating_duration_since(*at) < MDNS_PENDING_TTL);
+        self.confirmed
+            .retain(|_, (at, _)| now.saturating_duration_since(*at) < MDNS_CONFIRMED_TTL);
+    }
+
+    /// 하나 이상 확정했고 확인 중인 피어가 없다.
+    fn is_settled(&self) -> bool {
+        !self.confirmed.is_empty() && self.pending.is_empty()
+    }
+
+    fn into_confirmed(self) -> HashMap<PeerId, Vec<Multiaddr>> {
+        self.confirmed
+            .into_iter()
+            .map(|(peer_id, (_, addrs))| (peer_id, addrs))
+            .collect()
+    }
+}
+
+fn mdns_peer_book_entry(peer_id: PeerId, addrs: &[Multiaddr]) -> PeerBookPeer {
+    PeerBookPeer {
+        peer_id: peer_id.to_string(),
+        addrs: addrs
+            .iter()
+            .map(|addr| ensure_p2p_suffix(addr.clone(), peer_id).to_string())
+            .collect(),
+        // mDNS는 user/device를 알려주지 않는다. 기존 값은 peer_book이 유지한다.
+        user_id: None,
+        device_id: None,
+        last_seen_unix: OffsetDateTime::now_utc().unix_timestamp(),
+    }
+}
+
+/// `rr agent`의 tracker 재등록 작업이 읽을 수 있게 현재 주소를 남긴다(갱신 시각이 곧 생존 신호).
+fn record_local_advert(store: &LocalStore, local_peer_id: PeerId, known_addrs: &HashSet<String>) {
+    let mut addrs: Vec<String> = known_addrs.iter().cloned().collect();
+    addrs.sort();
+    let advert = crate::storage::LocalAdvert {
+        peer_id: local_peer_id.to_string(),
+        addrs,
+        updated_at_unix: OffsetDateTime::now_utc().unix_timestamp(),
+    };
+    if let Err(err) = store.set_local_advert(&advert) {
+        eprintln!("warn: record local advert failed: {err:#}");
+    }
+}
+
 fn spawn_register_all(
     trackers: Vec<crate::tracker::TrackerClient>,
     local_peer_id: PeerId,
@@ -476,6 +800,8 @@ fn spawn_register_all(
         peer_id,
         addrs,
         meta: Some(meta),
+        timestamp_ms: None,
+        signature: None,
     };
 
     drop(tokio::task::spawn_blocking(move || {
@@ -513,6 +839,273 @@ fn dialable_tracker_addr_from_external_candidate(
     Some(ensure_p2p_suffix(addr, peer_id).to_string())
 }
 
+/// 서버가 요청한 피어에게 허용하는 범위.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum PeerAccess {
+    /// 허용 목록(peer_acl)이 비어 있다: 기존처럼 PSK를 가진 모든 피어를 받아들인다.
+    Open,
+    /// 허용 목록에 등록된 디바이스. 해당 사용자의 엔트리만 읽고, 자기 디바이스 엔트리만 쓸 수 있다.
+    Device(PeerAcl),
+    Denied,
+}
+
+fn resolve_peer_access(store: &LocalStore, peer: &PeerId) -> Result<PeerAccess> {
+    if let Some(acl) = store.get_peer_acl(&peer.to_string())? {
+        return Ok(PeerAccess::Device(acl));
+    }
+    if store.count_peer_acl()? == 0 {
+        Ok(PeerAccess::Open)
+    } else {
+        Ok(PeerAccess::Denied)
+    }
+}
+
+fn handle_sync_pull(store: &LocalStore, peer: &PeerId, request: &SyncPull) -> Result<SyncBatch> {
+    let user_id = match resolve_peer_access(store, peer)? {
+        PeerAccess::Open => None,
+        PeerAccess::Device(acl) => Some(acl.user_id),
+        PeerAccess::Denied => {
+            eprintln!("warn: p2p pull denied: peer={peer} is not in peer_acl");
+            return Ok(SyncBatch {
+                entries: Vec::new(),
+                tombstones: Vec::new(),
+                next_cursor: None,
+                by_heads: false,
+                error: Some(format!("peer {peer} is not allowed")),
+            });
+        }
+    };
+    let batch = match (&request.heads, user_id.as_deref()) {
+        (Some(heads), user_id) => {
+            store.pull_by_heads(heads, request.cursor, request.limit, user_id)?
+        }
+        (None, None) => store.pull_since_cursor(request.cursor, request.limit)?,
+        (None, Some(user_id)) => {
+            store.pull_since_cursor_for_user(request.cursor, request.limit, user_id)?
+        }
+    };
+    Ok(SyncBatch {
+        entries: batch.entries,
+        tombstones: batch.tombstones,
+        next_cursor: batch.next_cursor,
+        by_heads: batch.by_heads,
+        error: None,
+    })
+}
+
+/// 서버가 보류 중인 notify 요청.
+struct NotifyWaiter<C> {
+    peer: PeerId,
+    cursor: i64,
+    deadline: tokio::time::Instant,
+    channel: C,
+}
+
+/// 답할 notify 요청을 `waiters`에서 빼서 돌려준다. 피어가 pull할 것이 생겼거나(허용 목록/사용자 범위는
+/// pull과 같게 적용), 거부됐거나, 보류 시간이 끝난 요청이다.
+fn take_ready_notify_waiters<C>(
+    store: &LocalStore,
+    waiters: &mut Vec<NotifyWaiter<C>>,
+    now: tokio::time::Instant,
+) -> Vec<(C, NotifyHead)> {
+    // 대부분의 tick에는 새 엔트리가 없으므로, 전체 head로 먼저 거른다.
+    let head = match store.latest_ingest_seq() {
+        Ok(v) => v,
+        Err(err) => {
+            eprintln!("warn: p2p notify: read latest ingest_seq failed: {err:#}");
+            return Vec::new();
+        }
+    };
+
+    let mut ready = Vec::new();
+    let mut pending = Vec::with_capacity(waiters.len());
+    for w in waiters.drain(..) {
+        let resp = if w.cursor < head {
+            match handle_sync_pull(
+                store,
+                &w.peer,
+                &SyncPull {
+                    cursor: w.cursor,
+                    limit: 1,
+                    heads: None,
+                },
+            ) {
+                Ok(batch) if batch.error.is_some() => Some(NotifyHead {
+                    changed: false,
+                    error: batch.error,
+                }),
+                Ok(batch) if batch.next_cursor.is_some() => Some(NotifyHead {
+                    changed: true,
+                    error: None,
+                }),
+                Ok(_) => None,
+                Err(err) => {
+                    eprintln!("warn: p2p notify check failed: peer={}: {err:#}", w.peer);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        match resp {
+            Some(resp) => ready.push((w.channel, resp)),
+            None if w.deadline <= now => ready.push((
+                w.channel,
+                NotifyHead {
+                    changed: false,
+                    error: None,
+                },
+            )),
+            None => pending.push(w),
+        }
+    }
+    *waiters = pending;
+    ready
+}
+
+fn handle_version_vector(store: &LocalStore, peer: &PeerId) -> VersionVectorReply {
+    let heads = match resolve_peer_access(store, peer) {
+        Ok(PeerAccess::Open) => store.version_vector(None),
+        Ok(PeerAccess::Device(acl)) => store.version_vector(Some(&acl.user_id)),
+        Ok(PeerAccess::Denied) => {
+            eprintln!("warn: p2p version vector denied: peer={peer} is not in peer_acl");
+            return VersionVectorReply {
+                heads: Default::default(),
+                error: Some(format!("peer {peer} is not allowed")),
+            };
+        }
+        Err(err) => Err(err),
+    };
+    match heads {
+        Ok(heads) => VersionVectorReply { heads, error: None },
+        Err(err) => {
+            eprintln!("warn: p2p version vector failed: {err:#}");
+            VersionVectorReply {
+                heads: Default::default(),
+                error: Some("version vector unavailable".to_string()),
+            }
+        }
+    }
+}
+
+fn handle_entries_push(store: &LocalStore, peer: &PeerId, request: &EntriesPush) -> PushAck {
+    let rejected = |error: String| {
+        eprintln!("warn: p2p push rejected: peer={peer}: {error}");
+        PushAck {
+            ok: false,
+            inserted: None,
+            ignored: None,
+            deleted: None,
+            error: Some(error),
+            rejected: None,
+        }
+    };
+
+    let authorized;
+    let (request, skipped) = match resolve_peer_access(store, peer) {
+        Ok(PeerAccess::Open) => (request, 0),
+        Ok(PeerAccess::Device(acl)) => match authorize_push(store, &acl, request) {
+            Ok((allowed, skipped)) => {
+                authorized = allowed;
+                (&authorized, skipped)
+            }
+            Err(error) => return rejected(error),
+        },
+        Ok(PeerAccess::Denied) => return rejected(format!("peer {peer} is not allowed")),
+        Err(err) => return rejected(format!("{err:#}")),
+    };
+
+    let applied = store
+        .apply_tombstones(&request.tombstones)
+        .and_then(|deleted| {
+            let stats = store.insert_entries_with_stats(&request.entries)?;
+            Ok((stats, deleted))
+        });
+    match applied {
+        Ok((stats, deleted)) => PushAck {
+            ok: true,
+            inserted: Some(stats.inserted),
+            ignored: Some(stats.ignored),
+            deleted: Some(deleted),
+            error: None,
+            rejected: (skipped > 0).then_some(skipped),
+        },
+        Err(err) => {
+            eprintln!("warn: p2p push insert failed: {err:#}");
+            PushAck {
+                ok: false,
+                inserted: None,
+                ignored: None,
+                deleted: None,
+                error: None,
+                rejected: None,
+            }
+        }
+    }
+}
+
+/// 엔트리는 피어에 묶인 user_id/device_id와 같아야 한다. 다만 gossip으로 전달된 엔트리는 같은 사용자의
+/// 다른 디바이스 것이어도, 그 디바이스가 허용 목록에 등록돼 있으면 받는다. tombstone은 같은 사용자의 것이면
+/// 받는다(다른 디바이스의 엔트리를 지운 tombstone도 push로 전파되므로). tombstone의 user_id는 보낸 쪽이
+/// 정하므로, 지울 행이 이미 있으면 그 행의 user_id도 피어의 사용자와 같아야 한다.
+///
+/// 맞지 않는 항목은 하나씩 건너뛰고(개수를 함께 반환) 나머지는 받는다. 등록되지 않은 디바이스의 엔트리
+/// 하나 때문에 gossip 배치 전체가 막히지 않게 한다.
+fn authorize_push(
+    store: &LocalStore,
+    acl: &PeerAcl,
+    request: &EntriesPush,
+) -> std::result::Result<(EntriesPush, usize), String> {
+    let mut allowed = EntriesPush {
+        entries: Vec::with_capacity(request.entries.len()),
+        tombstones: Vec::with_capacity(request.tombstones.len()),
+    };
+    let mut devices: HashMap<&str, bool> = HashMap::new();
+    let mut skipped = Vec::new();
+    for e in &request.entries {
+        let ok = e.user_id == acl.user_id
+            && (e.device_id == acl.device_id
+                || match devices.get(e.device_id.as_str()) {
+                    Some(ok) => *ok,
+                    None => {
+                        let ok = store
+                            .has_peer_acl_device(&e.user_id, &e.device_id)
+                            .map_err(|err| format!("{err:#}"))?;
+                        devices.insert(&e.device_id, ok);
+                        ok
+                    }
+                });
+        if ok {
+            allowed.entries.push(e.clone());
+        } else {
+            skipped.push(format!(
+                "entry {} (user={} device={})",
+                e.entry_id, e.user_id, e.device_id
+            ));
+        }
+    }
+    for t in &request.tombstones {
+        let owner = store
+            .entry_user_id(&t.entry_id)
+            .map_err(|err| format!("{err:#}"))?;
+        if t.user_id == acl.user_id && owner.is_none_or(|owner| owner == acl.user_id) {
+            allowed.tombstones.push(t.clone());
+        } else {
+            skipped.push(format!("tombstone {} (user={})", t.entry_id, t.user_id));
+        }
+    }
+    if let Some(first) = skipped.first() {
+        eprintln!(
+            "warn: p2p push: skipped {} item(s) outside peer-acl (peer is bound to user={} device={}), e.g. {first}",
+            skipped.len(),
+            acl.user_id,
+            acl.device_id
+        );
+    }
+    Ok((allowed, skipped.len()))
+}
+
 pub fn sync(
     peers: &[String],
     limit: usize,
@@ -547,119 +1140,413 @@ async fn sync_async(
         return Ok(());
     }
 
-    let store = LocalStore::open(db_path)?;
+    let store = LocalStore::open(db_path)?.with_content_key(cfg.content_key.clone());
+    let targets = resolve_targets(&store, peers, &cfg).await?;
+    let push_device_id = resolve_push_device_id(&cfg, push)?;
+    let gossip_user_id = resolve_gossip_user_id(&cfg, push)?;
+
+    let mut progress = crate::sync::SyncRunProgress::new(push);
+    let mut last_err: Option<anyhow::Error> = None;
+    for t in targets {
+        let mut client = match new_client(&t, &cfg) {
+            Ok(v) => v,
+            Err(err) => {
+                eprintln!("warn: p2p client init failed: {}: {err:#}", t.peer_key);
+                last_err = Some(err);
+                continue;
+            }
+        };
+
+        if let Err(err) = sync_target(
+            &store,
+            &t,
+            &mut client,
+            limit,
+            push_device_id,
+            gossip_user_id,
+            &mut progress,
+        )
+        .await
+        {
+            last_err = Some(err);
+        }
+    }
 
+    if progress.is_success() {
+        Ok(())
+    } else {
+        Err(last_err.unwrap_or_else(|| anyhow::anyhow!("p2p sync failed")))
+    }
+}
+
+async fn resolve_targets(
+    store: &LocalStore,
+    peers: &[String],
+    cfg: &SyncConfig,
+) -> Result<Vec<SyncTarget>> {
     let targets = if !peers.is_empty() {
-        build_manual_targets(&store, peers, cfg.relay_addr.clone())?
+        build_manual_targets(store, peers, cfg.relay_addr.clone())?
+    } else if cfg.mdns {
+        discover_targets_with_mdns(store, cfg).await?
     } else {
-        discover_targets(&store, &cfg)?
+        discover_targets(store, cfg)?
     };
 
     if targets.is_empty() {
         anyhow::bail!("no peers found");
     }
+    Ok(targets)
+}
 
-    let push_device_id = if push {
-        Some(
-            cfg.device_id
-                .as_deref()
-                .context("device_id required for push")?,
-        )
-    } else {
-        None
-    };
+fn resolve_push_device_id(cfg: &SyncConfig, push: bool) -> Result<Option<&str>> {
+    if !push {
+        return Ok(None);
+    }
+    cfg.device_id
+        .as_deref()
+        .context("device_id required for push")
+        .map(Some)
+}
 
-    let mut progress = crate::sync::SyncRunProgress::new(push);
+fn resolve_gossip_user_id(cfg: &SyncConfig, push: bool) -> Result<Option<&str>> {
+    if !push || !cfg.gossip {
+        return Ok(None);
+    }
+    cfg.user_id
+        .as_deref()
+        .context("user_id required for gossip")
+        .map(Some)
+}
+
+fn new_client(t: &SyncTarget, cfg: &SyncConfig) -> Result<P2pClient> {
+    P2pClient::new(
+        t.peer_id,
+        t.direct_addrs.clone(),
+        t.relay_addr.clone(),
+        cfg.psk,
+        cfg.identity.clone(),
+        cfg.request_retry_policy.clone(),
+    )
+}
+
+/// 피어 하나와 pull(+`push_device_id`가 있으면 push, `gossip_user_id`가 있으면 gossip)한다.
+/// 실패는 경고로 남기고, 마지막 에러를 돌려준다.
+async fn sync_target(
+    store: &LocalStore,
+    t: &SyncTarget,
+    client: &mut P2pClient,
+    limit: usize,
+    push_device_id: Option<&str>,
+    gossip_user_id: Option<&str>,
+    progress: &mut crate::sync::SyncRunProgress,
+) -> Result<()> {
     let mut last_err: Option<anyhow::Error> = None;
-    for t in targets {
-        let mut client = match P2pClient::new(
-            t.peer_id,
-            t.direct_addrs,
-            t.relay_addr,
-            cfg.psk,
-            cfg.request_retry_policy.clone(),
-        ) {
-            Ok(v) => v,
+
+    let pull_res = crate::sync::sync_pull_from_peer_async(store, &t.peer_key, limit, client)
+        .await
+        .with_context(|| format!("p2p pull peer: {}", t.peer_key));
+
+    match pull_res {
+        Ok(stats) => {
+            progress.mark_pull_ok();
+            if stats.received > 0 || stats.inserted > 0 || stats.deleted > 0 {
+                eprintln!(
+                    "p2p pull summary: {}: received={} inserted={} ignored={} deleted={}",
+                    t.peer_key, stats.received, stats.inserted, stats.ignored, stats.deleted
+                );
+            }
+        }
+        Err(err) => {
+            eprintln!("warn: p2p pull failed: {}: {err:#}", t.peer_key);
+            last_err = Some(err);
+        }
+    }
+
+    if let Some(device_id) = push_device_id {
+        let pending_push = match store.count_pending_push_entries(&t.peer_key, Some(device_id)) {
+            Ok(count) => count,
             Err(err) => {
-                eprintln!("warn: p2p client init failed: {}: {err:#}", t.peer_key);
-                last_err = Some(err);
-                continue;
+                eprintln!("warn: p2p push preflight failed: {}: {err:#}", t.peer_key);
+                return Err(err);
             }
         };
+        let push_needed = pending_push > 0;
+        progress.note_push_needed(push_needed);
 
-        let pull_res =
-            crate::sync::sync_pull_from_peer_async(&store, &t.peer_key, limit, &mut client)
-                .await
-                .with_context(|| format!("p2p pull peer: {}", t.peer_key));
+        client.reset_push_ack_stats();
 
-        match pull_res {
-            Ok(stats) => {
-                progress.mark_pull_ok();
-                if stats.received > 0 || stats.inserted > 0 {
+        let push_res = crate::sync::sync_push_to_peer_async(
+            store,
+            &t.peer_key,
+            limit,
+            Some(device_id),
+            client,
+        )
+        .await
+        .with_context(|| format!("p2p push peer: {}", t.peer_key));
+
+        match push_res {
+            Ok(pushed) => {
+                progress.mark_push_ok(push_needed);
+                if let Some((inserted, ignored)) = client.take_push_ack_stats() {
+                    eprintln!(
+                        "p2p push summary: {}: sent={pushed} inserted={inserted} ignored={ignored}",
+                        t.peer_key
+                    );
+                }
+            }
+            Err(err) => {
+                if let Some((inserted, ignored)) = client.take_push_ack_stats() {
                     eprintln!(
-                        "p2p pull summary: {}: received={} inserted={} ignored={}",
-                        t.peer_key, stats.received, stats.inserted, stats.ignored
+                        "warn: p2p push partial: {}: inserted={inserted} ignored={ignored}",
+                        t.peer_key
                     );
                 }
+                eprintln!("warn: p2p push failed: {}: {err:#}", t.peer_key);
+                last_err = Some(err);
             }
+        }
+    }
+
+    if let Some(user_id) = gossip_user_id {
+        // 자기 엔트리는 위 push가 이미 보냈으므로, 여기서는 피어가 아직 못 받은 다른 디바이스의 것만 남는다.
+        let gossip_res = crate::sync::sync_gossip_to_peer_async(store, user_id, limit, client)
+            .await
+            .with_context(|| format!("p2p gossip peer: {}", t.peer_key));
+        match gossip_res {
+            Ok(0) => {}
+            Ok(sent) => eprintln!("p2p gossip summary: {}: sent={sent}", t.peer_key),
             Err(err) => {
-                eprintln!("warn: p2p pull failed: {}: {err:#}", t.peer_key);
+                eprintln!("warn: p2p gossip failed: {}: {err:#}", t.peer_key);
                 last_err = Some(err);
             }
         }
+    }
 
-        if push {
-            let pending_push = match store.count_pending_push_entries(&t.peer_key, push_device_id) {
-                Ok(count) => count,
-                Err(err) => {
-                    eprintln!("warn: p2p push preflight failed: {}: {err:#}", t.peer_key);
-                    last_err = Some(err);
-                    continue;
-                }
-            };
-            let push_needed = pending_push > 0;
-            progress.note_push_needed(push_needed);
+    match last_err {
+        Some(err) => Err(err),
+        None => Ok(()),
+    }
+}
+
+/// `p2p-sync --watch`. `interval`마다 피어를 다시 찾아 전체 동기화를 돌고, 그 사이에는 각 피어에
+/// sync-notify로 구독해 두었다가 새 엔트리 알림이 오면 그 피어와 바로 동기화한다.
+/// `push`면 로컬에 새 기록이 생겼을 때도 바로 push한다. 연결은 라운드 사이에도 유지한다.
+pub fn watch(
+    peers: &[String],
+    limit: usize,
+    db_path: &str,
+    cfg: SyncConfig,
+    push: bool,
+    watch: WatchConfig,
+    stop: &AtomicBool,
+) -> Result<()> {
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .context("build tokio runtime")?;
+
+    rt.block_on(async move { watch_async(peers, limit, db_path, cfg, push, watch, stop).await })
+}
 
-            client.reset_push_ack_stats();
+struct SyncPeer {
+    target: SyncTarget,
+    client: P2pClient,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum WatchWake {
+    Stop,
+    /// 라운드 기한이 지났다. 피어를 다시 찾고 전체 동기화를 돈다.
+    Round,
+    /// `clients[i]`가 새 엔트리를 알렸다.
+    Peer(usize),
+    /// 로컬 DB에 새 기록이 생겼다(push 대상).
+    Local,
+}
+
+async fn watch_async(
+    peers: &[String],
+    limit: usize,
+    db_path: &str,
+    cfg: SyncConfig,
+    push: bool,
+    watch: WatchConfig,
+    stop: &AtomicBool,
+) -> Result<()> {
+    let store = LocalStore::open(db_path)?.with_content_key(cfg.content_key.clone());
+    let push_device_id = resolve_push_device_id(&cfg, push)?;
+    let gossip_user_id = resolve_gossip_user_id(&cfg, push)?;
+    let mut clients: Vec<SyncPeer> = Vec::new();
+
+    while !stop.load(Ordering::SeqCst) {
+        match resolve_targets(&store, peers, &cfg).await {
+            Ok(targets) => clients = reuse_sync_peers(clients, targets, &cfg),
+            // 탐색이 실패하면(tracker 장애 등) 이전에 찾은 피어로 계속한다.
+            Err(err) => eprintln!("warn: p2p-sync failed: {err:#}"),
+        }
 
-            let push_res = crate::sync::sync_push_to_peer_async(
+        let mut progress = crate::sync::SyncRunProgress::new(push);
+        let mut last_err: Option<anyhow::Error> = None;
+        for p in clients.iter_mut() {
+            if let Err(err) = sync_target(
                 &store,
-                &t.peer_key,
+                &p.target,
+                &mut p.client,
                 limit,
                 push_device_id,
-                &mut client,
+                gossip_user_id,
+                &mut progress,
             )
             .await
-            .with_context(|| format!("p2p push peer: {}", t.peer_key));
-
-            match push_res {
-                Ok(pushed) => {
-                    progress.mark_push_ok(push_needed);
-                    if let Some((inserted, ignored)) = client.take_push_ack_stats() {
-                        eprintln!(
-                            "p2p push summary: {}: sent={pushed} inserted={inserted} ignored={ignored}",
-                            t.peer_key
-                        );
+            {
+                last_err = Some(err);
+            }
+        }
+        if !clients.is_empty() && !progress.is_success() {
+            let err = last_err.unwrap_or_else(|| anyhow::anyhow!("p2p sync failed"));
+            eprintln!("warn: p2p-sync failed: {err:#}");
+        }
+
+        let round_deadline = tokio::time::Instant::now() + watch.interval;
+        loop {
+            // push 대상은 이 프로세스가 마지막으로 동기화한 뒤에 생긴 로컬 기록이다.
+            // 읽기가 잠깐 실패하면(SQLite busy 등) 이번 대기에서만 로컬 감시를 건너뛴다.
+            let local_head = match push_device_id {
+                Some(_) if watch.notify => match store.latest_ingest_seq() {
+                    Ok(head) => Some(head),
+                    Err(err) => {
+                        eprintln!("warn: p2p-sync read local head failed: {err:#}");
+                        None
                     }
-                }
+                },
+                _ => None,
+            };
+            let wake = wait_for_wake(
+                &store,
+                &mut clients,
+                &watch,
+                round_deadline,
+                local_head,
+                stop,
+            )
+            .await;
+            let targets: Vec<usize> = match wake {
+                WatchWake::Stop | WatchWake::Round => break,
+                WatchWake::Peer(i) => vec![i],
+                WatchWake::Local => (0..clients.len()).collect(),
+            };
+
+            tokio::time::sleep(watch.debounce).await;
+            let mut progress = crate::sync::SyncRunProgress::new(push);
+            for i in targets {
+                let p = &mut clients[i];
+                // 실패는 sync_target이 경고로 남긴다. 다음 알림이나 라운드에서 다시 시도한다.
+                let _ = sync_target(
+                    &store,
+                    &p.target,
+                    &mut p.client,
+                    limit,
+                    push_device_id,
+                    gossip_user_id,
+                    &mut progress,
+                )
+                .await;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// 새 탐색 결과로 피어 목록을 바꾼다. 이미 있던 피어는 연결과 구독 상태를 그대로 쓴다.
+fn reuse_sync_peers(
+    prev: Vec<SyncPeer>,
+    targets: Vec<SyncTarget>,
+    cfg: &SyncConfig,
+) -> Vec<SyncPeer> {
+    let mut prev: HashMap<PeerId, SyncPeer> =
+        prev.into_iter().map(|p| (p.target.peer_id, p)).collect();
+
+    let mut out = Vec::with_capacity(targets.len());
+    for t in targets {
+        if let Some(mut p) = prev.remove(&t.peer_id) {
+            p.client.direct_addrs = t.direct_addrs.clone();
+            p.client.relay_addr = t.relay_addr.clone();
+            p.client.notify.failed = false;
+            p.target = t;
+            out.push(p);
+            continue;
+        }
+        match new_client(&t, cfg) {
+            Ok(client) => out.push(SyncPeer { target: t, client }),
+            Err(err) => eprintln!("warn: p2p client init failed: {}: {err:#}", t.peer_key),
+        }
+    }
+    out
+}
+
+/// 알림, 로컬 새 기록(`local_head`가 있을 때), 라운드 기한, 중지 신호 중 먼저 오는 것을 기다린다.
+async fn wait_for_wake(
+    store: &LocalStore,
+    clients: &mut [SyncPeer],
+    watch: &WatchConfig,
+    deadline: tokio::time::Instant,
+    local_head: Option<i64>,
+    stop: &AtomicBool,
+) -> WatchWake {
+    let mut waits = futures::stream::FuturesUnordered::new();
+    if watch.notify {
+        let wait = deadline.saturating_duration_since(tokio::time::Instant::now());
+        for (i, p) in clients.iter_mut().enumerate() {
+            let cursor = match store.get_last_cursor(&p.target.peer_key) {
+                Ok(cursor) => cursor,
                 Err(err) => {
-                    if let Some((inserted, ignored)) = client.take_push_ack_stats() {
-                        eprintln!(
-                            "warn: p2p push partial: {}: inserted={inserted} ignored={ignored}",
-                            t.peer_key
-                        );
-                    }
-                    eprintln!("warn: p2p push failed: {}: {err:#}", t.peer_key);
-                    last_err = Some(err);
+                    // 이 피어는 이번 라운드에 구독하지 않고 라운드 기한에 다시 동기화한다.
+                    eprintln!(
+                        "warn: p2p-sync read cursor failed: {}: {err:#}",
+                        p.target.peer_key
+                    );
+                    continue;
                 }
-            }
+            };
+            let SyncPeer { target, client } = p;
+            waits.push(async move {
+                let res = client
+                    .wait_notify(cursor, wait)
+                    .await
+                    .with_context(|| format!("p2p notify peer: {}", target.peer_key));
+                (i, res)
+            });
         }
     }
 
-    if progress.is_success() {
-        Ok(())
-    } else {
-        Err(last_err.unwrap_or_else(|| anyhow::anyhow!("p2p sync failed")))
+    let mut tick = tokio::time::interval(WATCH_TICK);
+    tick.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    loop {
+        tokio::select! {
+            Some((i, res)) = waits.next() => match res {
+                Ok(()) => return WatchWake::Peer(i),
+                // 이 피어는 다음 라운드까지 interval polling으로만 동기화한다.
+                Err(err) => eprintln!("warn: p2p notify subscribe failed: {err:#}"),
+            },
+            _ = tick.tick() => {
+                if stop.load(Ordering::SeqCst) {
+                    return WatchWake::Stop;
+                }
+                if tokio::time::Instant::now() >= deadline {
+                    return WatchWake::Round;
+                }
+                if let Some(head) = local_head {
+                    match store.latest_ingest_seq() {
+                        Ok(latest) if latest > head => return WatchWake::Local,
+                        Ok(_) => {}
+                        // 일시적인 읽기 실패는 다음 tick에서 다시 본다.
+                        Err(err) => eprintln!("warn: p2p-sync read local head failed: {err:#}"),
+                    }
+                }
+            }
+        }
     }
 }
 
@@ -691,25 +1578,140 @@ fn build_manual_targets(
             store.set_last_pushed_seq(&peer_key, old_seq)?;
         }
 
-        // 수동 입력도 peerbook 캐시에 기록해 tracker 다운 시 fallback 후보로 활용한다.
-        store.upsert_peer_book(&PeerBookPeer {
-            peer_id: peer_key.clone(),
-            addrs: vec![peer_key_old.clone()],
-            user_id: None,
-            device_id: None,
-            last_seen_unix: OffsetDateTime::now_utc().unix_timestamp(),
-        })?;
-
+        // 수동 입력도 peerbook 캐시에 기록해 tracker 다운 시 fallback 후보로 활용한다.
+        store.upsert_peer_book(&PeerBookPeer {
+            peer_id: peer_key.clone(),
+            addrs: vec![peer_key_old.clone()],
+            user_id: None,
+            device_id: None,
+            last_seen_unix: OffsetDateTime::now_utc().unix_timestamp(),
+        })?;
+
+        out.push(SyncTarget {
+            peer_id,
+            peer_key,
+            direct_addrs: vec![base_addr],
+            relay_addr: relay_addr.clone(),
+        });
+    }
+    Ok(out)
+}
+
+/// LAN(mDNS)에서 찾은 피어를 먼저 두고, tracker/peer_book 결과를 합친다.
+/// tracker가 없거나 실패해도 LAN 피어가 있으면 그것만으로 동기화한다.
+async fn discover_targets_with_mdns(
+    store: &LocalStore,
+    cfg: &SyncConfig,
+) -> Result<Vec<SyncTarget>> {
+    let lan = discover_mdns_targets(store, cfg, MDNS_DISCOVERY_WINDOW).await?;
+    if cfg.trackers.is_empty() {
+        if lan.is_empty() {
+            anyhow::bail!(
+                "no peers provided, no trackers configured and no LAN peers found (mdns)"
+            );
+        }
+        return Ok(lan);
+    }
+
+    match discover_targets(store, cfg) {
+        Ok(remote) => Ok(merge_sync_targets(lan, remote)),
+        Err(err) if !lan.is_empty() => {
+            eprintln!("warn: tracker discovery failed, using LAN peers only: {err:#}");
+            Ok(lan)
+        }
+        Err(err) => Err(err),
+    }
+}
+
+async fn discover_mdns_targets(
+    store: &LocalStore,
+    cfg: &SyncConfig,
+    window: Duration,
+) -> Result<Vec<SyncTarget>> {
+    let identity = cfg
+        .identity
+        .clone()
+        .unwrap_or_else(libp2p::identity::Keypair::generate_ed25519);
+    let mut swarm = build_rustory_swarm_with_identity(identity, cfg.psk, true)?;
+    let local_peer_id = *swarm.local_peer_id();
+    let mut peers = MdnsPeers::default();
+
+    // 찾은 피어를 모두 PSK 연결로 확인하고 `MDNS_SETTLE` 동안 새 응답이 없으면 창이 끝나기 전에 돌아온다.
+    let deadline = tokio::time::sleep(window);
+    tokio::pin!(deadline);
+    let settle = tokio::time::sleep(MDNS_SETTLE);
+    tokio::pin!(settle);
+    loop {
+        tokio::select! {
+            _ = &mut deadline => break,
+            _ = &mut settle, if peers.is_settled() => break,
+            event = swarm.select_next_some() => {
+                let now = std::time::Instant::now();
+                match event {
+                    SwarmEvent::Behaviour(RustoryBehaviourEvent::Mdns(
+                        libp2p::mdns::Event::Discovered(list),
+                    )) => {
+                        for (peer_id, addrs) in peers.note_discovered(local_peer_id, list, now) {
+                            if swarm
+                                .dial(DialOpts::peer_id(peer_id).addresses(addrs).build())
+                                .is_err()
+                            {
+                                peers.dial_failed(peer_id);
+                            }
+                        }
+                    }
+                    SwarmEvent::ConnectionEstablished { peer_id, .. } => {
+                        let _ = peers.confirm(peer_id, now);
+                    }
+                    SwarmEvent::OutgoingConnectionError {
+                        peer_id: Some(peer_id),
+                        ..
+                    } => peers.dial_failed(peer_id),
+                    _ => continue,
+                }
+                settle
+                    .as_mut()
+                    .reset(tokio::time::Instant::now() + MDNS_SETTLE);
+            }
+        }
+    }
+
+    let mut out = Vec::new();
+    for (peer_id, addrs) in peers.into_confirmed() {
+        let entry = mdns_peer_book_entry(peer_id, &addrs);
+        store.upsert_peer_book(&entry)?;
+        eprintln!("mdns: found peer {peer_id}");
         out.push(SyncTarget {
             peer_id,
-            peer_key,
-            direct_addrs: vec![base_addr],
-            relay_addr: relay_addr.clone(),
+            peer_key: entry.peer_id,
+            direct_addrs: direct_candidate_addrs_from_tracker(&entry.addrs),
+            relay_addr: cfg.relay_addr.clone(),
         });
     }
     Ok(out)
 }
 
+/// 같은 피어가 양쪽에 있으면 LAN 주소를 앞에 둔다(direct dial은 주소 순서대로 시도한다).
+fn merge_sync_targets(lan: Vec<SyncTarget>, remote: Vec<SyncTarget>) -> Vec<SyncTarget> {
+    let mut out = lan;
+    for target in remote {
+        match out.iter_mut().find(|t| t.peer_id == target.peer_id) {
+            Some(existing) => {
+                for addr in target.direct_addrs {
+                    if !existing.direct_addrs.contains(&addr) {
+                        existing.direct_addrs.push(addr);
+                    }
+                }
+                if existing.relay_addr.is_none() {
+                    existing.relay_addr = target.relay_addr;
+                }
+            }
+            None => out.push(target),
+        }
+    }
+    out
+}
+
 fn discover_targets(store: &LocalStore, cfg: &SyncConfig) -> Result<Vec<SyncTarget>> {
     const PEER_BOOK_MAX_AGE_SECS: i64 = 60 * 60 * 24 * 7;
     const PEER_BOOK_LIMIT: usize = 1000;
@@ -875,18 +1877,40 @@ struct P2pClient {
     push_ack_stats_known: bool,
     push_ack_inserted_total: usize,
     push_ack_ignored_total: usize,
+    notify: NotifySubscription,
     swarm: Swarm<RustoryBehaviour>,
 }
 
+/// watch 모드에서 서버에 걸어 둔 sync-notify 구독 상태. 요청은 `wait_notify` future가 취소돼도
+/// 서버에 남아 있으므로, 응답은 pull/push 중에 도착해도 여기에 기록한다.
+#[derive(Debug, Default)]
+struct NotifySubscription {
+    /// 보류 중인 요청과, 그때까지 응답이 없으면 다시 구독할 시각.
+    pending: Option<(
+        libp2p_request_response::OutboundRequestId,
+        tokio::time::Instant,
+    )>,
+    /// changed=true 응답을 받았고 아직 동기화하지 않았다.
+    fired: bool,
+    /// 서버가 sync-notify를 모른다(구버전). 이 피어는 interval polling만 한다.
+    unsupported: bool,
+    /// 연결/구독이 실패했다. 다음 전체 라운드까지 다시 구독하지 않는다.
+    failed: bool,
+}
+
 impl P2pClient {
     fn new(
         peer_id: PeerId,
         direct_addrs: Vec<Multiaddr>,
         relay_addr: Option<Multiaddr>,
         psk: libp2p::pnet::PreSharedKey,
+        identity: Option<libp2p::identity::Keypair>,
         request_retry_policy: RequestRetryPolicy,
     ) -> Result<Self> {
-        let mut swarm = build_rustory_swarm(psk)?;
+        let mut swarm = match identity {
+            Some(identity) => build_rustory_swarm_with_identity(identity, psk, false)?,
+            None => build_rustory_swarm(psk)?,
+        };
         let listen: Multiaddr = "/ip4/0.0.0.0/tcp/0"
             .parse()
             .context("parse ephemeral listen multiaddr")?;
@@ -900,6 +1924,7 @@ impl P2pClient {
             push_ack_stats_known: false,
             push_ack_inserted_total: 0,
             push_ack_ignored_total: 0,
+            notify: NotifySubscription::default(),
             swarm,
         })
     }
@@ -919,6 +1944,106 @@ impl P2pClient {
         Some(out)
     }
 
+    /// 서버가 새 엔트리를 알릴 때까지 기다린다. 구독할 수 없는 피어(구버전/거부/이미 실패)는 끝나지 않는다.
+    async fn wait_notify(&mut self, cursor: i64, wait: Duration) -> Result<()> {
+        loop {
+            if self.notify.fired {
+                self.notify.fired = false;
+                return Ok(());
+            }
+            if self.notify.unsupported || self.notify.failed {
+                return std::future::pending().await;
+            }
+
+            let resubscribe_at = match self.notify.pending {
+                Some((_, at)) => at,
+                None => {
+                    if let Err(err) = self.ensure_connected().await {
+                        self.notify.failed = true;
+                        return Err(err);
+                    }
+                    let wait_ms = u64::try_from(wait.as_millis()).unwrap_or(u64::MAX);
+                    let request_id = self
+                        .swarm
+                        .behaviour_mut()
+                        .notify
+                        .send_request(&self.peer_id, NotifyWatch { cursor, wait_ms });
+                    let at = tokio::time::Instant::now()
+                        + wait.min(NOTIFY_MAX_WAIT)
+                        + NOTIFY_RESPONSE_GRACE;
+                    self.notify.pending = Some((request_id, at));
+                    at
+                }
+            };
+
+            tokio::select! {
+                _ = tokio::time::sleep_until(resubscribe_at) => {
+                    self.notify.pending = None;
+                }
+                event = self.swarm.select_next_some() => match event {
+                    SwarmEvent::Behaviour(RustoryBehaviourEvent::Notify(event)) => {
+                        self.on_notify_event(event);
+                    }
+                    SwarmEvent::Behaviour(RustoryBehaviourEvent::Dcutr(event)) => match &event.result {
+                        Ok(connection_id) => {
+                            eprintln!(
+                                "dcutr: upgraded to direct: peer={} connection_id={connection_id:?}",
+                                event.remote_peer_id
+                            );
+                        }
+                        Err(err) => {
+                            eprintln!(
+                                "dcutr: upgrade failed: peer={} error={err}",
+                                event.remote_peer_id
+                            );
+                        }
+                    },
+                    _ => {}
+                },
+            }
+        }
+    }
+
+    fn on_notify_event(&mut self, event: libp2p_request_response::Event<NotifyWatch, NotifyHead>) {
+        let pending = self.notify.pending.map(|(id, _)| id);
+        match event {
+            libp2p_request_response::Event::Message {
+                message:
+                    libp2p_request_response::Message::Response {
+                        request_id,
+                        response,
+                    },
+                ..
+            } if Some(request_id) == pending => {
+                self.notify.pending = None;
+                if let Some(error) = response.error {
+                    eprintln!("warn: p2p notify rejected: {}: {error}", self.peer_id);
+                    self.notify.failed = true;
+                } else if response.changed {
+                    self.notify.fired = true;
+                }
+            }
+            libp2p_request_response::Event::OutboundFailure {
+                request_id, error, ..
+            } if Some(request_id) == pending => {
+                self.notify.pending = None;
+                // 연결이 끊긴 경우 등은 다음 wait_notify에서 다시 연결해 구독한다.
+                if matches!(
+                    error,
+                    libp2p_request_response::OutboundFailure::UnsupportedProtocols
+                ) && !self.notify.unsupported
+                {
+                    eprintln!(
+                        "p2p notify: {} does not support {SYNC_NOTIFY_PROTOCOL}; falling back to interval polling",
+                        self.peer_id
+                    );
+                    self.notify.unsupported = true;
+                }
+            }
+            _ => {}
+        }
+    }
+
     async fn ensure_connected(&mut self) -> Result<()> {
         const DIRECT_BASE_TIMEOUT: Duration = Duration::from_secs(3);
         const RELAY_BASE_TIMEOUT: Duration = Duration::from_secs(10);
@@ -1036,7 +2161,12 @@ impl P2pClient {
         }
     }
 
-    async fn pull_batch_with_retries(&mut self, cursor: i64, limit: usize) -> Result<PullBatch> {
+    async fn pull_batch_with_retries(
+        &mut self,
+        cursor: i64,
+        limit: usize,
+        heads: &crate::core::SyncHeads,
+    ) -> Result<PullBatch> {
         // mutable borrow(&mut self) 중에도 policy 값을 쓰기 위해 복사해 둔다.
         let attempts = self.request_retry_policy.attempts;
         let timeout_base = self.request_retry_policy.timeout_base;
@@ -1047,7 +2177,7 @@ impl P2pClient {
         for attempt in 0..attempts {
             let timeout = exp_duration(timeout_base, attempt as u32, Some(timeout_cap));
 
-            match self.pull_batch_once(cursor, limit, timeout).await {
+            match self.pull_batch_once(cursor, limit, heads, timeout).await {
                 Ok(v) => return Ok(v),
                 Err(err) => {
                     if !is_retryable_p2p_request_error(&err) || attempt + 1 >= attempts {
@@ -1073,11 +2203,16 @@ impl P2pClient {
         &mut self,
         cursor: i64,
         limit: usize,
+        heads: &crate::core::SyncHeads,
         timeout: Duration,
     ) -> Result<PullBatch> {
         self.ensure_connected().await?;
 
-        let req = SyncPull { cursor, limit };
+        let req = SyncPull {
+            cursor,
+            limit,
+            heads: Some(heads.clone()),
+        };
         let request_id = self
             .swarm
             .behaviour_mut()
@@ -1101,9 +2236,14 @@ impl P2pClient {
                                     response,
                                 } => {
                                     if got_id == request_id {
+                                        if let Some(error) = response.error {
+                                            anyhow::bail!("p2p pull rejected: {error}");
+                                        }
                                         return Ok(PullBatch {
                                             entries: response.entries,
+                                            tombstones: response.tombstones,
                                             next_cursor: response.next_cursor,
+                                            by_heads: response.by_heads,
                                         });
                                     }
                                 }
@@ -1122,6 +2262,9 @@ impl P2pClient {
                             libp2p_request_response::Event::InboundFailure { .. } => {}
                             libp2p_request_response::Event::ResponseSent { .. } => {}
                         },
+                        SwarmEvent::Behaviour(RustoryBehaviourEvent::Notify(event)) => {
+                            self.on_notify_event(event);
+                        }
                         SwarmEvent::Behaviour(RustoryBehaviourEvent::Dcutr(event)) => match &event.result {
                             Ok(connection_id) => {
                                 eprintln!(
@@ -1143,8 +2286,104 @@ impl P2pClient {
         }
     }
 
-    async fn push_batch_with_retries(&mut self, entries: Vec<crate::core::Entry>) -> Result<()> {
-        if entries.is_empty() {
+    async fn version_vector_with_retries(&mut self) -> Result<crate::core::VersionVector> {
+        // mutable borrow(&mut self) 중에도 policy 값을 쓰기 위해 복사해 둔다.
+        let attempts = self.request_retry_policy.attempts;
+        let timeout_base = self.request_retry_policy.timeout_base;
+        let timeout_cap = self.request_retry_policy.timeout_cap;
+        let backoff_base = self.request_retry_policy.backoff_base;
+
+        let mut last_err: Option<anyhow::Error> = None;
+        for attempt in 0..attempts {
+            let timeout = exp_duration(timeout_base, attempt as u32, Some(timeout_cap));
+
+            match self.version_vector_once(timeout).await {
+                Ok(v) => return Ok(v),
+                Err(err) => {
+                    if !is_retryable_p2p_request_error(&err) || attempt + 1 >= attempts {
+                        return Err(err);
+                    }
+                    last_err = Some(err);
+                }
+            }
+
+            let _ = self.swarm.disconnect_peer_id(self.peer_id);
+
+            let backoff = exp_duration(backoff_base, attempt as u32, None);
+            if backoff > Duration::from_millis(0) {
+                tokio::time::sleep(backoff).await;
+            }
+        }
+
+        Err(last_err.unwrap_or_else(|| anyhow::anyhow!("p2p version vector failed")))
+    }
+
+    async fn version_vector_once(
+        &mut self,
+        timeout: Duration,
+    ) -> Result<crate::core::VersionVector> {
+        self.ensure_connected().await?;
+
+        let request_id = self
+            .swarm
+            .behaviour_mut()
+            .version_vector
+            .send_request(&self.peer_id, VersionVectorQuery {});
+
+        let deadline = tokio::time::sleep(timeout);
+        tokio::pin!(deadline);
+
+        loop {
+            tokio::select! {
+                _ = &mut deadline => {
+                    anyhow::bail!("p2p request timeout after {timeout:?}");
+                }
+                event = self.swarm.select_next_some() => {
+                    match event {
+                        SwarmEvent::Behaviour(RustoryBehaviourEvent::VersionVector(event)) => match event {
+                            libp2p_request_response::Event::Message { message, .. } => match message {
+                                libp2p_request_response::Message::Response {
+                                    request_id: got_id,
+                                    response,
+                                } => {
+                                    if got_id == request_id {
+                                        if let Some(error) = response.error {
+                                            anyhow::bail!("p2p version vector rejected: {error}");
+                                        }
+                                        return Ok(response.heads);
+                                    }
+                                }
+                                libp2p_request_response::Message::Request { .. } => {}
+                            },
+                            libp2p_request_response::Event::OutboundFailure {
+                                request_id: got_id,
+                                error,
+                                ..
+                            } => {
+                                if got_id == request_id {
+                                    return Err(anyhow::Error::new(error))
+                                        .context("p2p outbound request failed");
+                                }
+                            }
+                            libp2p_request_response::Event::InboundFailure { .. } => {}
+                            libp2p_request_response::Event::ResponseSent { .. } => {}
+                        },
+                        SwarmEvent::Behaviour(RustoryBehaviourEvent::Notify(event)) => {
+                            self.on_notify_event(event);
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+    }
+
+    async fn push_batch_with_retries(
+        &mut self,
+        entries: Vec<crate::core::Entry>,
+        tombstones: Vec<crate::core::Tombstone>,
+    ) -> Result<()> {
+        if entries.is_empty() && tombstones.is_empty() {
             return Ok(());
         }
 
@@ -1158,7 +2397,10 @@ impl P2pClient {
         for attempt in 0..attempts {
             let timeout = exp_duration(timeout_base, attempt as u32, Some(timeout_cap));
 
-            match self.push_batch_once(entries.clone(), timeout).await {
+            match self
+                .push_batch_once(entries.clone(), tombstones.clone(), timeout)
+                .await
+            {
                 Ok(()) => return Ok(()),
                 Err(err) => {
                     if !is_retryable_p2p_request_error(&err) || attempt + 1 >= attempts {
@@ -1182,12 +2424,16 @@ impl P2pClient {
     async fn push_batch_once(
         &mut self,
         entries: Vec<crate::core::Entry>,
+        tombstones: Vec<crate::core::Tombstone>,
         timeout: Duration,
     ) -> Result<()> {
         self.ensure_connected().await?;
 
         let entries_len = entries.len();
-        let req = EntriesPush { entries };
+        let req = EntriesPush {
+            entries,
+            tombstones,
+        };
         let request_id = self
             .swarm
             .behaviour_mut()
@@ -1211,6 +2457,11 @@ impl P2pClient {
                                     response,
                                 } => {
                                     if got_id == request_id {
+                                        if let Some(rejected) = response.rejected {
+                                            eprintln!(
+                                                "warn: p2p push: peer skipped {rejected} item(s) not allowed by its peer-acl"
+                                            );
+                                        }
                                         if response.ok {
                                             if let (Some(inserted), Some(ignored)) =
                                                 (response.inserted, response.ignored)
@@ -1231,7 +2482,10 @@ impl P2pClient {
                                             }
                                             return Ok(());
                                         }
-                                        anyhow::bail!("p2p push rejected");
+                                        match response.error {
+                                            Some(error) => anyhow::bail!("p2p push rejected: {error}"),
+                                            None => anyhow::bail!("p2p push rejected"),
+                                        }
                                     }
                                 }
                                 libp2p_request_response::Message::Request { .. } => {}
@@ -1249,6 +2503,9 @@ impl P2pClient {
                             libp2p_request_response::Event::InboundFailure { .. } => {}
                             libp2p_request_response::Event::ResponseSent { .. } => {}
                         },
+                        SwarmEvent::Behaviour(RustoryBehaviourEvent::Notify(event)) => {
+                            self.on_notify_event(event);
+                        }
                         SwarmEvent::Behaviour(RustoryBehaviourEvent::Dcutr(event)) => match &event.result {
                             Ok(connection_id) => {
                                 eprintln!(
@@ -1276,8 +2533,9 @@ impl crate::sync::Puller for P2pClient {
         &'a mut self,
         cursor: i64,
         limit: usize,
+        heads: &'a crate::core::SyncHeads,
     ) -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<PullBatch>> + 'a>> {
-        Box::pin(self.pull_batch_with_retries(cursor, limit))
+        Box::pin(self.pull_batch_with_retries(cursor, limit, heads))
     }
 }
 
@@ -1285,8 +2543,18 @@ impl crate::sync::Pusher for P2pClient {
     fn push<'a>(
         &'a mut self,
         entries: Vec<crate::core::Entry>,
+        tombstones: Vec<crate::core::Tombstone>,
     ) -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<()>> + 'a>> {
-        Box::pin(self.push_batch_with_retries(entries))
+        Box::pin(self.push_batch_with_retries(entries, tombstones))
+    }
+}
+
+impl crate::sync::VersionVectorSource for P2pClient {
+    fn version_vector<'a>(
+        &'a mut self,
+    ) -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<crate::core::VersionVector>> + 'a>>
+    {
+        Box::pin(self.version_vector_with_retries())
     }
 }
 
@@ -1350,6 +2618,9 @@ mod tests {
             shell: "zsh".to_string(),
             hostname: "host".to_string(),
             version: "0.1.0".to_string(),
+            session_id: None,
+            origin_seq: None,
+            origin_id: None,
         }
     }
 
@@ -1428,6 +2699,10 @@ mod tests {
             user_id: Some("u1".to_string()),
             device_id: Some("dev-local".to_string()),
             request_retry_policy: RequestRetryPolicy::default(),
+            content_key: None,
+            identity: None,
+            mdns: false,
+            gossip: false,
         };
 
         let got = discover_targets(&store, &cfg).unwrap();
@@ -1439,6 +2714,128 @@ mod tests {
         );
     }
 
+    #[test]
+    fn mdns_peers_are_confirmed_only_after_psk_connection() {
+        let local = PeerId::random();
+        let remote = PeerId::random();
+        let a1: Multiaddr = "/ip4/192.168.0.10/tcp/4001".parse().unwrap();
+        let a2: Multiaddr = "/ip4/192.168.0.10/tcp/4002".parse().unwrap();
+
+        let now = std::time::Instant::now();
+
+        let mut peers = MdnsPeers::default();
+        let to_dial = peers.note_discovered(
+            local,
+            vec![
+                (local, a1.clone()),
+                (remote, a1.clone()),
+                (remote, a2.clone()),
+            ],
+            now,
+        );
+        assert_eq!(to_dial, vec![(remote, vec![a1.clone(), a2.clone()])]);
+        // 같은 응답이 다시 와도 중복 dial하지 않는다.
+        assert!(
+            peers
+                .note_discovered(local, vec![(remote, a1.clone())], now)
+                .is_empty()
+        );
+        assert!(!peers.is_settled());
+
+        // 연결되지 않은 피어(PSK 불일치 등)는 확정되지 않는다.
+        assert_eq!(peers.confirm(PeerId::random(), now), None);
+        assert_eq!(peers.confirm(remote, now), Some(vec![a1.clone(), a2]));
+        assert!(peers.is_settled());
+        assert!(
+            peers
+                .note_discovered(local, vec![(remote, a1.clone())], now)
+                .is_empty()
+        );
+        // 확정은 TTL이 지나면 다시 확인한다.
+        let later = now + MDNS_CONFIRMED_TTL;
+        assert_eq!(
+            peers.note_discovered(local, vec![(remote, a1.clone())], later),
+            vec![(remote, vec![a1.clone()])]
+        );
+        assert_eq!(peers.confirm(remote, later), Some(vec![a1.clone()]));
+
+        // 응답이 없는 보류 항목은 TTL이 지나면 잊고, 보류 수와 피어당 주소 수는 제한한다.
+        let mut flood = MdnsPeers::default();
+        let many: Vec<(PeerId, Multiaddr)> = (0..MDNS_MAX_PENDING + 10)
+            .map(|_| (PeerId::random(), a1.clone()))
+            .collect();
+        assert_eq!(
+            flood.note_discovered(local, many, now).len(),
+            MDNS_MAX_PENDING
+        );
+        let noisy = PeerId::random();
+        let addrs: Vec<(PeerId, Multiaddr)> = (0..20u16)
+            .map(|port| (noisy, format!("/ip4/10.0.0.1/tcp/{port}").parse().unwrap()))
+            .collect();
+        assert!(flood.note_discovered(local, addrs.clone(), now).is_empty());
+        let got = flood.note_discovered(local, addrs, now + MDNS_PENDING_TTL);
+        assert_eq!(got.len(), 1);
+        assert_eq!(got[0].1.len(), MDNS_MAX_ADDRS_PER_PEER);
+        assert_eq!(flood.pending.len(), 1);
+
+        // mDNS로 갱신해도 tracker가 알려준 user/device는 peer_book에 남는다.
+        let store = LocalStore::open(":memory:").unwrap();
+        store
+            .upsert_peer_book(&PeerBookPeer {
+                peer_id: remote.to_string(),
+                addrs: vec![],
+                user_id: Some("u1".to_string()),
+                device_id: Some("dev-remote".to_string()),
+                last_seen_unix: 0,
+            })
+            .unwrap();
+        let confirmed = peers.into_confirmed();
+        store
+            .upsert_peer_book(&mdns_peer_book_entry(remote, &confirmed[&remote]))
+            .unwrap();
+        let got = store.list_peer_book(Some("u1"), 1, 10).unwrap();
+        assert_eq!(got.len(), 1);
+        assert_eq!(got[0].device_id.as_deref(), Some("dev-remote"));
+        assert_eq!(
+            got[0].addrs[0],
+            format!("/ip4/192.168.0.10/tcp/4001/p2p/{remote}")
+        );
+    }
+
+    #[test]
+    fn merge_sync_targets_puts_lan_addrs_first() {
+        let shared = PeerId::random();
+        let remote_only = PeerId::random();
+        let relay: Multiaddr = format!("/ip4/1.2.3.4/tcp/4001/p2p/{}", PeerId::random())
+            .parse()
+            .unwrap();
+        let target = |peer_id: PeerId, addr: &str, relay_addr: Option<Multiaddr>| SyncTarget {
+            peer_id,
+            peer_key: peer_id.to_string(),
+            direct_addrs: vec![addr.parse().unwrap()],
+            relay_addr,
+        };
+
+        let got = merge_sync_targets(
+            vec![target(shared, "/ip4/192.168.0.10/tcp/4001", None)],
+            vec![
+                target(shared, "/ip4/8.8.8.8/tcp/4001", Some(relay.clone())),
+                target(remote_only, "/ip4/8.8.4.4/tcp/4001", Some(relay.clone())),
+            ],
+        );
+        assert_eq!(got.len(), 2);
+        assert_eq!(got[0].peer_id, shared);
+        assert_eq!(
+            got[0].direct_addrs,
+            vec![
+                "/ip4/192.168.0.10/tcp/4001".parse::<Multiaddr>().unwrap(),
+                "/ip4/8.8.8.8/tcp/4001".parse().unwrap(),
+            ]
+        );
+        assert_eq!(got[0].relay_addr, Some(relay));
+        assert_eq!(got[1].peer_id, remote_only);
+    }
+
     #[test]
     fn build_manual_targets_migrates_legacy_pull_and_push_cursor_keys() {
         let store = LocalStore::open(":memory:").unwrap();
@@ -1489,6 +2886,10 @@ mod tests {
             user_id: Some("u1".to_string()),
             device_id: Some("dev-local".to_string()),
             request_retry_policy: RequestRetryPolicy::default(),
+            content_key: None,
+            identity: None,
+            mdns: false,
+            gossip: false,
         };
 
         let got = discover_targets(&store, &cfg).unwrap();
@@ -1532,6 +2933,7 @@ mod tests {
             SyncPull {
                 cursor: 0,
                 limit: 10,
+                heads: None,
             },
         );
 
@@ -1548,7 +2950,10 @@ mod tests {
                                 .unwrap();
                             let resp = SyncBatch {
                                 entries: batch.entries,
+                                tombstones: batch.tombstones,
                                 next_cursor: batch.next_cursor,
+                                by_heads: false,
+                                error: None,
                             };
                             let _ = server.behaviour_mut().sync.send_response(channel, resp);
                         }
@@ -1607,6 +3012,7 @@ mod tests {
             &server_peer,
             EntriesPush {
                 entries: vec![entry.clone()],
+                tombstones: Vec::new(),
             },
         );
 
@@ -1619,7 +3025,7 @@ mod tests {
                             && let libp2p_request_response::Message::Request { request, channel, .. } = message
                         {
                             remote.insert_entries(&request.entries).unwrap();
-                            let _ = server.behaviour_mut().push.send_response(channel, PushAck { ok: true, inserted: None, ignored: None });
+                            let _ = server.behaviour_mut().push.send_response(channel, PushAck { ok: true, inserted: None, ignored: None, deleted: None, error: None, rejected: None });
                         }
                     }
                     e = client.select_next_some() => {
@@ -1659,4 +3065,213 @@ mod tests {
         let err = anyhow::anyhow!("p2p request timeout after 5s");
         assert!(is_retryable_p2p_request_error(&err));
     }
+
+    #[test]
+    fn serve_handlers_enforce_peer_acl() {
+        let store = LocalStore::open(":memory:").unwrap();
+        let mut other_user = entry("id-2", 2, "echo other");
+        other_user.user_id = "user2".to_string();
+        store
+            .insert_entries(&[entry("id-1", 1, "echo mine"), other_user.clone()])
+            .unwrap();
+        let pull = SyncPull {
+            cursor: 0,
+            limit: 10,
+            heads: None,
+        };
+        let bound = PeerId::random();
+        let stranger = PeerId::random();
+
+        // 허용 목록이 비어 있으면 기존처럼 모두 받아들인다.
+        let batch = handle_sync_pull(&store, &stranger, &pull).unwrap();
+        assert_eq!(batch.entries.len(), 2);
+        assert_eq!(batch.error, None);
+
+        store
+            .upsert_peer_acl(&PeerAcl {
+                peer_id: bound.to_string(),
+                user_id: "user1".to_string(),
+                device_id: "dev1".to_string(),
+                added_at_unix: 1,
+            })
+            .unwrap();
+
+        let batch = handle_sync_pull(&store, &bound, &pull).unwrap();
+        let ids: Vec<_> = batch.entries.iter().map(|e| e.entry_id.as_str()).collect();
+        assert_eq!(ids, vec!["id-1"]);
+
+        let by_heads = SyncPull {
+            heads: Some(Default::default()),
+            ..pull.clone()
+        };
+        let batch = handle_sync_pull(&store, &bound, &by_heads).unwrap();
+        assert!(batch.by_heads);
+        let ids: Vec<_> = batch.entries.iter().map(|e| e.entry_id.as_str()).collect();
+        assert_eq!(ids, vec!["id-1"]);
+
+        let denied = handle_sync_pull(&store, &stranger, &pull).unwrap();
+        assert!(denied.entries.is_empty());
+        assert!(denied.error.is_some());
+
+        let ok = handle_entries_push(
+            &store,
+            &bound,
+            &EntriesPush {
+                entries: vec![entry("id-3", 3, "echo new")],
+                tombstones: Vec::new(),
+            },
+        );
+        assert!(ok.ok);
+        assert_eq!(ok.inserted, Some(1));
+
+        // 허용되지 않은 항목만 건너뛰고 같은 배치의 나머지는 받는다.
+        let mut spoofed = entry("id-4", 4, "echo spoofed");
+        spoofed.device_id = "dev2".to_string();
+        let ack = handle_entries_push(
+            &store,
+            &bound,
+            &EntriesPush {
+                entries: vec![spoofed, other_user, entry("id-7", 7, "echo mixed")],
+                tombstones: Vec::new(),
+            },
+        );
+        assert!(ack.ok, "{ack:?}");
+        assert_eq!(ack.inserted, Some(1));
+        assert_eq!(ack.rejected, Some(2));
+
+        // 다른 사용자의 엔트리를 가리키는 tombstone은 자기 user_id를 붙여 보내도 건너뛴다.
+        let forged = crate::core::Tombstone {
+            entry_id: "id-2".to_string(),
+            device_id: "dev1".to_string(),
+            user_id: "user1".to_string(),
+            deleted_at: OffsetDateTime::from_unix_timestamp(10).unwrap(),
+            origin_seq: None,
+            origin_id: None,
+            deleted_by: None,
+            delete_seq: None,
+        };
+        let ack = handle_entries_push(
+            &store,
+            &bound,
+            &EntriesPush {
+                entries: Vec::new(),
+                tombstones: vec![forged],
+            },
+        );
+        assert!(ack.ok, "{ack:?}");
+        assert_eq!(ack.rejected, Some(1));
+        assert!(
+            store
+                .pull_since_cursor(0, 10)
+                .unwrap()
+                .tombstones
+                .is_empty()
+        );
+
+        let ack = handle_entries_push(
+            &store,
+            &stranger,
+            &EntriesPush {
+                entries: vec![entry("id-5", 5, "echo stranger")],
+                tombstones: Vec::new(),
+            },
+        );
+        assert!(!ack.ok);
+        assert!(ack.error.is_some());
+        assert_eq!(store.pull_since_cursor(0, 10).unwrap().entries.len(), 4);
+
+        // gossip: 같은 사용자의 등록된 다른 디바이스 엔트리는 전달받을 수 있다.
+        store
+            .upsert_peer_acl(&PeerAcl {
+                peer_id: PeerId::random().to_string(),
+                user_id: "user1".to_string(),
+                device_id: "dev2".to_string(),
+                added_at_unix: 2,
+            })
+            .unwrap();
+        let mut forwarded = entry("id-6", 6, "echo forwarded");
+        forwarded.device_id = "dev2".to_string();
+        forwarded.origin_seq = Some(1);
+        let ack = handle_entries_push(
+            &store,
+            &bound,
+            &EntriesPush {
+                entries: vec![forwarded],
+                tombstones: Vec::new(),
+            },
+        );
+        assert!(ack.ok);
+        assert_eq!(ack.inserted, Some(1));
+
+        let vv = handle_version_vector(&store, &bound);
+        assert_eq!(vv.error, None);
+        assert_eq!(vv.heads.get("dev2"), Some(&1));
+        let denied = handle_version_vector(&store, &stranger);
+        assert!(denied.heads.is_empty());
+        assert!(denied.error.is_some());
+    }
+
+    #[test]
+    fn notify_waiters_answer_only_when_peer_can_pull_or_wait_expires() {
+        let store = LocalStore::open(":memory:").unwrap();
+        let mut other_user = entry("id-2", 2, "echo other");
+        other_user.user_id = "user2".to_string();
+        store
+            .insert_entries(&[entry("id-1", 1, "echo mine"), other_user])
+            .unwrap();
+        let bound = PeerId::random();
+        store
+            .upsert_peer_acl(&PeerAcl {
+                peer_id: bound.to_string(),
+                user_id: "user1".to_string(),
+                device_id: "dev1".to_string(),
+                added_at_unix: 1,
+            })
+            .unwrap();
+
+        let now = tokio::time::Instant::now();
+        let later = now + Duration::from_secs(60);
+        let waiter = |peer, cursor, deadline, channel| NotifyWaiter {
+            peer,
+            cursor,
+            deadline,
+            channel,
+        };
+        let mut waiters = vec![
+            // 아직 pull하지 않은 자기 엔트리가 있다.
+            waiter(bound, 0, later, "behind"),
+            // 남은 것은 다른 사용자의 엔트리뿐이라 보류한다.
+            waiter(bound, 1, later, "caught-up"),
+            // 보류 시간이 끝났다.
+            waiter(bound, 1, now, "expired"),
+            // 허용 목록 밖의 피어는 바로 거부한다.
+            waiter(PeerId::random(), 0, later, "stranger"),
+        ];
+
+        let ready = take_ready_notify_waiters(&store, &mut waiters, now);
+        let got: Vec<_> = ready
+            .iter()
+            .map(|(channel, resp)| (*channel, resp.changed, resp.error.is_some()))
+            .collect();
+        assert_eq!(
+            got,
+            vec![
+                ("behind", true, false),
+                ("expired", false, false),
+                ("stranger", false, true),
+            ]
+        );
+        assert_eq!(waiters.len(), 1);
+        assert_eq!(waiters[0].channel, "caught-up");
+
+        // 자기 엔트리가 새로 들어오면 보류 중이던 구독도 답한다.
+        store
+            .insert_entries(&[entry("id-3", 3, "echo new")])
+            .unwrap();
+        let ready = take_ready_notify_waiters(&store, &mut waiters, now);
+        assert_eq!(ready.len(), 1);
+        assert_eq!(ready[0].0, "caught-up");
+        assert!(ready[0].1.changed);
+        assert!(waiters.is_empty());
+    }
 }
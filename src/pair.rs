@@ -0,0 +1,300 @@
+use anyhow::{Context, Result, bail};
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use libp2p::identity::{Keypair, PublicKey};
+
+/// 초대 코드의 접두사. 뒤에는 `base64url(payload JSON).base64url(서명)`이 온다.
+pub const INVITE_PREFIX: &str = "rrpair1.";
+
+/// 수락 코드의 접두사. 형식은 초대 코드와 같고, 합류한 디바이스의 identity 키로 서명한다.
+pub const ACCEPT_PREFIX: &str = "rraccept1.";
+
+/// 새 디바이스가 swarm에 합류하는 데 필요한 값 전부. 초대한 디바이스의 identity 키로 서명한다.
+///
+/// PSK가 들어 있으므로 코드 자체가 비밀이다. 새 디바이스의 identity 키는 합류하는 쪽이 직접 만든다.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Invitation {
+    /// 초대마다 새로 만드는 id. 초대한 디바이스는 이 id로 대기 중인 초대를 기억해 두고,
+    /// 수락 코드가 같은 id를 가져와야 허용 목록에 올린다.
+    pub invite_id: String,
+    pub user_id: String,
+    /// 합류할 디바이스의 device_id.
+    pub device_id: String,
+    pub inviter_peer_id: String,
+    pub inviter_device_id: String,
+    /// 서명 검증용 공개키(protobuf, base64url). `inviter_peer_id`와 일치해야 한다.
+    pub inviter_public_key: String,
+    /// `swarm.key` 파일 내용.
+    pub swarm_key: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_key: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub trackers: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tracker_token: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub relay_addr: Option<String>,
+    pub expires_at_unix: i64,
+}
+
+impl Invitation {
+    pub fn swarm_psk(&self) -> Result<libp2p::pnet::PreSharedKey> {
+        self.swarm_key.parse().context("parse swarm key")
+    }
+}
+
+/// 합류한 디바이스가 초대한 디바이스에게 돌려주는 값. 합류한 디바이스의 identity 키로 서명하므로,
+/// 초대한 쪽은 그 키의 PeerId를 허용 목록에 올린다. 공개 정보만 담는다.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Acceptance {
+    /// 수락한 초대의 `invite_id`.
+    pub invite_id: String,
+    pub user_id: String,
+    pub device_id: String,
+    pub peer_id: String,
+    /// 서명 검증용 공개키(protobuf, base64url). `peer_id`와 일치해야 한다.
+    pub public_key: String,
+    /// 수락한 초대를 만든 디바이스. 다른 디바이스의 `rr pair accept`는 이 코드를 거부한다.
+    pub inviter_peer_id: String,
+    /// 초대 코드의 만료 시각을 그대로 쓴다.
+    pub expires_at_unix: i64,
+}
+
+/// `inviter_peer_id`/`inviter_public_key`는 서명 키에서 채운다.
+pub fn sign_invitation(inviter: &Keypair, mut invitation: Invitation) -> Result<String> {
+    let public = inviter.public();
+    invitation.inviter_peer_id = public.to_peer_id().to_string();
+    invitation.inviter_public_key = URL_SAFE_NO_PAD.encode(public.encode_protobuf());
+    sign_code(INVITE_PREFIX, inviter, &invitation)
+}
+
+/// 서명과 만료를 확인한다. 서명 키가 `inviter_peer_id`의 키인지도 함께 본다.
+pub fn verify_invitation(code: &str, now_unix: i64) -> Result<Invitation> {
+    let (payload, signature) =
+        split_code(INVITE_PREFIX, code).context("not a rustory invitation code")?;
+    let invitation: Invitation =
+        serde_json::from_slice(&payload).context("parse invitation payload")?;
+    verify_signature(
+        "invitation",
+        &invitation.inviter_public_key,
+        &invitation.inviter_peer_id,
+        &payload,
+        &signature,
+    )?;
+    if now_unix >= invitation.expires_at_unix {
+        bail!(
+            "invitation expired at {} (ask for a new `rr pair invite`)",
+            invitation.expires_at_unix
+        );
+    }
+    Ok(invitation)
+}
+
+/// `peer_id`/`public_key`는 서명 키(합류한 디바이스의 identity)에서 채운다.
+pub fn sign_acceptance(device: &Keypair, mut acceptance: Acceptance) -> Result<String> {
+    let public = device.public();
+    acceptance.peer_id = public.to_peer_id().to_string();
+    acceptance.public_key = URL_SAFE_NO_PAD.encode(public.encode_protobuf());
+    sign_code(ACCEPT_PREFIX, device, &acceptance)
+}
+
+/// 서명과 만료, 그리고 `inviter_peer_id`(이 디바이스가 만든 초대인지)를 확인한다.
+pub fn verify_acceptance(code: &str, inviter_peer_id: &str, now_unix: i64) -> Result<Acceptance> {
+    let (payload, signature) =
+        split_code(ACCEPT_PREFIX, code).context("not a rustory acceptance code")?;
+    let acceptance: Acceptance =
+        serde_json::from_slice(&payload).context("parse acceptance payload")?;
+    verify_signature(
+        "acceptance",
+        &acceptance.public_key,
+        &acceptance.peer_id,
+        &payload,
+        &signature,
+    )?;
+    if acceptance.inviter_peer_id != inviter_peer_id {
+        bail!(
+            "acceptance is for an invitation from {} (this device is {inviter_peer_id})",
+            acceptance.inviter_peer_id
+        );
+    }
+    if now_unix >= acceptance.expires_at_unix {
+        bail!(
+            "acceptance expired at {} (ask for a new `rr pair invite`)",
+            acceptance.expires_at_unix
+        );
+    }
+    Ok(acceptance)
+}
+
+fn sign_code<T: serde::Serialize>(prefix: &str, key: &Keypair, value: &T) -> Result<String> {
+    let payload = serde_json::to_vec(value).context("encode pairing payload")?;
+    let signature = key
+        .sign(&payload)
+        .map_err(|err| anyhow::anyhow!("sign pairing payload: {err}"))?;
+    Ok(format!(
+        "{prefix}{}.{}",
+        URL_SAFE_NO_PAD.encode(payload),
+        URL_SAFE_NO_PAD.encode(signature)
+    ))
+}
+
+fn split_code(prefix: &str, code: &str) -> Result<(Vec<u8>, Vec<u8>)> {
+    let body = code
+        .trim()
+        .strip_prefix(prefix)
+        .context("unexpected prefix")?;
+    let (payload_b64, signature_b64) = body.split_once('.').context("code is truncated")?;
+    let payload = URL_SAFE_NO_PAD
+        .decode(payload_b64)
+        .context("decode payload")?;
+    let signature = URL_SAFE_NO_PAD
+        .decode(signature_b64)
+        .context("decode signature")?;
+    Ok((payload, signature))
+}
+
+/// `public_key_b64`가 `peer_id`의 키이고 `payload`의 서명이 맞는지 확인한다.
+fn verify_signature(
+    what: &str,
+    public_key_b64: &str,
+    peer_id: &str,
+    payload: &[u8],
+    signature: &[u8],
+) -> Result<()> {
+    let public_raw = URL_SAFE_NO_PAD
+        .decode(public_key_b64)
+        .with_context(|| format!("decode {what} public key"))?;
+    let public = PublicKey::try_decode_protobuf(&public_raw)
+        .with_context(|| format!("parse {what} public key"))?;
+    if public.to_peer_id().to_string() != peer_id {
+        bail!("{what} public key does not match its peer id");
+    }
+    if !public.verify(payload, signature) {
+        bail!("{what} signature is invalid");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn invitation() -> Invitation {
+        Invitation {
+            invite_id: "inv-1".to_string(),
+            user_id: "user1".to_string(),
+            device_id: "laptop".to_string(),
+            inviter_peer_id: String::new(),
+            inviter_device_id: "desktop".to_string(),
+            inviter_public_key: String::new(),
+            swarm_key: libp2p::pnet::PreSharedKey::new([7; 32]).to_string(),
+            content_key: None,
+            trackers: vec!["http://127.0.0.1:8850".to_string()],
+            tracker_token: Some("secret".to_string()),
+            relay_addr: None,
+            expires_at_unix: 1_000,
+        }
+    }
+
+    #[test]
+    fn signed_invitation_roundtrip() {
+        let inviter = Keypair::generate_ed25519();
+        let code = sign_invitation(&inviter, invitation()).unwrap();
+        assert!(code.starts_with(INVITE_PREFIX));
+
+        let got = verify_invitation(&code, 999).unwrap();
+        assert_eq!(
+            got.inviter_peer_id,
+            inviter.public().to_peer_id().to_string()
+        );
+        assert_eq!(got.device_id, "laptop");
+        assert_eq!(
+            got.swarm_psk().unwrap().fingerprint().to_string(),
+            libp2p::pnet::PreSharedKey::new([7; 32])
+                .fingerprint()
+                .to_string()
+        );
+    }
+
+    #[test]
+    fn verify_rejects_expired_tampered_and_foreign_key() {
+        let inviter = Keypair::generate_ed25519();
+        let code = sign_invitation(&inviter, invitation()).unwrap();
+
+        let err = verify_invitation(&code, 1_000).unwrap_err();
+        assert!(err.to_string().contains("expired"), "{err:#}");
+
+        // payload만 바꾸면 서명이 맞지 않는다.
+        let (payload_b64, signature_b64) = code[INVITE_PREFIX.len()..].split_once('.').unwrap();
+        let mut tampered: Invitation =
+            serde_json::from_slice(&URL_SAFE_NO_PAD.decode(payload_b64).unwrap()).unwrap();
+        tampered.trackers = vec!["http://evil.example".to_string()];
+        let tampered_code = format!(
+            "{INVITE_PREFIX}{}.{signature_b64}",
+            URL_SAFE_NO_PAD.encode(serde_json::to_vec(&tampered).unwrap())
+        );
+        let err = verify_invitation(&tampered_code, 0).unwrap_err();
+        assert!(err.to_string().contains("signature"), "{err:#}");
+
+        // 다른 키로 다시 서명해도 inviter_peer_id는 그 키의 것이 되므로, 위조하려면 peer id가 바뀐다.
+        let mut forged = tampered.clone();
+        forged.inviter_public_key =
+            URL_SAFE_NO_PAD.encode(Keypair::generate_ed25519().public().encode_protobuf());
+        let forged_code = format!(
+            "{INVITE_PREFIX}{}.{signature_b64}",
+            URL_SAFE_NO_PAD.encode(serde_json::to_vec(&forged).unwrap())
+        );
+        let err = verify_invitation(&forged_code, 0).unwrap_err();
+        assert!(err.to_string().contains("does not match"), "{err:#}");
+
+        assert!(verify_invitation("garbage", 0).is_err());
+    }
+
+    #[test]
+    fn acceptance_carries_the_joiners_own_key_back_to_the_inviter() {
+        let inviter = Keypair::generate_ed25519();
+        let inviter_peer_id = inviter.public().to_peer_id().to_string();
+        let device = Keypair::generate_ed25519();
+        let code = sign_acceptance(
+            &device,
+            Acceptance {
+                invite_id: "inv-1".to_string(),
+                user_id: "user1".to_string(),
+                device_id: "laptop".to_string(),
+                peer_id: String::new(),
+                public_key: String::new(),
+                inviter_peer_id: inviter_peer_id.clone(),
+                expires_at_unix: 1_000,
+            },
+        )
+        .unwrap();
+        assert!(code.starts_with(ACCEPT_PREFIX));
+        // 초대 코드 자리에는 쓸 수 없다.
+        assert!(verify_invitation(&code, 0).is_err());
+
+        let got = verify_acceptance(&code, &inviter_peer_id, 999).unwrap();
+        assert_eq!(got.peer_id, device.public().to_peer_id().to_string());
+        assert_eq!(got.device_id, "laptop");
+
+        let other = Keypair::generate_ed25519()
+            .public()
+            .to_peer_id()
+            .to_string();
+        let err = verify_acceptance(&code, &other, 0).unwrap_err();
+        assert!(err.to_string().contains("invitation from"), "{err:#}");
+        let err = verify_acceptance(&code, &inviter_peer_id, 1_000).unwrap_err();
+        assert!(err.to_string().contains("expired"), "{err:#}");
+
+        // 합류한 디바이스의 키로 서명돼 있으므로, 다른 peer id를 끼워 넣을 수 없다.
+        let (payload_b64, signature_b64) = code[ACCEPT_PREFIX.len()..].split_once('.').unwrap();
+        let mut forged: Acceptance =
+            serde_json::from_slice(&URL_SAFE_NO_PAD.decode(payload_b64).unwrap()).unwrap();
+        forged.peer_id = other;
+        let forged_code = format!(
+            "{ACCEPT_PREFIX}{}.{signature_b64}",
+            URL_SAFE_NO_PAD.encode(serde_json::to_vec(&forged).unwrap())
+        );
+        let err = verify_acceptance(&forged_code, &inviter_peer_id, 0).unwrap_err();
+        assert!(err.to_string().contains("does not match"), "{err:#}");
+    }
+}
@@ -0,0 +1,598 @@
+use crate::core::Entry;
+use crate::entry_format::{escape_one_line, format_duration_ms, format_ts};
+use anyhow::{Context, Result, bail};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::style::{Attribute, Print, SetAttribute};
+use crossterm::terminal::{self, ClearType};
+use crossterm::{cursor, execute, queue};
+use std::io::Write;
+
+const PREVIEW_HEIGHT: u16 = 5;
+
+/// picker에서 후보를 좁히는 범위. `ctrl+r`로 순환한다.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FilterMode {
+    #[default]
+    Global,
+    Host,
+    Directory,
+    Session,
+}
+
+impl FilterMode {
+    pub fn parse(value: &str) -> Result<Self> {
+        match value.trim() {
+            "global" => Ok(Self::Global),
+            "host" => Ok(Self::Host),
+            "directory" => Ok(Self::Directory),
+            "session" => Ok(Self::Session),
+            other => {
+                bail!("unsupported filter mode: {other} (expected global|host|directory|session)")
+            }
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Global => "global",
+            Self::Host => "host",
+            Self::Directory => "directory",
+            Self::Session => "session",
+        }
+    }
+
+    fn next(self) -> Self {
+        match self {
+            Self::Global => Self::Host,
+            Self::Host => Self::Directory,
+            Self::Directory => Self::Session,
+            Self::Session => Self::Global,
+        }
+    }
+}
+
+/// picker 시작 모드와, host/directory/session 모드에서 비교 기준으로 쓰는 현재 셸 정보.
+///
+/// 기준 값이 없는 모드는 전체(global)와 같이 동작한다.
+#[derive(Debug, Clone, Default)]
+pub struct PickerContext {
+    pub mode: FilterMode,
+    pub cwd: Option<String>,
+    pub hostname: Option<String>,
+    pub session_id: Option<String>,
+}
+
+impl PickerContext {
+    pub fn allows(&self, mode: FilterMode, entry: &Entry) -> bool {
+        match mode {
+            FilterMode::Global => true,
+            FilterMode::Host => self.hostname.as_deref().is_none_or(|h| entry.hostname == h),
+            FilterMode::Directory => self.cwd.as_deref().is_none_or(|c| entry.cwd == c),
+            FilterMode::Session => self
+                .session_id
+                .as_deref()
+                .is_none_or(|s| entry.session_id.as_deref() == Some(s)),
+        }
+    }
+}
+
+/// 내장 TUI picker를 띄워 선택된 커맨드를 돌려준다(취소 시 `None`).
+///
+/// stdout은 선택 결과 전달에 쓰이므로(`$(rr search)`), 화면은 `/dev/tty`에 그린다.
+pub fn pick(entries: &[Entry], ctx: &PickerContext) -> Result<Option<String>> {
+    if entries.is_empty() {
+        return Ok(None);
+    }
+
+    let mut state = PickerState::new(entries, ctx);
+    let mut term = TerminalGuard::enter()?;
+
+    loop {
+        let (_, rows) = terminal::size().context("read terminal size")?;
+        let list_height = rows.saturating_sub(PREVIEW_HEIGHT + 2).max(1) as usize;
+        state.ensure_visible(list_height);
+        render(&mut term.out, &state, list_height)?;
+
+        match event::read().context("read terminal event")? {
+            Event::Key(key) if key.kind != KeyEventKind::Release => {
+                match state.handle_key(key, list_height) {
+                    Action::Continue => {}
+                    Action::Accept => return Ok(state.selected_entry().map(|e| e.cmd.clone())),
+                    Action::Cancel => return Ok(None),
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Action {
+    Continue,
+    Accept,
+    Cancel,
+}
+
+struct PickerState<'a> {
+    entries: &'a [Entry],
+    ctx: &'a PickerContext,
+    query: String,
+    mode: FilterMode,
+    matches: Vec<usize>,
+    selected: usize,
+    scroll: usize,
+}
+
+impl<'a> PickerState<'a> {
+    fn new(entries: &'a [Entry], ctx: &'a PickerContext) -> Self {
+        let mut state = Self {
+            entries,
+            ctx,
+            query: String::new(),
+            mode: ctx.mode,
+            matches: Vec::new(),
+            selected: 0,
+            scroll: 0,
+        };
+        state.refilter();
+        state
+    }
+
+    fn refilter(&mut self) {
+        let terms: Vec<&str> = self.query.split_whitespace().collect();
+
+        let mut scored: Vec<(i64, usize)> = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter(|(_, e)| self.ctx.allows(self.mode, e))
+            .filter_map(|(idx, e)| {
+                let mut total = 0;
+                for term in &terms {
+                    total += fuzzy_score(term, &e.cmd)?;
+                }
+                Some((total, idx))
+            })
+            .collect();
+
+        // 점수가 같으면 입력 순서(최신순)를 유지한다.
+        scored.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+        self.matches = scored.into_iter().map(|(_, idx)| idx).collect();
+        self.selected = 0;
+        self.scroll = 0;
+    }
+
+    fn selected_entry(&self) -> Option<&'a Entry> {
+        self.matches
+            .get(self.selected)
+            .map(|&idx| &self.entries[idx])
+    }
+
+    fn move_selection(&mut self, delta: isize) {
+        if self.matches.is_empty() {
+            self.selected = 0;
+            return;
+        }
+        let max = self.matches.len() - 1;
+        self.selected = self.selected.saturating_add_signed(delta).min(max);
+    }
+
+    fn ensure_visible(&mut self, height: usize) {
+        if self.selected < self.scroll {
+            self.scroll = self.selected;
+        } else if self.selected >= self.scroll + height {
+            self.scroll = self.selected + 1 - height;
+        }
+    }
+
+    fn handle_key(&mut self, key: KeyEvent, page: usize) -> Action {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        let page = page.max(1) as isize;
+
+        match key.code {
+            KeyCode::Enter => return Action::Accept,
+            KeyCode::Esc => return Action::Cancel,
+            KeyCode::Char('c' | 'g' | 'd') if ctrl => return Action::Cancel,
+            KeyCode::Char('r') if ctrl => {
+                self.mode = self.mode.next();
+                self.refilter();
+            }
+            KeyCode::Up => self.move_selection(-1),
+            KeyCode::Char('p' | 'k') if ctrl => self.move_selection(-1),
+            KeyCode::Down => self.move_selection(1),
+            KeyCode::Char('n' | 'j') if ctrl => self.move_selection(1),
+            KeyCode::PageUp => self.move_selection(-page),
+            KeyCode::PageDown => self.move_selection(page),
+            KeyCode::Char('u') if ctrl => {
+                self.query.clear();
+                self.refilter();
+            }
+            KeyCode::Char('w') if ctrl => {
+                let trimmed = self.query.trim_end();
+                let cut = trimmed.rfind(char::is_whitespace).map_or(0, |i| i + 1);
+                self.query.truncate(cut);
+                self.refilter();
+            }
+            KeyCode::Backspace => {
+                if self.query.pop().is_some() {
+                    self.refilter();
+                }
+            }
+            KeyCode::Char(c) if !ctrl => {
+                self.query.push(c);
+                self.refilter();
+            }
+            _ => {}
+        }
+        Action::Continue
+    }
+}
+
+/// 공백 없는 단일 term에 대한 fuzzy(subsequence) 점수. 매칭되지 않으면 `None`.
+///
+/// smart-case: term에 대문자가 있을 때만 대소문자를 구분한다.
+fn fuzzy_score(term: &str, text: &str) -> Option<i64> {
+    if term.is_empty() {
+        return Some(0);
+    }
+
+    let case_sensitive = term.chars().any(char::is_uppercase);
+    let fold = |c: char| {
+        if case_sensitive {
+            c
+        } else {
+            c.to_ascii_lowercase()
+        }
+    };
+
+    let pattern: Vec<char> = term.chars().map(fold).collect();
+    let text: Vec<char> = text.chars().map(fold).collect();
+
+    let mut score: i64 = 0;
+    let mut pi = 0;
+    let mut prev_match: Option<usize> = None;
+    for (ti, &c) in text.iter().enumerate() {
+        if pi == pattern.len() {
+            break;
+        }
+        if c != pattern[pi] {
+            continue;
+        }
+
+        score += 1;
+        match prev_match {
+            Some(prev) if prev + 1 == ti => score += 5,
+            Some(prev) => score -= ((ti - prev - 1) as i64).min(5),
+            None => {}
+        }
+        if ti == 0 || is_word_boundary(text[ti - 1]) {
+            score += 3;
+        }
+        prev_match = Some(ti);
+        pi += 1;
+    }
+
+    if pi < pattern.len() {
+        return None;
+    }
+
+    // 연속 부분 문자열로 포함되면 흩어진 매칭보다 우선한다.
+    let pattern: String = pattern.into_iter().collect();
+    let text: String = text.into_iter().collect();
+    if text.contains(&pattern) {
+        score += 10;
+    }
+    Some(score)
+}
+
+fn is_word_boundary(c: char) -> bool {
+    c.is_whitespace() || matches!(c, '/' | '-' | '_' | '.' | ':' | '=' | '"' | '\'')
+}
+
+fn truncate_to_width(value: &str, width: usize) -> String {
+    value.chars().take(width).collect()
+}
+
+fn preview_lines(entry: &Entry) -> Vec<String> {
+    vec![
+        format!("cwd: {}", entry.cwd),
+        format!(
+            "exit_code: {}  duration: {}",
+            entry.exit_code,
+            format_duration_ms(entry.duration_ms)
+        ),
+        format!(
+            "hostname: {}  device_id: {}",
+            entry.hostname, entry.device_id
+        ),
+        format!(
+            "time: {}  shell: {}  session: {}",
+            format_ts(entry.ts),
+            entry.shell,
+            entry.session_id.as_deref().unwrap_or("-")
+        ),
+    ]
+}
+
+fn render(out: &mut impl Write, state: &PickerState<'_>, list_height: usize) -> Result<()> {
+    let (cols, _) = terminal::size().context("read terminal size")?;
+    let width = cols as usize;
+
+    queue!(out, cursor::MoveTo(0, 0), terminal::Clear(ClearType::All))?;
+
+    let status = format!(
+        "[{}] {}/{}",
+        state.mode.as_str(),
+        state.matches.len(),
+        state.entries.len()
+    );
+    queue!(
+        out,
+        cursor::MoveTo(0, 0),
+        Print(truncate_to_width(&format!("> {}", state.query), width)),
+    )?;
+    if width > status.len() + state.query.chars().count() + 3 {
+        queue!(
+            out,
+            cursor::MoveTo((width - status.len()) as u16, 0),
+            SetAttribute(Attribute::Dim),
+            Print(&status),
+            SetAttribute(Attribute::Reset),
+        )?;
+    }
+
+    for (row, &idx) in state
+        .matches
+        .iter()
+        .skip(state.scroll)
+        .take(list_height)
+        .enumerate()
+    {
+        let entry = &state.entries[idx];
+        let line = truncate_to_width(&format!("  {}", escape_one_line(&entry.cmd)), width);
+        queue!(out, cursor::MoveTo(0, (row + 1) as u16))?;
+        if state.scroll + row == state.selected {
+            queue!(
+                out,
+                SetAttribute(Attribute::Reverse),
+                Print(&line),
+                SetAttribute(Attribute::Reset),
+            )?;
+        } else {
+            queue!(out, Print(&line))?;
+        }
+    }
+
+    let preview_top = (list_height + 1) as u16;
+    queue!(
+        out,
+        cursor::MoveTo(0, preview_top),
+        SetAttribute(Attribute::Dim),
+        Print("─".repeat(width)),
+        SetAttribute(Attribute::Reset),
+    )?;
+    if let Some(entry) = state.selected_entry() {
+        for (i, line) in preview_lines(entry).iter().enumerate() {
+            queue!(
+                out,
+                cursor::MoveTo(0, preview_top + 1 + i as u16),
+                Print(truncate_to_width(line, width)),
+            )?;
+        }
+    }
+
+    let prompt_col = (2 + state.query.chars().count()).min(width.saturating_sub(1));
+    queue!(out, cursor::MoveTo(prompt_col as u16, 0))?;
+    out.flush().context("flush picker output")?;
+    Ok(())
+}
+
+/// raw mode + alternate screen을 켜고, drop 시점에 원래대로 되돌린다.
+struct TerminalGuard {
+    out: Box<dyn Write>,
+}
+
+impl TerminalGuard {
+    fn enter() -> Result<Self> {
+        let mut out = open_tty_writer();
+        terminal::enable_raw_mode().context("enable raw mode")?;
+        if let Err(err) = execute!(out, terminal::EnterAlternateScreen) {
+            let _ = terminal::disable_raw_mode();
+            return Err(err).context("enter alternate screen");
+        }
+        Ok(Self { out })
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let _ = execute!(self.out, terminal::LeaveAlternateScreen, cursor::Show);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+fn open_tty_writer() -> Box<dyn Write> {
+    #[cfg(unix)]
+    if let Ok(tty) = std::fs::OpenOptions::new().write(true).open("/dev/tty") {
+        return Box::new(tty);
+    }
+    Box::new(std::io::stderr())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::OffsetDateTime;
+
+    fn entry(entry_id: &str, cmd: &str, cwd: &str, hostname: &str) -> Entry {
+        Entry {
+            entry_id: entry_id.to_string(),
+            device_id: "dev1".to_string(),
+            user_id: "user1".to_string(),
+            ts: OffsetDateTime::from_unix_timestamp(1).unwrap(),
+            cmd: cmd.to_string(),
+            cwd: cwd.to_string(),
+            exit_code: 0,
+            duration_ms: 12,
+            shell: "zsh".to_string(),
+            hostname: hostname.to_string(),
+            version: "0.1.0".to_string(),
+            session_id: None,
+            origin_seq: None,
+            origin_id: None,
+        }
+    }
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    fn ctrl(c: char) -> KeyEvent {
+        KeyEvent::new(KeyCode::Char(c), KeyModifiers::CONTROL)
+    }
+
+    #[test]
+    fn fuzzy_score_matches_subsequence_and_prefers_substring() {
+        assert!(fuzzy_score("gst", "git status").is_some());
+        assert!(fuzzy_score("xyz", "git status").is_none());
+
+        let substring = fuzzy_score("stat", "git status").unwrap();
+        let scattered = fuzzy_score("stat", "s t a t").unwrap();
+        assert!(substring > scattered);
+    }
+
+    #[test]
+    fn fuzzy_score_is_smart_case() {
+        assert!(fuzzy_score("make", "MAKE build").is_some());
+        assert!(fuzzy_score("Make", "make build").is_none());
+        assert!(fuzzy_score("Make", "Make build").is_some());
+    }
+
+    #[test]
+    fn state_filters_incrementally_and_keeps_recency_on_ties() {
+        let entries = vec![
+            entry("1", "git push", "/a", "h1"),
+            entry("2", "cargo test", "/a", "h1"),
+            entry("3", "git pull", "/b", "h2"),
+        ];
+        let ctx = PickerContext::default();
+        let mut state = PickerState::new(&entries, &ctx);
+        assert_eq!(state.matches, vec![0, 1, 2]);
+
+        for c in "git".chars() {
+            state.handle_key(key(KeyCode::Char(c)), 10);
+        }
+        assert_eq!(state.matches, vec![0, 2]);
+
+        state.handle_key(key(KeyCode::Char(' ')), 10);
+        state.handle_key(key(KeyCode::Char('l')), 10);
+        assert_eq!(state.matches, vec![2]);
+
+        state.handle_key(ctrl('u'), 10);
+        assert_eq!(state.query, "");
+        assert_eq!(state.matches.len(), 3);
+    }
+
+    #[test]
+    fn filter_mode_parse_roundtrip() {
+        for mode in [
+            FilterMode::Global,
+            FilterMode::Host,
+            FilterMode::Directory,
+            FilterMode::Session,
+        ] {
+            assert_eq!(FilterMode::parse(mode.as_str()).unwrap(), mode);
+        }
+        assert!(FilterMode::parse("cwd").is_err());
+    }
+
+    #[test]
+    fn ctrl_r_cycles_filter_modes() {
+        let mut entries = vec![
+            entry("1", "ls", "/a", "h1"),
+            entry("2", "ls", "/b", "h1"),
+            entry("3", "ls", "/a", "h2"),
+        ];
+        entries[1].session_id = Some("s1".to_string());
+        let ctx = PickerContext {
+            cwd: Some("/a".to_string()),
+            hostname: Some("h1".to_string()),
+            session_id: Some("s1".to_string()),
+            ..Default::default()
+        };
+        let mut state = PickerState::new(&entries, &ctx);
+        assert_eq!(state.mode, FilterMode::Global);
+        assert_eq!(state.matches.len(), 3);
+
+        state.handle_key(ctrl('r'), 10);
+        assert_eq!(state.mode, FilterMode::Host);
+        assert_eq!(state.matches, vec![0, 1]);
+
+        state.handle_key(ctrl('r'), 10);
+        assert_eq!(state.mode, FilterMode::Directory);
+        assert_eq!(state.matches, vec![0, 2]);
+
+        state.handle_key(ctrl('r'), 10);
+        assert_eq!(state.mode, FilterMode::Session);
+        assert_eq!(state.matches, vec![1]);
+
+        state.handle_key(ctrl('r'), 10);
+        assert_eq!(state.mode, FilterMode::Global);
+    }
+
+    #[test]
+    fn starts_in_configured_mode() {
+        let entries = vec![entry("1", "ls", "/a", "h1"), entry("2", "ls", "/b", "h1")];
+        let ctx = PickerContext {
+            mode: FilterMode::Directory,
+            cwd: Some("/b".to_string()),
+            ..Default::default()
+        };
+        let state = PickerState::new(&entries, &ctx);
+        assert_eq!(state.mode, FilterMode::Directory);
+        assert_eq!(state.matches, vec![1]);
+    }
+
+    #[test]
+    fn navigation_and_accept_return_selected_cmd() {
+        let entries = vec![entry("1", "a", "/", "h"), entry("2", "b", "/", "h")];
+        let ctx = PickerContext::default();
+        let mut state = PickerState::new(&entries, &ctx);
+
+        state.handle_key(key(KeyCode::Up), 10);
+        assert_eq!(state.selected, 0);
+        state.handle_key(key(KeyCode::Down), 10);
+        state.handle_key(key(KeyCode::Down), 10);
+        assert_eq!(state.selected, 1);
+        assert_eq!(state.handle_key(key(KeyCode::Enter), 10), Action::Accept);
+        assert_eq!(state.selected_entry().unwrap().cmd, "b");
+
+        assert_eq!(state.handle_key(key(KeyCode::Esc), 10), Action::Cancel);
+        assert_eq!(state.handle_key(ctrl('c'), 10), Action::Cancel);
+    }
+
+    #[test]
+    fn ctrl_w_deletes_last_word() {
+        let entries = vec![entry("1", "a", "/", "h")];
+        let ctx = PickerContext::default();
+        let mut state = PickerState::new(&entries, &ctx);
+        state.query = "git push".to_string();
+        state.handle_key(ctrl('w'), 10);
+        assert_eq!(state.query, "git ");
+        state.handle_key(ctrl('w'), 10);
+        assert_eq!(state.query, "");
+    }
+
+    #[test]
+    fn preview_shows_entry_metadata() {
+        let mut e = entry("1", "sleep 2", "/work", "laptop");
+        e.exit_code = 130;
+        e.duration_ms = 2_500;
+        let lines = preview_lines(&e).join("\n");
+        assert!(lines.contains("cwd: /work"));
+        assert!(lines.contains("exit_code: 130"));
+        assert!(lines.contains("duration: 2.5s"));
+        assert!(lines.contains("hostname: laptop"));
+        assert!(lines.contains("1970-01-01 00:00:01 UTC"));
+    }
+}
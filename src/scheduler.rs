@@ -0,0 +1,233 @@
+use crate::storage::{JobState, LocalStore};
+use anyhow::Result;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+// 중지 신호와 새로 기한이 된 작업을 확인하는 주기.
+const TICK: Duration = Duration::from_secs(1);
+
+/// `rr agent`가 주기적으로 돌리는 작업. 이름은 config의 `[scheduler.<name>]`과 상태 테이블 키로 쓴다.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Job {
+    /// tracker/mDNS로 찾은 피어와 pull(+push).
+    Sync,
+    /// 오래된 로컬 엔트리 보관 정리(`rr prune`).
+    Prune,
+    /// 실행 중인 p2p-serve의 주소를 tracker에 다시 등록.
+    TrackerRegister,
+    /// 오래 보이지 않은 peer_book 항목 정리.
+    PeerBook,
+}
+
+impl Job {
+    pub const ALL: [Job; 4] = [Job::Sync, Job::Prune, Job::TrackerRegister, Job::PeerBook];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Job::Sync => "sync",
+            Job::Prune => "prune",
+            Job::TrackerRegister => "tracker_register",
+            Job::PeerBook => "peer_book",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JobSchedule {
+    pub interval: Duration,
+    pub backoff_base: Duration,
+    pub backoff_cap: Duration,
+}
+
+impl JobSchedule {
+    /// 성공하면 `interval` 뒤, 실패하면 연속 실패 횟수에 따라 base → 2배씩 → cap 뒤에 다시 돈다.
+    pub fn delay_after(&self, consecutive_failures: u32) -> Duration {
+        if consecutive_failures == 0 {
+            return self.interval;
+        }
+        let factor = 1u32 << (consecutive_failures - 1).min(16);
+        self.backoff_base
+            .saturating_mul(factor)
+            .min(self.backoff_cap)
+    }
+}
+
+/// 작업 본문. 스케줄러가 연 `store`를 함께 받고, 성공하면 상태에 남길 한 줄 요약을 돌려준다.
+pub trait JobRunner {
+    fn run(&mut self, store: &LocalStore, job: Job) -> Result<String>;
+}
+
+pub struct Scheduler {
+    jobs: Vec<(Job, JobSchedule)>,
+}
+
+impl Scheduler {
+    pub fn new(jobs: Vec<(Job, JobSchedule)>) -> Self {
+        Self { jobs }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.jobs.is_empty()
+    }
+
+    /// 기한이 된 작업을 차례로 한 번씩 실행하고 결과를 `scheduler_jobs`에 남긴다.
+    /// 상태가 DB에 있으므로 agent를 재시작해도 주기/backoff가 이어진다.
+    pub fn run_due(
+        &self,
+        store: &LocalStore,
+        runner: &mut dyn JobRunner,
+        now_unix: &dyn Fn() -> i64,
+    ) -> Result<()> {
+        for (job, schedule) in &self.jobs {
+            let prev = store.get_job_state(job.as_str())?;
+            let started = now_unix();
+            if prev.as_ref().is_some_and(|s| s.next_due_unix > started) {
+                continue;
+            }
+
+            let result = runner.run(store, *job);
+            let state = next_state(prev, *job, schedule, started, now_unix(), result);
+            if let Some(err) = state.last_error.as_deref() {
+                eprintln!(
+                    "warn: scheduler job {} failed (failures={}, retry at {}): {err}",
+                    job.as_str(),
+                    state.consecutive_failures,
+                    state.next_due_unix
+                );
+            }
+            store.set_job_state(&state)?;
+        }
+        Ok(())
+    }
+
+    /// `stop`이 켜질 때까지 돈다. 작업 하나가 도는 동안에는 중지 신호를 확인하지 못한다.
+    pub fn run(&self, store: &LocalStore, runner: &mut dyn JobRunner, stop: &AtomicBool) {
+        let now_unix = || time::OffsetDateTime::now_utc().unix_timestamp();
+        while !stop.load(Ordering::SeqCst) {
+            if let Err(err) = self.run_due(store, runner, &now_unix) {
+                eprintln!("warn: scheduler: {err:#}");
+            }
+            std::thread::sleep(TICK);
+        }
+    }
+}
+
+fn next_state(
+    prev: Option<JobState>,
+    job: Job,
+    schedule: &JobSchedule,
+    started_unix: i64,
+    finished_unix: i64,
+    result: Result<String>,
+) -> JobState {
+    let prev_failures = prev.as_ref().map_or(0, |s| s.consecutive_failures);
+    let last_ok_unix = prev.and_then(|s| s.last_ok_unix);
+    let (consecutive_failures, last_ok_unix, last_summary, last_error) = match result {
+        Ok(summary) => (0, Some(finished_unix), Some(summary), None),
+        Err(err) => (
+            prev_failures.saturating_add(1),
+            last_ok_unix,
+            None,
+            Some(format!("{err:#}")),
+        ),
+    };
+    let delay =
+        i64::try_from(schedule.delay_after(consecutive_failures).as_secs()).unwrap_or(i64::MAX);
+
+    JobState {
+        job: job.as_str().to_string(),
+        last_run_unix: Some(started_unix),
+        last_ok_unix,
+        consecutive_failures,
+        next_due_unix: finished_unix.saturating_add(delay),
+        last_summary,
+        last_error,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schedule(interval_sec: u64) -> JobSchedule {
+        JobSchedule {
+            interval: Duration::from_secs(interval_sec),
+            backoff_base: Duration::from_secs(10),
+            backoff_cap: Duration::from_secs(60),
+        }
+    }
+
+    #[derive(Default)]
+    struct FakeRunner {
+        ran: Vec<Job>,
+        fail: Option<Job>,
+    }
+
+    impl JobRunner for FakeRunner {
+        fn run(&mut self, _store: &LocalStore, job: Job) -> Result<String> {
+            self.ran.push(job);
+            if self.fail == Some(job) {
+                anyhow::bail!("boom");
+            }
+            Ok(format!("{} ok", job.as_str()))
+        }
+    }
+
+    #[test]
+    fn delay_after_backs_off_exponentially_up_to_cap() {
+        let s = schedule(300);
+        assert_eq!(s.delay_after(0), Duration::from_secs(300));
+        assert_eq!(s.delay_after(1), Duration::from_secs(10));
+        assert_eq!(s.delay_after(2), Duration::from_secs(20));
+        assert_eq!(s.delay_after(3), Duration::from_secs(40));
+        assert_eq!(s.delay_after(4), Duration::from_secs(60));
+        assert_eq!(s.delay_after(u32::MAX), Duration::from_secs(60));
+    }
+
+    #[test]
+    fn run_due_persists_state_and_backs_off_failing_jobs_independently() {
+        let store = LocalStore::open(":memory:").unwrap();
+        let scheduler = Scheduler::new(vec![
+            (Job::Sync, schedule(30)),
+            (Job::Prune, schedule(3600)),
+        ]);
+        let mut runner = FakeRunner {
+            fail: Some(Job::Sync),
+            ..Default::default()
+        };
+
+        scheduler.run_due(&store, &mut runner, &|| 100).unwrap();
+        assert_eq!(runner.ran, vec![Job::Sync, Job::Prune]);
+
+        let sync = store.get_job_state("sync").unwrap().unwrap();
+        assert_eq!(sync.consecutive_failures, 1);
+        assert_eq!(sync.next_due_unix, 110);
+        assert_eq!(sync.last_ok_unix, None);
+        assert_eq!(sync.last_error.as_deref(), Some("boom"));
+
+        let prune = store.get_job_state("prune").unwrap().unwrap();
+        assert_eq!(prune.consecutive_failures, 0);
+        assert_eq!(prune.next_due_unix, 3700);
+        assert_eq!(prune.last_summary.as_deref(), Some("prune ok"));
+
+        // 아직 기한 전이면 돌지 않는다.
+        runner.ran.clear();
+        scheduler.run_due(&store, &mut runner, &|| 105).unwrap();
+        assert!(runner.ran.is_empty());
+
+        scheduler.run_due(&store, &mut runner, &|| 110).unwrap();
+        assert_eq!(runner.ran, vec![Job::Sync]);
+        let sync = store.get_job_state("sync").unwrap().unwrap();
+        assert_eq!(sync.consecutive_failures, 2);
+        assert_eq!(sync.next_due_unix, 130);
+
+        // 성공하면 실패 횟수를 지우고 원래 주기로 돌아간다.
+        runner.fail = None;
+        scheduler.run_due(&store, &mut runner, &|| 130).unwrap();
+        let sync = store.get_job_state("sync").unwrap().unwrap();
+        assert_eq!(sync.consecutive_failures, 0);
+        assert_eq!(sync.last_ok_unix, Some(130));
+        assert_eq!(sync.last_error, None);
+        assert_eq!(sync.next_due_unix, 160);
+    }
+}
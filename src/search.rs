@@ -1,13 +1,64 @@
 use crate::core::Entry;
-use anyhow::{Context, Result};
+use crate::entry_format::{escape_one_line, unescape_one_line};
+use crate::picker::{self, PickerContext};
+use anyhow::{Context, Result, bail};
 use std::io::Write;
 use std::process::{Command, Stdio};
 
-pub fn select_command(entries: &[Entry]) -> Result<Option<String>> {
+/// `rr search`가 사용할 picker 구현.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SearchBackend {
+    /// `rr` 바이너리에 내장된 TUI picker(기본값).
+    #[default]
+    Builtin,
+    /// 외부 `fzf` 바이너리(opt-in).
+    Fzf,
+}
+
+impl SearchBackend {
+    pub fn parse(value: &str) -> Result<Self> {
+        match value.trim() {
+            "builtin" => Ok(Self::Builtin),
+            "fzf" => Ok(Self::Fzf),
+            other => bail!("unsupported search backend: {other} (expected builtin|fzf)"),
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Builtin => "builtin",
+            Self::Fzf => "fzf",
+        }
+    }
+}
+
+pub fn select_command(
+    entries: &[Entry],
+    backend: SearchBackend,
+    ctx: &PickerContext,
+) -> Result<Option<String>> {
     if entries.is_empty() {
         return Ok(None);
     }
 
+    match backend {
+        SearchBackend::Builtin => picker::pick(entries, ctx),
+        SearchBackend::Fzf => {
+            // fzf는 모드 전환이 없으므로 시작 모드로 미리 걸러서 넘긴다.
+            let entries: Vec<Entry> = entries
+                .iter()
+                .filter(|e| ctx.allows(ctx.mode, e))
+                .cloned()
+                .collect();
+            if entries.is_empty() {
+                return Ok(None);
+            }
+            select_command_with_fzf(&entries)
+        }
+    }
+}
+
+fn select_command_with_fzf(entries: &[Entry]) -> Result<Option<String>> {
     let lines = format_fzf_lines(entries);
     let Some(selected_line) = run_fzf(&lines)? else {
         return Ok(None);
@@ -19,14 +70,10 @@ pub fn select_command(entries: &[Entry]) -> Result<Option<String>> {
 fn format_fzf_lines(entries: &[Entry]) -> Vec<String> {
     entries
         .iter()
-        .map(|e| format!("{}\t{}", e.entry_id, sanitize_one_line(&e.cmd)))
+        .map(|e| format!("{}\t{}", e.entry_id, escape_one_line(&e.cmd)))
         .collect()
 }
 
-fn sanitize_one_line(value: &str) -> String {
-    value.replace(['\n', '\r'], " ")
-}
-
 fn run_fzf(lines: &[String]) -> Result<Option<String>> {
     let mut child = Command::new("fzf")
         .args([
@@ -91,7 +138,8 @@ fn parse_selected_cmd(selected_line: &str) -> Option<String> {
     if cmd.is_empty() {
         None
     } else {
-        Some(cmd.to_string())
+        // fzf에는 한 줄로 이스케이프해서 넘겼으므로 원래 여러 줄 명령으로 되돌린다.
+        Some(unescape_one_line(cmd))
     }
 }
 
@@ -101,25 +149,53 @@ mod tests {
     use time::OffsetDateTime;
 
     #[test]
-    fn sanitize_one_line_replaces_newlines() {
-        assert_eq!(sanitize_one_line("a\nb\rc"), "a b c");
+    fn search_backend_parse() {
+        assert_eq!(
+            SearchBackend::parse("builtin").unwrap(),
+            SearchBackend::Builtin
+        );
+        assert_eq!(SearchBackend::parse("fzf").unwrap(), SearchBackend::Fzf);
+        assert!(SearchBackend::parse("skim").is_err());
+        assert_eq!(SearchBackend::default(), SearchBackend::Builtin);
     }
 
     #[test]
-    fn format_fzf_lines_prefixes_entry_id_and_tab() {
-        let entries = vec![Entry {
-            entry_id: "id-1".to_string(),
+    fn fzf_line_roundtrips_multiline_cmd() {
+        let mut e = entry("id-1", "for i in 1 2; do\n  echo \"$i\"\ndone");
+        let lines = format_fzf_lines(std::slice::from_ref(&e));
+        assert_eq!(lines.len(), 1);
+        assert!(!lines[0].contains('\n'));
+        assert_eq!(parse_selected_cmd(&lines[0]), Some(e.cmd.clone()));
+
+        e.cmd = r"echo a\ b".to_string();
+        assert_eq!(
+            format_fzf_lines(std::slice::from_ref(&e)),
+            vec!["id-1\techo a\\ b"]
+        );
+    }
+
+    fn entry(entry_id: &str, cmd: &str) -> Entry {
+        Entry {
+            entry_id: entry_id.to_string(),
             device_id: "dev1".to_string(),
             user_id: "user1".to_string(),
             ts: OffsetDateTime::from_unix_timestamp(1).unwrap(),
-            cmd: "echo 1".to_string(),
+            cmd: cmd.to_string(),
             cwd: "/tmp".to_string(),
             exit_code: 0,
             duration_ms: 12,
             shell: "zsh".to_string(),
             hostname: "host".to_string(),
             version: "0.1.0".to_string(),
-        }];
+            session_id: None,
+            origin_seq: None,
+            origin_id: None,
+        }
+    }
+
+    #[test]
+    fn format_fzf_lines_prefixes_entry_id_and_tab() {
+        let entries = vec![entry("id-1", "echo 1")];
 
         let lines = format_fzf_lines(&entries);
         assert_eq!(lines, vec!["id-1\techo 1".to_string()]);
@@ -1,3 +1,4 @@
+use crate::core::{SyncHeads, VersionVector};
 use crate::storage::{LocalStore, PullBatch};
 use anyhow::{Context, Result};
 use std::{future::Future, pin::Pin};
@@ -7,6 +8,8 @@ pub struct PullStats {
     pub received: usize,
     pub inserted: usize,
     pub ignored: usize,
+    /// 새로 적용한 tombstone 수.
+    pub deleted: usize,
 }
 
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
@@ -46,9 +49,11 @@ impl SyncRunProgress {
     }
 }
 
-/// peer로부터 pull 기반으로 cursor를 따라잡는다.
+/// peer로부터 pull 기반으로 따라잡는다.
 ///
-/// - cursor는 local의 `peer_state.last_cursor(peer_id)`를 사용한다.
+/// - 요청마다 로컬 `SyncHeads`(origin별로 본 것)를 보내, 서버가 빠진 것만 계산하게 한다.
+///   피어 key가 바뀌거나 어느 쪽 DB가 복원돼도 빠뜨리거나 전부 다시 받지 않는다.
+/// - `peer_state.last_cursor(peer_id)`는 순번 없는 구버전 행과, heads를 모르는 구버전 서버에만 쓴다.
 /// - remote pull은 클로저로 주입한다(HTTP/P2P 등 transport에 독립적).
 pub fn sync_pull_from_peer<F>(
     local: &LocalStore,
@@ -57,20 +62,17 @@ pub fn sync_pull_from_peer<F>(
     mut pull: F,
 ) -> Result<PullStats>
 where
-    F: FnMut(i64, usize) -> Result<PullBatch>,
+    F: FnMut(i64, usize, &SyncHeads) -> Result<PullBatch>,
 {
     if limit == 0 {
         return Ok(PullStats::default());
     }
 
-    let mut cursor = local.get_last_cursor(peer_id)?;
-    let mut received_total = 0usize;
-    let mut inserted_total = 0usize;
-    let mut ignored_total = 0usize;
+    let mut state = PullState::new(local, peer_id)?;
     let mut batch_limit = limit;
 
     loop {
-        let batch = match pull(cursor, batch_limit) {
+        let batch = match pull(state.cursor, batch_limit, &state.heads) {
             Ok(v) => v,
             Err(err) => {
                 if is_payload_too_large_error(&err) {
@@ -83,30 +85,105 @@ where
                 return Err(err);
             }
         };
-        if batch.entries.is_empty() {
+        if !state.apply(batch)? {
             break;
         }
+    }
+
+    Ok(state.stats)
+}
+
+/// pull 루프의 진행 상태. 받은 배치를 적용하고 heads/커서를 올린다.
+struct PullState<'a> {
+    local: &'a LocalStore,
+    peer_id: &'a str,
+    cursor: i64,
+    heads: SyncHeads,
+    stats: PullStats,
+}
+
+impl<'a> PullState<'a> {
+    fn new(local: &'a LocalStore, peer_id: &'a str) -> Result<Self> {
+        Ok(Self {
+            local,
+            peer_id,
+            cursor: local.get_last_cursor(peer_id)?,
+            heads: local.sync_heads(None)?,
+            stats: PullStats::default(),
+        })
+    }
 
-        let stats = local.insert_entries_with_stats(&batch.entries)?;
-        received_total += batch.entries.len();
-        inserted_total += stats.inserted;
-        ignored_total += stats.ignored;
+    /// 배치를 적용한다. 더 받을 것이 없으면 false.
+    fn apply(&mut self, batch: PullBatch) -> Result<bool> {
+        let empty = batch.entries.is_empty() && batch.tombstones.is_empty();
+        let heads_advanced = self.advance_heads(&batch);
+
+        if !empty {
+            // tombstone을 먼저 적용해 같은 배치의 지워진 엔트리가 다시 들어오지 않게 한다.
+            self.stats.deleted += self.local.apply_tombstones(&batch.tombstones)?;
+            let stats = self.local.insert_entries_with_stats(&batch.entries)?;
+            self.stats.received += batch.entries.len();
+            self.stats.inserted += stats.inserted;
+            self.stats.ignored += stats.ignored;
+        }
+
+        if batch.by_heads {
+            // 빈 배치도 서버의 ingest head를 커서로 알려 준다(notify 구독이 이 값을 쓴다).
+            let cursor_advanced = match batch.next_cursor {
+                Some(next) if next > self.cursor => {
+                    self.cursor = next;
+                    self.local.set_last_cursor(self.peer_id, next)?;
+                    true
+                }
+                _ => false,
+            };
+            if empty {
+                return Ok(false);
+            }
+            if !heads_advanced && !cursor_advanced {
+                anyhow::bail!("invalid pull batch: neither heads nor cursor advanced");
+            }
+            return Ok(true);
+        }
 
+        if empty {
+            return Ok(false);
+        }
         let Some(next_cursor) = batch.next_cursor else {
             anyhow::bail!("invalid pull batch: entries is non-empty but next_cursor is None");
         };
-        if next_cursor <= cursor {
+        if next_cursor <= self.cursor {
             anyhow::bail!("invalid pull batch: next_cursor did not advance");
         }
-        cursor = next_cursor;
-        local.set_last_cursor(peer_id, cursor)?;
+        self.cursor = next_cursor;
+        self.local.set_last_cursor(self.peer_id, next_cursor)?;
+        Ok(true)
     }
 
-    Ok(PullStats {
-        received: received_total,
-        inserted: inserted_total,
-        ignored: ignored_total,
-    })
+    fn advance_heads(&mut self, batch: &PullBatch) -> bool {
+        let mut advanced = false;
+        let mut bump = |heads: &mut VersionVector, origin: &str, seq: i64| {
+            let have = heads.entry(origin.to_string()).or_insert(0);
+            if seq > *have {
+                *have = seq;
+                advanced = true;
+            }
+        };
+        for e in &batch.entries {
+            if let Some(seq) = e.origin_seq {
+                bump(&mut self.heads.entries, e.origin_key(), seq);
+            }
+        }
+        for t in &batch.tombstones {
+            if let Some(seq) = t.origin_seq {
+                bump(&mut self.heads.entries, t.origin_key(), seq);
+            }
+            if let (Some(deleted_by), Some(seq)) = (&t.deleted_by, t.delete_seq) {
+                bump(&mut self.heads.deletes, deleted_by, seq);
+            }
+        }
+        advanced
+    }
 }
 
 pub trait Puller {
@@ -114,6 +191,7 @@ pub trait Puller {
         &'a mut self,
         cursor: i64,
         limit: usize,
+        heads: &'a SyncHeads,
     ) -> Pin<Box<dyn Future<Output = Result<PullBatch>> + 'a>>;
 }
 
@@ -121,9 +199,17 @@ pub trait Pusher {
     fn push<'a>(
         &'a mut self,
         entries: Vec<crate::core::Entry>,
+        tombstones: Vec<crate::core::Tombstone>,
     ) -> Pin<Box<dyn Future<Output = Result<()>> + 'a>>;
 }
 
+/// 피어가 가진 origin별 최대 `origin_seq`를 알려준다(gossip push의 협상 단계).
+pub trait VersionVectorSource {
+    fn version_vector<'a>(
+        &'a mut self,
+    ) -> Pin<Box<dyn Future<Output = Result<VersionVector>> + 'a>>;
+}
+
 pub async fn sync_pull_from_peer_async<P>(
     local: &LocalStore,
     peer_id: &str,
@@ -137,14 +223,11 @@ where
         return Ok(PullStats::default());
     }
 
-    let mut cursor = local.get_last_cursor(peer_id)?;
-    let mut received_total = 0usize;
-    let mut inserted_total = 0usize;
-    let mut ignored_total = 0usize;
+    let mut state = PullState::new(local, peer_id)?;
     let mut batch_limit = limit;
 
     loop {
-        let batch = match puller.pull(cursor, batch_limit).await {
+        let batch = match puller.pull(state.cursor, batch_limit, &state.heads).await {
             Ok(v) => v,
             Err(err) => {
                 if is_payload_too_large_error(&err) {
@@ -157,30 +240,12 @@ where
                 return Err(err);
             }
         };
-        if batch.entries.is_empty() {
+        if !state.apply(batch)? {
             break;
         }
-
-        let stats = local.insert_entries_with_stats(&batch.entries)?;
-        received_total += batch.entries.len();
-        inserted_total += stats.inserted;
-        ignored_total += stats.ignored;
-
-        let Some(next_cursor) = batch.next_cursor else {
-            anyhow::bail!("invalid pull batch: entries is non-empty but next_cursor is None");
-        };
-        if next_cursor <= cursor {
-            anyhow::bail!("invalid pull batch: next_cursor did not advance");
-        }
-        cursor = next_cursor;
-        local.set_last_cursor(peer_id, cursor)?;
     }
 
-    Ok(PullStats {
-        received: received_total,
-        inserted: inserted_total,
-        ignored: ignored_total,
-    })
+    Ok(state.stats)
 }
 
 /// local에서 peer로 push 기반으로 cursor를 따라잡는다.
@@ -195,7 +260,7 @@ pub fn sync_push_to_peer<F>(
     mut push: F,
 ) -> Result<usize>
 where
-    F: FnMut(Vec<crate::core::Entry>) -> Result<()>,
+    F: FnMut(Vec<crate::core::Entry>, Vec<crate::core::Tombstone>) -> Result<()>,
 {
     if limit == 0 {
         return Ok(0);
@@ -212,13 +277,13 @@ where
             }
             None => local.pull_since_cursor(cursor, batch_limit)?,
         };
-        if batch.entries.is_empty() {
+        if batch.entries.is_empty() && batch.tombstones.is_empty() {
             break;
         }
 
         let entries = batch.entries;
-        let entries_len = entries.len();
-        match push(entries) {
+        let batch_len = entries.len() + batch.tombstones.len();
+        match push(entries, batch.tombstones) {
             Ok(()) => {}
             Err(err) => {
                 if is_payload_too_large_error(&err) {
@@ -231,7 +296,7 @@ where
                 return Err(err);
             }
         }
-        pushed_total += entries_len;
+        pushed_total += batch_len;
 
         let Some(next_cursor) = batch.next_cursor else {
             anyhow::bail!("invalid local push batch: entries is non-empty but next_cursor is None");
@@ -271,13 +336,13 @@ where
             }
             None => local.pull_since_cursor(cursor, batch_limit)?,
         };
-        if batch.entries.is_empty() {
+        if batch.entries.is_empty() && batch.tombstones.is_empty() {
             break;
         }
 
         let entries = batch.entries;
-        let entries_len = entries.len();
-        match pusher.push(entries).await {
+        let batch_len = entries.len() + batch.tombstones.len();
+        match pusher.push(entries, batch.tombstones).await {
             Ok(()) => {}
             Err(err) => {
                 if is_payload_too_large_error(&err) {
@@ -290,7 +355,7 @@ where
                 return Err(err);
             }
         }
-        pushed_total += entries_len;
+        pushed_total += batch_len;
 
         let Some(next_cursor) = batch.next_cursor else {
             anyhow::bail!("invalid local push batch: entries is non-empty but next_cursor is None");
@@ -305,6 +370,63 @@ where
     Ok(pushed_total)
 }
 
+/// 다른 디바이스의 엔트리까지 포함해, 피어가 아직 갖지 않은 `user_id`의 엔트리를 push한다(다중 홉 전달).
+///
+/// - 먼저 피어의 버전 벡터를 받아, origin별로 그보다 큰 `origin_seq`만 origin 순서대로 보낸다.
+/// - 보낸 만큼 로컬에 든 피어 버전 벡터를 올리므로, 피어가 무시(중복/tombstone)해도 다시 보내지 않는다.
+/// - 받는 쪽이 이미 가진 것은 보내지 않으므로 A→B→C→A처럼 돌아도 같은 엔트리가 계속 돌지 않는다.
+/// - tombstone은 기존 push(모든 tombstone을 ingest 커서로 전송)가 이미 다중 홉으로 전파하므로 여기서는 다루지 않는다.
+pub async fn sync_gossip_to_peer_async<P>(
+    local: &LocalStore,
+    user_id: &str,
+    limit: usize,
+    peer: &mut P,
+) -> Result<usize>
+where
+    P: Pusher + VersionVectorSource,
+{
+    if limit == 0 {
+        return Ok(0);
+    }
+
+    let mut remote = peer.version_vector().await?;
+    let mut pushed_total = 0usize;
+    let mut batch_limit = limit;
+
+    loop {
+        let entries = local.pull_missing_by_origin(&remote, user_id, batch_limit)?;
+        if entries.is_empty() {
+            break;
+        }
+
+        let sent: Vec<(String, i64)> = entries
+            .iter()
+            .filter_map(|e| e.origin_seq.map(|seq| (e.origin_key().to_string(), seq)))
+            .collect();
+        match peer.push(entries, Vec::new()).await {
+            Ok(()) => {}
+            Err(err) => {
+                if is_payload_too_large_error(&err) {
+                    if batch_limit <= 1 {
+                        return Err(err).context("gossip batch too large even with limit=1");
+                    }
+                    batch_limit = (batch_limit / 2).max(1);
+                    continue;
+                }
+                return Err(err);
+            }
+        }
+        pushed_total += sent.len();
+
+        for (origin, seq) in sent {
+            let have = remote.entry(origin).or_insert(0);
+            *have = (*have).max(seq);
+        }
+    }
+
+    Ok(pushed_total)
+}
+
 pub(crate) fn is_payload_too_large_error(err: &anyhow::Error) -> bool {
     err.chain().any(|cause| {
         if let Some(e) = cause.downcast_ref::<ureq::Error>() {
@@ -342,9 +464,34 @@ mod tests {
             &'a mut self,
             cursor: i64,
             limit: usize,
+            heads: &'a SyncHeads,
         ) -> Pin<Box<dyn Future<Output = Result<PullBatch>> + 'a>> {
             let remote = self.remote;
-            Box::pin(async move { remote.pull_since_cursor(cursor, limit) })
+            Box::pin(async move { remote.pull_by_heads(heads, cursor, limit, None) })
+        }
+    }
+
+    struct StoreGossipPeer<'a> {
+        remote: &'a LocalStore,
+    }
+
+    impl Pusher for StoreGossipPeer<'_> {
+        fn push<'a>(
+            &'a mut self,
+            entries: Vec<Entry>,
+            _tombstones: Vec<crate::core::Tombstone>,
+        ) -> Pin<Box<dyn Future<Output = Result<()>> + 'a>> {
+            let remote = self.remote;
+            Box::pin(async move { remote.insert_entries_with_stats(&entries).map(|_| ()) })
+        }
+    }
+
+    impl VersionVectorSource for StoreGossipPeer<'_> {
+        fn version_vector<'a>(
+            &'a mut self,
+        ) -> Pin<Box<dyn Future<Output = Result<VersionVector>> + 'a>> {
+            let remote = self.remote;
+            Box::pin(async move { remote.version_vector(Some("user1")) })
         }
     }
 
@@ -361,6 +508,9 @@ mod tests {
             shell: "zsh".to_string(),
             hostname: "host".to_string(),
             version: "0.1.0".to_string(),
+            session_id: None,
+            origin_seq: None,
+            origin_id: None,
         }
     }
 
@@ -410,7 +560,7 @@ mod tests {
             .insert_entries(&[entry("id-1", 1, "echo 1"), entry("id-2", 2, "echo 2")])
             .unwrap();
 
-        let pulled = sync_pull_from_peer(&local, "peer-1", 100, |cursor, limit| {
+        let pulled = sync_pull_from_peer(&local, "peer-1", 100, |cursor, limit, _heads| {
             remote.pull_since_cursor(cursor, limit)
         })
         .unwrap();
@@ -431,7 +581,7 @@ mod tests {
             .insert_entries(&[entry("id-1", 1, "echo 1"), entry("id-2", 2, "echo 2")])
             .unwrap();
 
-        let a = sync_pull_from_peer(&local, "peer-1", 1, |cursor, limit| {
+        let a = sync_pull_from_peer(&local, "peer-1", 1, |cursor, limit, _heads| {
             remote.pull_since_cursor(cursor, limit)
         })
         .unwrap();
@@ -440,7 +590,7 @@ mod tests {
         assert_eq!(a.ignored, 0);
         assert_eq!(local.list_recent(10).unwrap().len(), 2);
 
-        let b = sync_pull_from_peer(&local, "peer-1", 1, |cursor, limit| {
+        let b = sync_pull_from_peer(&local, "peer-1", 1, |cursor, limit, _heads| {
             remote.pull_since_cursor(cursor, limit)
         })
         .unwrap();
@@ -509,7 +659,7 @@ mod tests {
             .insert_entries(&[entry("id-1", 1, "echo 1"), entry("id-2", 2, "echo 2")])
             .unwrap();
 
-        let a = sync_push_to_peer(&local, "peer-1", 1, Some("dev1"), |entries| {
+        let a = sync_push_to_peer(&local, "peer-1", 1, Some("dev1"), |entries, _| {
             remote.insert_entries(&entries)?;
             Ok(())
         })
@@ -517,7 +667,7 @@ mod tests {
         assert_eq!(a, 2);
         assert_eq!(remote.list_recent(10).unwrap().len(), 2);
 
-        let b = sync_push_to_peer(&local, "peer-1", 1, Some("dev1"), |entries| {
+        let b = sync_push_to_peer(&local, "peer-1", 1, Some("dev1"), |entries, _| {
             remote.insert_entries(&entries)?;
             Ok(())
         })
@@ -534,7 +684,7 @@ mod tests {
             .insert_entries(&[entry("id-1", 1, "echo 1"), entry("id-2", 2, "echo 2")])
             .unwrap();
 
-        let err = sync_push_to_peer(&local, "peer-1", 100, Some("dev1"), |_entries| {
+        let err = sync_push_to_peer(&local, "peer-1", 100, Some("dev1"), |_entries, _| {
             anyhow::bail!("network error");
         })
         .unwrap_err();
@@ -556,7 +706,7 @@ mod tests {
 
         local.insert_entries(&[e1, e2, e3]).unwrap();
 
-        let pushed = sync_push_to_peer(&local, "peer-1", 100, Some("dev-local"), |entries| {
+        let pushed = sync_push_to_peer(&local, "peer-1", 100, Some("dev-local"), |entries, _| {
             remote.insert_entries(&entries)?;
             Ok(())
         })
@@ -585,7 +735,7 @@ mod tests {
             .unwrap();
 
         let mut call_limits: Vec<usize> = Vec::new();
-        let pulled = sync_pull_from_peer(&local, "peer-1", 8, |cursor, limit| {
+        let pulled = sync_pull_from_peer(&local, "peer-1", 8, |cursor, limit, _heads| {
             call_limits.push(limit);
             if limit > 1 {
                 return Err(payload_too_large_err());
@@ -619,7 +769,7 @@ mod tests {
         local.insert_entries(&[e1, e2, e3]).unwrap();
 
         let mut call_sizes: Vec<usize> = Vec::new();
-        let pushed = sync_push_to_peer(&local, "peer-1", 8, Some("dev-local"), |entries| {
+        let pushed = sync_push_to_peer(&local, "peer-1", 8, Some("dev-local"), |entries, _| {
             call_sizes.push(entries.len());
             if entries.len() > 1 {
                 return Err(payload_too_large_err());
@@ -636,4 +786,177 @@ mod tests {
         assert!(call_sizes.contains(&1));
         assert!(call_sizes.first().copied().unwrap_or(0) > 1);
     }
+
+    #[test]
+    fn gossip_forwards_other_devices_across_hops_without_resending() {
+        let a = LocalStore::open(":memory:").unwrap();
+        let b = LocalStore::open(":memory:").unwrap();
+        let c = LocalStore::open(":memory:").unwrap();
+
+        let mut a1 = entry("a-1", 1, "echo a1");
+        a1.device_id = "dev-a".to_string();
+        let mut a2 = entry("a-2", 2, "echo a2");
+        a2.device_id = "dev-a".to_string();
+        a.insert_local_entries(&[a1, a2]).unwrap();
+
+        let mut b1 = entry("b-1", 3, "echo b1");
+        b1.device_id = "dev-b".to_string();
+        b.insert_local_entries(&[b1]).unwrap();
+
+        let mut other_user = entry("x-1", 4, "echo other");
+        other_user.device_id = "dev-x".to_string();
+        other_user.user_id = "user2".to_string();
+        b.insert_local_entries(&[other_user]).unwrap();
+
+        let gossip = |from: &LocalStore, to: &LocalStore| {
+            executor::block_on(sync_gossip_to_peer_async(
+                from,
+                "user1",
+                1,
+                &mut StoreGossipPeer { remote: to },
+            ))
+            .unwrap()
+        };
+
+        // A -> B -> C: C는 A와 직접 연결된 적 없이도 A의 엔트리를 받는다.
+        assert_eq!(gossip(&a, &b), 2);
+        assert_eq!(gossip(&b, &c), 3);
+        assert_eq!(c.list_recent(10).unwrap().len(), 3);
+
+        let got = c.version_vector(None).unwrap();
+        assert_eq!(got.get(&a.origin_id().unwrap()), Some(&2));
+        assert_eq!(got.get(&b.origin_id().unwrap()), Some(&1));
+        assert_eq!(got.len(), 2);
+
+        // C -> A로 돌아와도 A가 이미 가진 자기 엔트리는 다시 보내지 않는다.
+        assert_eq!(gossip(&c, &a), 1);
+        assert!(
+            a.list_recent(10)
+                .unwrap()
+                .iter()
+                .any(|e| e.entry_id == "b-1")
+        );
+
+        // 모두 수렴한 뒤에는 어느 방향으로도 보낼 것이 없다.
+        assert_eq!(gossip(&a, &b), 0);
+        assert_eq!(gossip(&b, &c), 0);
+        assert_eq!(gossip(&c, &a), 0);
+        assert_eq!(a.version_vector(Some("user1")).unwrap(), got);
+    }
+
+    #[test]
+    fn pull_by_heads_survives_peer_rekey_and_restored_remote() {
+        let local = LocalStore::open(":memory:").unwrap();
+        let remote = LocalStore::open(":memory:").unwrap();
+
+        let forwarded = |id: &str, seq: i64| {
+            let mut e = entry(id, seq, id);
+            e.device_id = "dev-a".to_string();
+            e.origin_seq = Some(seq);
+            e
+        };
+        remote
+            .insert_entries_with_stats(&[forwarded("a-1", 1), forwarded("a-2", 2)])
+            .unwrap();
+
+        let pull = |key: &str, remote: &LocalStore| {
+            executor::block_on(sync_pull_from_peer_async(
+                &local,
+                key,
+                1,
+                &mut StorePuller { remote },
+            ))
+            .unwrap()
+        };
+
+        assert_eq!(pull("peer-1", &remote).inserted, 2);
+        assert_eq!(local.get_last_cursor("peer-1").unwrap(), 2);
+
+        // 같은 피어를 다른 key로 만나도 이미 받은 것을 다시 받지 않는다.
+        assert_eq!(pull("peer-1-rekeyed", &remote).received, 0);
+
+        // 피어 DB가 백업에서 복원돼 ingest_seq가 되돌아가도 새 엔트리를 빠뜨리지 않는다.
+        let restored = LocalStore::open(":memory:").unwrap();
+        restored
+            .insert_entries_with_stats(&[forwarded("a-3", 3)])
+            .unwrap();
+        assert_eq!(restored.latest_ingest_seq().unwrap(), 1);
+        let stats = pull("peer-1", &restored);
+        assert_eq!(stats.inserted, 1);
+        assert_eq!(local.list_recent(10).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn restored_origin_switches_origin_id_and_still_replicates() {
+        let dir = tempfile::tempdir().unwrap();
+        let origin_path = dir.path().join("origin.db");
+        let backups = [
+            dir.path().join("backup-1.db"),
+            dir.path().join("backup-2.db"),
+        ];
+        let origin_path = origin_path.to_str().unwrap();
+
+        let origin = LocalStore::open(origin_path).unwrap();
+        origin
+            .insert_local_entries(&[entry("a-1", 1, "echo a1")])
+            .unwrap();
+        drop(origin);
+        for backup in &backups {
+            std::fs::copy(origin_path, backup).unwrap();
+        }
+        let origin = LocalStore::open(origin_path).unwrap();
+        origin
+            .insert_local_entries(&[entry("a-2", 2, "echo a2"), entry("a-3", 3, "echo a3")])
+            .unwrap();
+
+        let peer = LocalStore::open(":memory:").unwrap();
+        let pull = |local: &LocalStore, remote: &LocalStore| {
+            executor::block_on(sync_pull_from_peer_async(
+                local,
+                "peer",
+                10,
+                &mut StorePuller { remote },
+            ))
+            .unwrap()
+        };
+        assert_eq!(pull(&peer, &origin).inserted, 3);
+
+        // 백업에서 복원한 DB는 예전 origin id로 피어가 이미 가진 순번(2)을 다시 쓴다.
+        let restored = LocalStore::open(backups[0].to_str().unwrap()).unwrap();
+        let old_id = restored.origin_id().unwrap();
+        assert_eq!(old_id, origin.origin_id().unwrap());
+        restored
+            .insert_local_entries(&[entry("r-2", 4, "echo restored")])
+            .unwrap();
+
+        // 피어에게서 더 큰 순번을 받으면 새 origin으로 갈아타고, 놓친 예전 엔트리는 다음 pull에서 받는다.
+        assert_eq!(pull(&restored, &peer).inserted, 1);
+        let new_id = restored.origin_id().unwrap();
+        assert_ne!(new_id, old_id);
+        assert_eq!(pull(&restored, &peer).inserted, 1);
+        assert_eq!(restored.list_recent(10).unwrap().len(), 4);
+
+        // 복원 뒤 기록한 엔트리도 피어에 간다.
+        restored
+            .insert_local_entries(&[entry("r-3", 5, "echo restored again")])
+            .unwrap();
+        assert_eq!(pull(&peer, &restored).inserted, 2);
+        let ids: Vec<_> = peer
+            .list_recent(10)
+            .unwrap()
+            .into_iter()
+            .map(|e| e.entry_id)
+            .collect();
+        assert_eq!(ids, vec!["r-3", "r-2", "a-3", "a-2", "a-1"]);
+        assert_eq!(peer.version_vector(None).unwrap().get(&new_id), Some(&3));
+
+        // 먼저 pull 받는 쪽이 복원된 DB여도(피어가 heads로 알려 줌) 갈아탄다.
+        let restored_again = LocalStore::open(backups[1].to_str().unwrap()).unwrap();
+        assert_eq!(restored_again.origin_id().unwrap(), old_id);
+        restored_again
+            .insert_local_entries(&[entry("s-2", 6, "echo served")])
+            .unwrap();
+        assert_eq!(pull(&peer, &restored_again).inserted, 1);
+        assert_ne!(restored_again.origin_id().unwrap(), old_id);
+    }
 }
@@ -1,7 +1,7 @@
 use anyhow::{Context, Result};
 use libp2p::PeerId;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::io::Read;
 use std::sync::{Arc, Mutex};
 use time::OffsetDateTime;
@@ -20,6 +20,73 @@ pub struct RegisterRequest {
     pub addrs: Vec<String>,
     #[serde(default)]
     pub meta: Option<PeerMeta>,
+    /// 서명 시각(unix ms). 같은 peer_id에 대해 이전보다 커야 한다(재전송 방지).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp_ms: Option<i64>,
+    /// (peer_id, addrs, meta, timestamp_ms)에 대한 identity 키 서명(base64url).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+}
+
+/// 서명 시각과 tracker 시각의 최대 허용 차이.
+const MAX_REGISTER_CLOCK_SKEW_MS: i64 = 5 * 60 * 1000;
+
+impl RegisterRequest {
+    fn signing_payload(&self, timestamp_ms: i64) -> Result<Vec<u8>> {
+        let mut out = b"rustory/tracker-register/v1\0".to_vec();
+        serde_json::to_writer(
+            &mut out,
+            &(&self.peer_id, &self.addrs, &self.meta, timestamp_ms),
+        )
+        .context("encode register signing payload")?;
+        Ok(out)
+    }
+
+    /// `timestamp_ms`/`signature`를 채운다. `peer_id`는 `identity`의 PeerId여야 tracker가 받아들인다.
+    pub fn sign(&mut self, identity: &libp2p::identity::Keypair, timestamp_ms: i64) -> Result<()> {
+        use base64::Engine;
+
+        let payload = self.signing_payload(timestamp_ms)?;
+        let signature = identity
+            .sign(&payload)
+            .map_err(|err| anyhow::anyhow!("sign register request: {err}"))?;
+        self.timestamp_ms = Some(timestamp_ms);
+        self.signature = Some(base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(signature));
+        Ok(())
+    }
+
+    /// 서명이 있으면 검증하고 서명 시각을 돌려준다. 서명이 없으면 `Ok(None)`.
+    ///
+    /// 공개키는 PeerId에 들어 있는 것(ed25519 identity multihash)을 쓴다.
+    fn verify(&self, peer_id: &PeerId, now_ms: i64) -> std::result::Result<Option<i64>, String> {
+        use base64::Engine;
+
+        let (timestamp_ms, signature) = match (self.timestamp_ms, self.signature.as_deref()) {
+            (None, None) => return Ok(None),
+            (Some(ts), Some(sig)) => (ts, sig),
+            _ => return Err("timestamp_ms and signature must be sent together".to_string()),
+        };
+
+        let multihash = peer_id.as_ref();
+        if multihash.code() != 0 {
+            return Err("peer_id does not embed a public key".to_string());
+        }
+        let public = libp2p::identity::PublicKey::try_decode_protobuf(multihash.digest())
+            .map_err(|_| "peer_id does not embed a valid public key".to_string())?;
+        let signature = base64::engine::general_purpose::URL_SAFE_NO_PAD
+            .decode(signature)
+            .map_err(|_| "invalid signature encoding".to_string())?;
+        let payload = self
+            .signing_payload(timestamp_ms)
+            .map_err(|err| format!("{err:#}"))?;
+        if !public.verify(&payload, &signature) {
+            return Err("invalid signature".to_string());
+        }
+        if (now_ms - timestamp_ms).abs() > MAX_REGISTER_CLOCK_SKEW_MS {
+            return Err("timestamp_ms is outside the allowed clock skew".to_string());
+        }
+        Ok(Some(timestamp_ms))
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -41,34 +108,508 @@ pub struct ListResponse {
     pub peers: Vec<PeerInfo>,
 }
 
+/// tracker 설정 파일(`tracker-serve --config`). 테넌트별 토큰과 관리자 토큰을 정의한다.
+///
+/// ```toml
+/// admin_token = "admin-secret"
+///
+/// [[tenants]]
+/// name = "alice"
+/// tokens = ["alice-laptop-token", "alice-desktop-token"]
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct TrackerConfigFile {
+    #[serde(default)]
+    pub admin_token: Option<String>,
+    #[serde(default)]
+    pub tenants: Vec<TenantConfig>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TenantConfig {
+    pub name: String,
+    #[serde(default)]
+    pub tokens: Vec<String>,
+}
+
+/// 요청 토큰으로 접근 범위를 정한다.
+///
+/// - 테넌트 모드(설정 파일에 테넌트가 있음): 토큰이 속한 테넌트의 피어만 등록/조회한다.
+/// - 단일 토큰 모드(`--token`): 기존처럼 토큰 하나로 전체에 접근한다.
+#[derive(Debug, Clone, Default)]
+pub struct TrackerAuth {
+    token: Option<String>,
+    admin_token: Option<String>,
+    tenants: Vec<TenantConfig>,
+    tenant_by_token: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Access {
+    /// `None`은 단일 토큰/무인증 모드의 전체 범위.
+    Scope(Option<String>),
+    Admin,
+    Denied,
+}
+
+impl TrackerAuth {
+    pub fn single_token(token: Option<String>) -> Self {
+        Self {
+            token: token
+                .map(|t| t.trim().to_string())
+                .filter(|t| !t.is_empty()),
+            ..Self::default()
+        }
+    }
+
+    pub fn from_config_file(path: &str) -> Result<Self> {
+        let path = crate::config::expand_home_path(path)?;
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("read tracker config: {}", path.display()))?;
+        let file: TrackerConfigFile = toml::from_str(&content)
+            .with_context(|| format!("parse tracker config: {}", path.display()))?;
+        Self::from_file_config(file)
+    }
+
+    fn from_file_config(file: TrackerConfigFile) -> Result<Self> {
+        let admin_token = file
+            .admin_token
+            .map(|t| t.trim().to_string())
+            .filter(|t| !t.is_empty());
+
+        let mut names = HashSet::new();
+        let mut tenant_by_token = HashMap::new();
+        let mut tenants = Vec::new();
+        for tenant in file.tenants {
+            let name = tenant.name.trim().to_string();
+            if name.is_empty() {
+                anyhow::bail!("tracker config: tenant name is empty");
+            }
+            if !names.insert(name.clone()) {
+                anyhow::bail!("tracker config: duplicate tenant {name}");
+            }
+            let mut tokens = Vec::new();
+            for token in tenant.tokens {
+                let token = token.trim().to_string();
+                if token.is_empty() {
+                    anyhow::bail!("tracker config: empty token in tenant {name}");
+                }
+                if admin_token.as_deref() == Some(token.as_str()) {
+                    anyhow::bail!("tracker config: tenant {name} reuses the admin token");
+                }
+                if tenant_by_token
+                    .insert(token.clone(), name.clone())
+                    .is_some()
+                {
+                    anyhow::bail!("tracker config: token is shared by more than one tenant");
+                }
+                tokens.push(token);
+            }
+            tenants.push(TenantConfig { name, tokens });
+        }
+        // 테넌트가 없으면 모든 요청이 단일 토큰 없는 모드(인증 없음)로 떨어진다.
+        if tenants.is_empty() {
+            anyhow::bail!("tracker config: at least one [[tenants]] entry is required");
+        }
+
+        Ok(Self {
+            token: None,
+            admin_token,
+            tenants,
+            tenant_by_token,
+        })
+    }
+
+    pub fn tenant_count(&self) -> usize {
+        self.tenants.len()
+    }
+
+    fn is_multi_tenant(&self) -> bool {
+        !self.tenants.is_empty()
+    }
+
+    fn resolve(&self, bearer: Option<&str>, revoked: &HashSet<String>) -> Access {
+        if let (Some(admin), Some(bearer)) = (self.admin_token.as_deref(), bearer)
+            && admin == bearer
+        {
+            return Access::Admin;
+        }
+
+        if self.is_multi_tenant() {
+            return match bearer.and_then(|b| self.tenant_by_token.get(b).map(|t| (b, t))) {
+                Some((b, tenant)) if !revoked.contains(&token_fingerprint(b)) => {
+                    Access::Scope(Some(tenant.clone()))
+                }
+                _ => Access::Denied,
+            };
+        }
+
+        match self.token.as_deref() {
+            None => Access::Scope(None),
+            Some(token) if bearer == Some(token) => Access::Scope(None),
+            Some(_) => Access::Denied,
+        }
+    }
+}
+
+/// 토큰을 드러내지 않고 관리 API에서 가리키기 위한 짧은 지문.
+pub fn token_fingerprint(token: &str) -> String {
+    use sha2::{Digest, Sha256};
+
+    let digest = Sha256::new()
+        .chain_update(b"rustory/tracker-token\0")
+        .chain_update(token.as_bytes())
+        .finalize();
+    digest[..8].iter().map(|b| format!("{b:02x}")).collect()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdminTenantsResponse {
+    pub tenants: Vec<AdminTenant>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdminTenant {
+    pub name: String,
+    pub peers: usize,
+    pub tokens: Vec<AdminToken>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdminToken {
+    pub fingerprint: String,
+    pub revoked: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RevokeTokenRequest {
+    pub fingerprint: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RevokeTokenResponse {
+    pub ok: bool,
+    pub tenant: String,
+    pub fingerprint: String,
+}
+
+/// tracker-serve 동작 옵션.
+#[derive(Debug, Clone, Default)]
+pub struct TrackerOptions {
+    pub ttl_sec: u64,
+    pub auth: TrackerAuth,
+    /// 서명 없는 등록도 받는다(구버전 클라이언트 전환용).
+    pub allow_unsigned: bool,
+}
+
 #[derive(Debug, Clone)]
 struct PeerRecord {
+    /// 등록한 토큰의 테넌트(테넌트 모드가 아니면 `None`).
+    tenant: Option<String>,
     addrs: Vec<String>,
     meta: Option<PeerMeta>,
     last_seen_unix: i64,
+    /// 마지막으로 받아들인 서명 등록의 시각. 있으면 서명 없는 갱신은 거부한다.
+    signed_at_ms: Option<i64>,
 }
 
 #[derive(Debug, Default)]
 struct TrackerState {
     peers: HashMap<String, PeerRecord>,
+    /// 관리 API로 폐기한 테넌트 토큰의 지문.
+    revoked: HashSet<String>,
+    /// peer_id별로 마지막으로 받아들인 서명 등록 시각. 레코드가 TTL로 사라져도 서명이 시계 오차
+    /// 범위(`MAX_REGISTER_CLOCK_SKEW_MS`) 안에 있는 동안은 남겨 두어 같은 등록의 재전송을 막는다.
+    last_signed_ms: HashMap<String, i64>,
+    /// `--db`를 주면 레코드를 SQLite에도 써서, 재시작 직후에도 목록을 돌려줄 수 있게 한다.
+    db: Option<TrackerDb>,
 }
 
-pub fn serve(bind: &str, ttl_sec: u64, token: Option<String>) -> Result<()> {
-    let state = Arc::new(Mutex::new(TrackerState::default()));
-    serve_http(bind, ttl_sec, token, state)
+impl TrackerState {
+    fn open_persistent(path: &str, now: OffsetDateTime, ttl_sec: u64) -> Result<Self> {
+        let db = TrackerDb::open(path)?;
+        let mut state = Self {
+            peers: db.load()?,
+            revoked: db.load_revoked()?,
+            last_signed_ms: db.load_signed()?,
+            db: Some(db),
+        };
+        prune_expired(&mut state, now, ttl_sec);
+        Ok(state)
+    }
+
+    fn upsert(&mut self, peer_id: String, rec: PeerRecord) -> Result<()> {
+        if let Some(db) = self.db.as_ref() {
+            db.upsert(&peer_id, &rec)?;
+            if let Some(ts) = rec.signed_at_ms {
+                db.upsert_signed(&peer_id, ts)?;
+            }
+        }
+        if let Some(ts) = rec.signed_at_ms {
+            self.last_signed_ms.insert(peer_id.clone(), ts);
+        }
+        self.peers.insert(peer_id, rec);
+        Ok(())
+    }
+
+    fn revoke(&mut self, fingerprint: &str, now_unix: i64) -> Result<()> {
+        if let Some(db) = self.db.as_ref() {
+            db.insert_revoked(fingerprint, now_unix)?;
+        }
+        self.revoked.insert(fingerprint.to_string());
+        Ok(())
+    }
 }
 
-fn serve_http(
-    bind: &str,
-    ttl_sec: u64,
-    token: Option<String>,
-    state: Arc<Mutex<TrackerState>>,
-) -> Result<()> {
+pub fn serve(bind: &str, opts: TrackerOptions, db_path: Option<&str>) -> Result<()> {
+    let ttl_sec = opts.ttl_sec;
+    let state = match db_path {
+        Some(path) => {
+            let state = TrackerState::open_persistent(path, OffsetDateTime::now_utc(), ttl_sec)?;
+            eprintln!("tracker db: {path} (restored peers={})", state.peers.len());
+            state
+        }
+        None => TrackerState::default(),
+    };
+    let state = Arc::new(Mutex::new(state));
+    spawn_prune_loop(state.clone(), ttl_sec);
+    serve_http(bind, &opts, state)
+}
+
+/// 요청이 없어도 만료 레코드(특히 DB 행)가 쌓이지 않도록 주기적으로 정리한다.
+fn spawn_prune_loop(state: Arc<Mutex<TrackerState>>, ttl_sec: u64) {
+    let interval = std::time::Duration::from_secs(ttl_sec.clamp(1, 60));
+    std::thread::spawn(move || {
+        loop {
+            std::thread::sleep(interval);
+            let mut locked = state.lock().unwrap();
+            prune_expired(&mut locked, OffsetDateTime::now_utc(), ttl_sec);
+        }
+    });
+}
+
+#[derive(Debug)]
+struct TrackerDb {
+    conn: rusqlite::Connection,
+}
+
+impl TrackerDb {
+    fn open(path: &str) -> Result<Self> {
+        let path = crate::config::expand_home_path(path)?;
+        if let Some(parent) = path.parent()
+            && !parent.as_os_str().is_empty()
+        {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("create dir: {}", parent.display()))?;
+        }
+
+        let conn = rusqlite::Connection::open(&path)
+            .with_context(|| format!("open tracker db: {}", path.display()))?;
+        conn.busy_timeout(std::time::Duration::from_secs(5))
+            .context("set sqlite busy_timeout")?;
+        conn.execute_batch(
+            r#"
+CREATE TABLE IF NOT EXISTS tracker_peers (
+  peer_id TEXT PRIMARY KEY,
+  addrs_json TEXT NOT NULL,
+  meta_json TEXT,
+  last_seen_unix INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_tracker_peers_last_seen ON tracker_peers(last_seen_unix);
+
+CREATE TABLE IF NOT EXISTS tracker_revoked_tokens (
+  fingerprint TEXT PRIMARY KEY,
+  revoked_at INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS tracker_signed_registrations (
+  peer_id TEXT PRIMARY KEY,
+  signed_at_ms INTEGER NOT NULL
+);
+"#,
+        )
+        .context("init tracker schema")?;
+        ensure_tracker_column(&conn, "signed_at_ms", "INTEGER")?;
+        ensure_tracker_column(&conn, "tenant", "TEXT")?;
+        Ok(Self { conn })
+    }
+
+    fn load(&self) -> Result<HashMap<String, PeerRecord>> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT peer_id, addrs_json, meta_json, last_seen_unix, signed_at_ms, tenant FROM tracker_peers",
+            )
+            .context("prepare load tracker_peers")?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, Option<String>>(2)?,
+                    row.get::<_, i64>(3)?,
+                    row.get::<_, Option<i64>>(4)?,
+                    row.get::<_, Option<String>>(5)?,
+                ))
+            })
+            .context("query tracker_peers")?;
+
+        let mut peers = HashMap::new();
+        for row in rows {
+            let (peer_id, addrs_json, meta_json, last_seen_unix, signed_at_ms, tenant) = row?;
+            let addrs = serde_json::from_str(&addrs_json)
+                .with_context(|| format!("parse addrs of tracker peer {peer_id}"))?;
+            let meta = match meta_json {
+                Some(v) => Some(
+                    serde_json::from_str(&v)
+                        .with_context(|| format!("parse meta of tracker peer {peer_id}"))?,
+                ),
+                None => None,
+            };
+            peers.insert(
+                peer_id,
+                PeerRecord {
+                    tenant,
+                    addrs,
+                    meta,
+                    last_seen_unix,
+                    signed_at_ms,
+                },
+            );
+        }
+        Ok(peers)
+    }
+
+    fn upsert(&self, peer_id: &str, rec: &PeerRecord) -> Result<()> {
+        let addrs_json = serde_json::to_string(&rec.addrs).context("serialize addrs")?;
+        let meta_json = rec
+            .meta
+            .as_ref()
+            .map(serde_json::to_string)
+            .transpose()
+            .context("serialize meta")?;
+        self.conn
+            .execute(
+                r#"
+INSERT INTO tracker_peers(peer_id, addrs_json, meta_json, last_seen_unix, signed_at_ms, tenant)
+VALUES (?, ?, ?, ?, ?, ?)
+ON CONFLICT(peer_id) DO UPDATE SET
+  addrs_json = excluded.addrs_json,
+  meta_json = excluded.meta_json,
+  last_seen_unix = excluded.last_seen_unix,
+  signed_at_ms = excluded.signed_at_ms,
+  tenant = excluded.tenant
+"#,
+                rusqlite::params![
+                    peer_id,
+                    addrs_json,
+                    meta_json,
+                    rec.last_seen_unix,
+                    rec.signed_at_ms,
+                    rec.tenant
+                ],
+            )
+            .context("upsert tracker_peers")?;
+        Ok(())
+    }
+
+    /// `last_seen_unix < cutoff`인 행을 지운다(`None`이면 전부).
+    fn delete_older_than(&self, cutoff: Option<i64>) -> Result<usize> {
+        let n = match cutoff {
+            Some(cutoff) => self.conn.execute(
+                "DELETE FROM tracker_peers WHERE last_seen_unix < ?",
+                rusqlite::params![cutoff],
+            ),
+            None => self.conn.execute("DELETE FROM tracker_peers", []),
+        }
+        .context("delete expired tracker_peers")?;
+        Ok(n)
+    }
+
+    fn load_revoked(&self) -> Result<HashSet<String>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT fingerprint FROM tracker_revoked_tokens")
+            .context("prepare load tracker_revoked_tokens")?;
+        let rows = stmt
+            .query_map([], |row| row.get::<_, String>(0))
+            .context("query tracker_revoked_tokens")?;
+        rows.collect::<std::result::Result<_, _>>()
+            .context("read tracker_revoked_tokens")
+    }
+
+    fn insert_revoked(&self, fingerprint: &str, now_unix: i64) -> Result<()> {
+        self.conn
+            .execute(
+                "INSERT OR IGNORE INTO tracker_revoked_tokens(fingerprint, revoked_at) VALUES (?, ?)",
+                rusqlite::params![fingerprint, now_unix],
+            )
+            .context("insert tracker_revoked_tokens")?;
+        Ok(())
+    }
+
+    fn load_signed(&self) -> Result<HashMap<String, i64>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT peer_id, signed_at_ms FROM tracker_signed_registrations")
+            .context("prepare load tracker_signed_registrations")?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .context("query tracker_signed_registrations")?;
+        rows.collect::<std::result::Result<_, _>>()
+            .context("read tracker_signed_registrations")
+    }
+
+    fn upsert_signed(&self, peer_id: &str, signed_at_ms: i64) -> Result<()> {
+        self.conn
+            .execute(
+                r#"
+INSERT INTO tracker_signed_registrations(peer_id, signed_at_ms)
+VALUES (?, ?)
+ON CONFLICT(peer_id) DO UPDATE SET signed_at_ms = excluded.signed_at_ms
+"#,
+                rusqlite::params![peer_id, signed_at_ms],
+            )
+            .context("upsert tracker_signed_registrations")?;
+        Ok(())
+    }
+
+    /// `signed_at_ms < cutoff_ms`인 행을 지운다.
+    fn delete_signed_before(&self, cutoff_ms: i64) -> Result<usize> {
+        self.conn
+            .execute(
+                "DELETE FROM tracker_signed_registrations WHERE signed_at_ms < ?",
+                rusqlite::params![cutoff_ms],
+            )
+            .context("delete expired tracker_signed_registrations")
+    }
+}
+
+fn ensure_tracker_column(conn: &rusqlite::Connection, name: &str, decl: &str) -> Result<()> {
+    let exists: bool = conn
+        .query_row(
+            "SELECT COUNT(*) > 0 FROM pragma_table_info('tracker_peers') WHERE name = ?",
+            rusqlite::params![name],
+            |row| row.get(0),
+        )
+        .context("inspect tracker_peers columns")?;
+    if !exists {
+        conn.execute(
+            &format!("ALTER TABLE tracker_peers ADD COLUMN {name} {decl}"),
+            [],
+        )
+        .with_context(|| format!("add tracker_peers.{name}"))?;
+    }
+    Ok(())
+}
+
+fn serve_http(bind: &str, opts: &TrackerOptions, state: Arc<Mutex<TrackerState>>) -> Result<()> {
     let server =
         tiny_http::Server::http(bind).map_err(|e| anyhow::anyhow!("listen {bind}: {e}"))?;
 
     for mut req in server.incoming_requests() {
-        let res = route_http_request(&state, ttl_sec, token.as_deref(), &mut req)
+        let res = route_http_request(&state, opts, &mut req)
             .unwrap_or_else(|err| respond_text(500, &format!("error: {err:#}\n")));
         let _ = req.respond(res);
     }
@@ -78,25 +619,35 @@ fn serve_http(
 
 fn route_http_request(
     state: &Arc<Mutex<TrackerState>>,
-    ttl_sec: u64,
-    token: Option<&str>,
+    opts: &TrackerOptions,
     req: &mut tiny_http::Request,
 ) -> Result<tiny_http::Response<std::io::Cursor<Vec<u8>>>> {
-    if let Some(token) = token
-        && !is_authorized(req, token)
-    {
-        return Ok(respond_text(401, "unauthorized\n"));
-    }
+    let ttl_sec = opts.ttl_sec;
+    let access = {
+        let locked = state.lock().unwrap();
+        opts.auth
+            .resolve(bearer_token(req).as_deref(), &locked.revoked)
+    };
 
     let url = req.url().to_string();
-    let method = req.method().as_str();
+    let method = req.method().to_string();
 
     let (path, query) = match url.split_once('?') {
         Some((p, q)) => (p, Some(q)),
         None => (url.as_str(), None),
     };
 
-    match (method, path) {
+    if path.starts_with("/api/v1/admin/") {
+        if access != Access::Admin {
+            return Ok(respond_text(401, "unauthorized\n"));
+        }
+        return route_admin_request(state, opts, &method, path, req);
+    }
+    let Access::Scope(tenant) = access else {
+        return Ok(respond_text(401, "unauthorized\n"));
+    };
+
+    match (method.as_str(), path) {
         ("GET", "/api/v1/ping") => Ok(respond_text(200, "ok\n")),
         ("POST", "/api/v1/peers/register") => {
             let mut buf = Vec::new();
@@ -121,12 +672,40 @@ fn route_http_request(
             };
 
             let now = OffsetDateTime::now_utc();
+            let now_ms = (now.unix_timestamp_nanos() / 1_000_000) as i64;
+            let signed_at_ms = match reg.verify(&peer_id, now_ms) {
+                Ok(v) => v,
+                Err(msg) => return Ok(respond_text(403, &format!("{msg}\n"))),
+            };
+            if signed_at_ms.is_none() && !opts.allow_unsigned {
+                return Ok(respond_text(403, "signed registration required\n"));
+            }
             {
                 let mut locked = state.lock().unwrap();
                 prune_expired(&mut locked, now, ttl_sec);
-                locked.peers.insert(
+                let existing = locked.peers.get(&peer_id.to_string());
+                if existing.is_some_and(|rec| rec.tenant != tenant) {
+                    return Ok(respond_text(403, "peer_id belongs to another tenant\n"));
+                }
+                if signed_at_ms.is_none() && existing.is_some_and(|rec| rec.signed_at_ms.is_some())
+                {
+                    return Ok(respond_text(
+                        403,
+                        "peer is registered with a signature; unsigned update rejected\n",
+                    ));
+                }
+                // 레코드가 아니라 `last_signed_ms`와 비교한다(레코드가 만료된 뒤의 재전송도 막는다).
+                if let (Some(ts), Some(prev)) = (
+                    signed_at_ms,
+                    locked.last_signed_ms.get(&peer_id.to_string()),
+                ) && ts <= *prev
+                {
+                    return Ok(respond_text(409, "replayed registration\n"));
+                }
+                locked.upsert(
                     peer_id.to_string(),
                     PeerRecord {
+                        tenant,
                         addrs: reg
                             .addrs
                             .into_iter()
@@ -135,8 +714,9 @@ fn route_http_request(
                             .collect(),
                         meta: reg.meta,
                         last_seen_unix: now.unix_timestamp(),
+                        signed_at_ms,
                     },
-                );
+                )?;
             }
 
             respond_json(200, &RegisterResponse { ok: true, ttl_sec })
@@ -158,6 +738,7 @@ fn route_http_request(
                 locked
                     .peers
                     .iter()
+                    .filter(|(_, rec)| rec.tenant == tenant)
                     .filter(|(_, rec)| match (user_id.as_deref(), &rec.meta) {
                         (None, _) => true,
                         (Some(want), Some(meta)) => meta.user_id.as_deref() == Some(want),
@@ -178,6 +759,75 @@ fn route_http_request(
     }
 }
 
+fn route_admin_request(
+    state: &Arc<Mutex<TrackerState>>,
+    opts: &TrackerOptions,
+    method: &str,
+    path: &str,
+    req: &mut tiny_http::Request,
+) -> Result<tiny_http::Response<std::io::Cursor<Vec<u8>>>> {
+    match (method, path) {
+        ("GET", "/api/v1/admin/tenants") => {
+            let locked = state.lock().unwrap();
+            let tenants = opts
+                .auth
+                .tenants
+                .iter()
+                .map(|t| AdminTenant {
+                    name: t.name.clone(),
+                    peers: locked
+                        .peers
+                        .values()
+                        .filter(|rec| rec.tenant.as_deref() == Some(t.name.as_str()))
+                        .count(),
+                    tokens: t
+                        .tokens
+                        .iter()
+                        .map(|token| {
+                            let fingerprint = token_fingerprint(token);
+                            AdminToken {
+                                revoked: locked.revoked.contains(&fingerprint),
+                                fingerprint,
+                            }
+                        })
+                        .collect(),
+                })
+                .collect();
+            respond_json(200, &AdminTenantsResponse { tenants })
+        }
+        ("POST", "/api/v1/admin/tokens/revoke") => {
+            let mut buf = Vec::new();
+            req.as_reader()
+                .take(64 * 1024)
+                .read_to_end(&mut buf)
+                .context("read request body")?;
+            let revoke: RevokeTokenRequest =
+                serde_json::from_slice(&buf).context("parse revoke request json")?;
+            let fingerprint = revoke.fingerprint.trim();
+
+            let Some(tenant) = opts.auth.tenants.iter().find(|t| {
+                t.tokens
+                    .iter()
+                    .any(|token| token_fingerprint(token) == fingerprint)
+            }) else {
+                return Ok(respond_text(404, "unknown token fingerprint\n"));
+            };
+
+            let mut locked = state.lock().unwrap();
+            locked.revoke(fingerprint, OffsetDateTime::now_utc().unix_timestamp())?;
+            respond_json(
+                200,
+                &RevokeTokenResponse {
+                    ok: true,
+                    tenant: tenant.name.clone(),
+                    fingerprint: fingerprint.to_string(),
+                },
+            )
+        }
+        _ => Ok(respond_text(404, "not found\n")),
+    }
+}
+
 #[cfg(test)]
 fn max_request_body_bytes() -> usize {
     8 * 1024
@@ -189,16 +839,31 @@ fn max_request_body_bytes() -> usize {
 }
 
 fn prune_expired(state: &mut TrackerState, now: OffsetDateTime, ttl_sec: u64) {
-    if ttl_sec == 0 {
+    let cutoff = if ttl_sec == 0 {
         state.peers.clear();
-        return;
-    }
+        None
+    } else {
+        let now_ts = now.unix_timestamp();
+        let ttl = ttl_sec as i64;
+        state
+            .peers
+            .retain(|_, rec| now_ts - rec.last_seen_unix <= ttl);
+        Some(now_ts - ttl)
+    };
 
-    let now_ts = now.unix_timestamp();
-    let ttl = ttl_sec as i64;
-    state
-        .peers
-        .retain(|_, rec| now_ts - rec.last_seen_unix <= ttl);
+    // 시계 오차 범위를 벗어난 서명은 어차피 검증에서 거부되므로 그보다 오래된 기록만 지운다.
+    let signed_cutoff_ms =
+        (now.unix_timestamp_nanos() / 1_000_000) as i64 - MAX_REGISTER_CLOCK_SKEW_MS;
+    state.last_signed_ms.retain(|_, ts| *ts >= signed_cutoff_ms);
+
+    // DB 정리는 best-effort: 실패해도 메모리 상태는 이미 정리됐고 다음 주기에 다시 시도한다.
+    if let Some(db) = state.db.as_ref()
+        && let Err(err) = db
+            .delete_older_than(cutoff)
+            .and_then(|_| db.delete_signed_before(signed_cutoff_ms))
+    {
+        eprintln!("warn: tracker db prune failed: {err:#}");
+    }
 }
 
 fn query_get<'a>(query: &'a str, key: &str) -> Option<&'a str> {
@@ -213,25 +878,16 @@ fn query_get<'a>(query: &'a str, key: &str) -> Option<&'a str> {
     None
 }
 
-fn is_authorized(req: &tiny_http::Request, token: &str) -> bool {
-    let token = token.trim();
-    if token.is_empty() {
-        return true;
-    }
-
+pub(crate) fn bearer_token(req: &tiny_http::Request) -> Option<String> {
     // 1) Authorization: Bearer <token>
-    if let Some(value) = header_value(req, "Authorization")
-        && let Some(rest) = value.strip_prefix("Bearer ")
-    {
-        return rest.trim() == token;
+    if let Some(value) = header_value(req, "Authorization") {
+        return value
+            .strip_prefix("Bearer ")
+            .map(|rest| rest.trim().to_string());
     }
 
     // 2) X-Rustory-Token: <token>
-    if let Some(value) = header_value(req, "X-Rustory-Token") {
-        return value.trim() == token;
-    }
-
-    false
+    header_value(req, "X-Rustory-Token").map(|value| value.trim().to_string())
 }
 
 fn header_value(req: &tiny_http::Request, name: &'static str) -> Option<String> {
@@ -245,6 +901,7 @@ fn header_value(req: &tiny_http::Request, name: &'static str) -> Option<String>
 pub struct TrackerClient {
     base_url: String,
     token: Option<String>,
+    identity: Option<libp2p::identity::Keypair>,
 }
 
 impl TrackerClient {
@@ -252,22 +909,49 @@ impl TrackerClient {
         Self {
             base_url: base_url.trim_end_matches('/').to_string(),
             token,
+            identity: None,
+        }
+    }
+
+    /// 등록 요청을 이 identity 키로 서명한다.
+    pub fn with_identity(mut self, identity: libp2p::identity::Keypair) -> Self {
+        self.identity = Some(identity);
+        self
+    }
+
+    fn register_body(&self, req: &RegisterRequest) -> Result<Vec<u8>> {
+        match self.identity.as_ref() {
+            Some(identity) => {
+                let mut signed = req.clone();
+                let now_ms = (OffsetDateTime::now_utc().unix_timestamp_nanos() / 1_000_000) as i64;
+                signed.sign(identity, now_ms)?;
+                serde_json::to_vec(&signed).context("serialize register request")
+            }
+            None => serde_json::to_vec(req).context("serialize register request"),
         }
     }
 
     pub fn register(&self, req: &RegisterRequest) -> Result<RegisterResponse> {
         let url = format!("{}/api/v1/peers/register", self.base_url);
-        let body = serde_json::to_vec(req).context("serialize register request")?;
+        let first_body = self.register_body(req)?;
+        let mut attempt = 0;
 
         let token = self.token.clone();
-        let resp = crate::http_retry::request_with_retry(
+        let resp = crate::http_retry::request_with_retry_fallible(
             crate::http_retry::RetryPolicy::tracker(),
             |agent| {
+                // 재시도마다 새 시각으로 다시 서명한다(같은 서명을 다시 보내면 재전송으로 거부된다).
+                let body = if attempt == 0 {
+                    first_body.clone()
+                } else {
+                    self.register_body(req)?
+                };
+                attempt += 1;
                 let mut r = agent.post(&url).set("Content-Type", "application/json");
                 if let Some(token) = &token {
                     r = r.set("Authorization", &format!("Bearer {}", token.trim()));
                 }
-                r.send_bytes(&body)
+                Ok(r.send_bytes(&body))
             },
         )
         .with_context(|| format!("POST {url}"))?;
@@ -344,6 +1028,14 @@ mod tests {
     }
 
     fn start_test_server(ttl_sec: u64, token: Option<String>) -> TestServer {
+        start_test_server_with(TrackerOptions {
+            ttl_sec,
+            auth: TrackerAuth::single_token(token),
+            allow_unsigned: false,
+        })
+    }
+
+    fn start_test_server_with(opts: TrackerOptions) -> TestServer {
         let listener = TcpListener::bind("127.0.0.1:0").unwrap();
         let addr = listener.local_addr().unwrap();
         drop(listener);
@@ -354,7 +1046,6 @@ mod tests {
         let state = Arc::new(Mutex::new(TrackerState::default()));
         let shutdown = Arc::new(AtomicBool::new(false));
         let shutdown2 = shutdown.clone();
-        let token2 = token.clone();
         let state2 = state.clone();
 
         let join = thread::spawn(move || {
@@ -362,7 +1053,7 @@ mod tests {
             while !shutdown2.load(Ordering::SeqCst) {
                 match server.recv_timeout(Duration::from_millis(50)) {
                     Ok(Some(mut req)) => {
-                        let res = route_http_request(&state2, ttl_sec, token2.as_deref(), &mut req)
+                        let res = route_http_request(&state2, &opts, &mut req)
                             .unwrap_or_else(|e| respond_text(500, &format!("error: {e:#}\n")));
                         let _ = req.respond(res);
                     }
@@ -391,10 +1082,12 @@ mod tests {
     #[test]
     fn tracker_register_and_list_end_to_end() {
         let server = start_test_server(60, None);
-        let client = TrackerClient::new(server.base_url.clone(), None);
+        let identity = libp2p::identity::Keypair::generate_ed25519();
+        let client =
+            TrackerClient::new(server.base_url.clone(), None).with_identity(identity.clone());
 
         let user_id = "u 1/2";
-        let peer_id = PeerId::random().to_string();
+        let peer_id = identity.public().to_peer_id().to_string();
         let req = RegisterRequest {
             peer_id: peer_id.clone(),
             addrs: vec!["/ip4/127.0.0.1/tcp/1234".to_string()],
@@ -404,9 +1097,14 @@ mod tests {
                 hostname: None,
                 version: Some("0.1.0".to_string()),
             }),
+            timestamp_ms: None,
+            signature: None,
         };
         let resp = client.register(&req).unwrap();
         assert!(resp.ok);
+        // 다음 등록은 새 시각으로 서명되므로 재전송으로 보지 않는다.
+        std::thread::sleep(Duration::from_millis(5));
+        assert!(client.register(&req).unwrap().ok);
 
         let list = client.list(Some(user_id)).unwrap();
         assert_eq!(list.peers.len(), 1);
@@ -424,6 +1122,8 @@ mod tests {
             peer_id: PeerId::random().to_string(),
             addrs: vec![],
             meta: None,
+            timestamp_ms: None,
+            signature: None,
         };
 
         let err = client.register(&req).unwrap_err();
@@ -436,12 +1136,16 @@ mod tests {
     #[test]
     fn tracker_accepts_with_token() {
         let server = start_test_server(60, Some("secret".to_string()));
-        let client = TrackerClient::new(server.base_url.clone(), Some("secret".to_string()));
+        let identity = libp2p::identity::Keypair::generate_ed25519();
+        let client = TrackerClient::new(server.base_url.clone(), Some("secret".to_string()))
+            .with_identity(identity.clone());
 
         let req = RegisterRequest {
-            peer_id: PeerId::random().to_string(),
+            peer_id: identity.public().to_peer_id().to_string(),
             addrs: vec![],
             meta: None,
+            timestamp_ms: None,
+            signature: None,
         };
         let resp = client.register(&req).unwrap();
         assert!(resp.ok);
@@ -458,6 +1162,8 @@ mod tests {
             peer_id: "not-a-peer-id".to_string(),
             addrs: vec![],
             meta: None,
+            timestamp_ms: None,
+            signature: None,
         };
 
         let err = client.register(&req).unwrap_err();
@@ -467,6 +1173,310 @@ mod tests {
         server.shutdown();
     }
 
+    #[test]
+    fn tracker_db_restores_live_peers_and_prunes_expired() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tracker.db");
+        let path = path.to_str().unwrap();
+        let now = OffsetDateTime::from_unix_timestamp(1_000).unwrap();
+        let meta = PeerMeta {
+            device_id: Some("d1".to_string()),
+            hostname: None,
+            user_id: Some("u1".to_string()),
+            version: None,
+        };
+
+        {
+            let mut state = TrackerState::open_persistent(path, now, 60).unwrap();
+            state
+                .upsert(
+                    "live".to_string(),
+                    PeerRecord {
+                        tenant: Some("alice".to_string()),
+                        addrs: vec!["/ip4/127.0.0.1/tcp/1".to_string()],
+                        meta: Some(meta.clone()),
+                        last_seen_unix: 990,
+                        signed_at_ms: Some(990_000),
+                    },
+                )
+                .unwrap();
+            state
+                .upsert(
+                    "stale".to_string(),
+                    PeerRecord {
+                        tenant: None,
+                        addrs: vec![],
+                        meta: None,
+                        last_seen_unix: 900,
+                        signed_at_ms: None,
+                    },
+                )
+                .unwrap();
+        }
+
+        // 재시작: 만료된 레코드는 메모리/DB 양쪽에서 사라지고 나머지는 바로 보인다.
+        let state = TrackerState::open_persistent(path, now, 60).unwrap();
+        assert_eq!(state.peers.len(), 1);
+        let live = &state.peers["live"];
+        assert_eq!(live.addrs, vec!["/ip4/127.0.0.1/tcp/1".to_string()]);
+        assert_eq!(live.meta.as_ref().unwrap().user_id.as_deref(), Some("u1"));
+        assert_eq!(live.signed_at_ms, Some(990_000));
+        assert_eq!(live.tenant.as_deref(), Some("alice"));
+        assert_eq!(state.db.as_ref().unwrap().load().unwrap().len(), 1);
+
+        let mut state = state;
+        prune_expired(&mut state, now, 0);
+        assert!(state.peers.is_empty());
+        assert!(state.db.as_ref().unwrap().load().unwrap().is_empty());
+    }
+
+    #[test]
+    fn tracker_rejects_unsigned_forged_and_replayed_registrations() {
+        let server = start_test_server(60, None);
+        let url = format!("{}/api/v1/peers/register", server.base_url);
+        let post = |req: &RegisterRequest| -> u16 {
+            match ureq::post(&url)
+                .set("Content-Type", "application/json")
+                .send_bytes(&serde_json::to_vec(req).unwrap())
+            {
+                Ok(resp) => resp.status(),
+                Err(ureq::Error::Status(code, _)) => code,
+                Err(err) => panic!("{err}"),
+            }
+        };
+        let now_ms = (OffsetDateTime::now_utc().unix_timestamp_nanos() / 1_000_000) as i64;
+
+        let victim = libp2p::identity::Keypair::generate_ed25519();
+        let attacker = libp2p::identity::Keypair::generate_ed25519();
+        let mut req = RegisterRequest {
+            peer_id: victim.public().to_peer_id().to_string(),
+            addrs: vec!["/ip4/10.0.0.1/tcp/1".to_string()],
+            meta: None,
+            timestamp_ms: None,
+            signature: None,
+        };
+
+        assert_eq!(post(&req), 403, "unsigned");
+
+        let mut forged = req.clone();
+        forged.sign(&attacker, now_ms).unwrap();
+        assert_eq!(post(&forged), 403, "signed by another key");
+
+        req.sign(&victim, now_ms).unwrap();
+        assert_eq!(post(&req), 200);
+        assert_eq!(post(&req), 409, "replay");
+
+        let mut tampered = req.clone();
+        tampered.addrs = vec!["/ip4/6.6.6.6/tcp/1".to_string()];
+        assert_eq!(post(&tampered), 403, "addrs changed after signing");
+
+        let mut stale = req.clone();
+        stale
+            .sign(&victim, now_ms - MAX_REGISTER_CLOCK_SKEW_MS - 1)
+            .unwrap();
+        assert_eq!(post(&stale), 403, "outside clock skew");
+
+        let list = TrackerClient::new(server.base_url.clone(), None)
+            .list(None)
+            .unwrap();
+        assert_eq!(list.peers.len(), 1);
+        assert_eq!(list.peers[0].addrs, vec!["/ip4/10.0.0.1/tcp/1".to_string()]);
+
+        server.shutdown();
+    }
+
+    #[test]
+    fn tracker_rejects_replay_after_the_record_expires() {
+        // ttl_sec=0이면 레코드는 요청마다 바로 만료된다.
+        let server = start_test_server(0, None);
+        let url = format!("{}/api/v1/peers/register", server.base_url);
+        let post = |req: &RegisterRequest| -> u16 {
+            match ureq::post(&url)
+                .set("Content-Type", "application/json")
+                .send_bytes(&serde_json::to_vec(req).unwrap())
+            {
+                Ok(resp) => resp.status(),
+                Err(ureq::Error::Status(code, _)) => code,
+                Err(err) => panic!("{err}"),
+            }
+        };
+        let now_ms = (OffsetDateTime::now_utc().unix_timestamp_nanos() / 1_000_000) as i64;
+
+        let identity = libp2p::identity::Keypair::generate_ed25519();
+        let mut req = RegisterRequest {
+            peer_id: identity.public().to_peer_id().to_string(),
+            addrs: vec!["/ip4/10.0.0.1/tcp/1".to_string()],
+            meta: None,
+            timestamp_ms: None,
+            signature: None,
+        };
+        req.sign(&identity, now_ms).unwrap();
+        assert_eq!(post(&req), 200);
+        let list = TrackerClient::new(server.base_url.clone(), None)
+            .list(None)
+            .unwrap();
+        assert!(list.peers.is_empty());
+        assert_eq!(post(&req), 409, "replay after expiry");
+
+        req.sign(&identity, now_ms + 1).unwrap();
+        assert_eq!(post(&req), 200);
+
+        server.shutdown();
+    }
+
+    #[test]
+    fn tracker_keeps_signed_registrations_past_record_ttl() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tracker.db");
+        let path = path.to_str().unwrap();
+        let now = OffsetDateTime::from_unix_timestamp(1_000).unwrap();
+        let rec = PeerRecord {
+            tenant: None,
+            addrs: vec![],
+            meta: None,
+            last_seen_unix: 900,
+            signed_at_ms: Some(900_000),
+        };
+        {
+            let mut state = TrackerState::open_persistent(path, now, 60).unwrap();
+            state.upsert("p1".to_string(), rec.clone()).unwrap();
+            state
+                .upsert(
+                    "p2".to_string(),
+                    PeerRecord {
+                        signed_at_ms: Some(1_000_000 - MAX_REGISTER_CLOCK_SKEW_MS - 1),
+                        ..rec
+                    },
+                )
+                .unwrap();
+        }
+
+        // 레코드는 만료됐지만 시계 오차 범위 안의 서명 시각은 재시작 뒤에도 남는다.
+        let state = TrackerState::open_persistent(path, now, 60).unwrap();
+        assert!(state.peers.is_empty());
+        assert_eq!(
+            state.last_signed_ms,
+            HashMap::from([("p1".to_string(), 900_000)])
+        );
+        assert_eq!(state.db.as_ref().unwrap().load_signed().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn tracker_tenant_tokens_scope_peers_and_admin_can_revoke() {
+        let auth = TrackerAuth::from_file_config(TrackerConfigFile {
+            admin_token: Some("admin".to_string()),
+            tenants: vec![
+                TenantConfig {
+                    name: "alice".to_string(),
+                    tokens: vec!["tok-a".to_string()],
+                },
+                TenantConfig {
+                    name: "bob".to_string(),
+                    tokens: vec!["tok-b".to_string()],
+                },
+            ],
+        })
+        .unwrap();
+        let server = start_test_server_with(TrackerOptions {
+            ttl_sec: 60,
+            auth,
+            allow_unsigned: false,
+        });
+        let client =
+            |token: &str| TrackerClient::new(server.base_url.clone(), Some(token.to_string()));
+
+        let identity = libp2p::identity::Keypair::generate_ed25519();
+        let req = RegisterRequest {
+            peer_id: identity.public().to_peer_id().to_string(),
+            addrs: vec![],
+            meta: None,
+            timestamp_ms: None,
+            signature: None,
+        };
+        assert!(
+            client("tok-a")
+                .with_identity(identity.clone())
+                .register(&req)
+                .unwrap()
+                .ok
+        );
+
+        assert_eq!(client("tok-a").list(None).unwrap().peers.len(), 1);
+        assert!(client("tok-b").list(None).unwrap().peers.is_empty());
+        let err = client("tok-b")
+            .with_identity(identity.clone())
+            .register(&req)
+            .unwrap_err();
+        assert!(format!("{err:#}").contains("status code 403"), "{err:#}");
+        for token in ["admin", "nope"] {
+            let err = client(token).list(None).unwrap_err();
+            assert!(format!("{err:#}").contains("status code 401"), "{err:#}");
+        }
+
+        let admin_get = |token: &str| {
+            ureq::get(&format!("{}/api/v1/admin/tenants", server.base_url))
+                .set("Authorization", &format!("Bearer {token}"))
+                .call()
+        };
+        assert!(matches!(
+            admin_get("tok-a"),
+            Err(ureq::Error::Status(401, _))
+        ));
+        let tenants: AdminTenantsResponse =
+            serde_json::from_str(&admin_get("admin").unwrap().into_string().unwrap()).unwrap();
+        assert_eq!(tenants.tenants.len(), 2);
+        assert_eq!(tenants.tenants[0].name, "alice");
+        assert_eq!(tenants.tenants[0].peers, 1);
+        assert!(!tenants.tenants[0].tokens[0].revoked);
+
+        let fingerprint = token_fingerprint("tok-a");
+        assert_eq!(tenants.tenants[0].tokens[0].fingerprint, fingerprint);
+        let revoked = ureq::post(&format!("{}/api/v1/admin/tokens/revoke", server.base_url))
+            .set("Authorization", "Bearer admin")
+            .set("Content-Type", "application/json")
+            .send_bytes(&serde_json::to_vec(&RevokeTokenRequest { fingerprint }).unwrap())
+            .unwrap()
+            .into_string()
+            .unwrap();
+        let revoked: RevokeTokenResponse = serde_json::from_str(&revoked).unwrap();
+        assert_eq!(revoked.tenant, "alice");
+
+        let err = client("tok-a").list(None).unwrap_err();
+        assert!(format!("{err:#}").contains("status code 401"), "{err:#}");
+        assert!(client("tok-b").list(None).is_ok());
+
+        server.shutdown();
+    }
+
+    #[test]
+    fn tracker_config_rejects_ambiguous_tokens() {
+        let tenant = |name: &str, token: &str| TenantConfig {
+            name: name.to_string(),
+            tokens: vec![token.to_string()],
+        };
+        let shared = TrackerAuth::from_file_config(TrackerConfigFile {
+            admin_token: None,
+            tenants: vec![tenant("a", "t"), tenant("b", "t")],
+        });
+        assert!(shared.is_err());
+        let admin_reuse = TrackerAuth::from_file_config(TrackerConfigFile {
+            admin_token: Some("t".to_string()),
+            tenants: vec![tenant("a", "t")],
+        });
+        assert!(admin_reuse.is_err());
+        let duplicate = TrackerAuth::from_file_config(TrackerConfigFile {
+            admin_token: None,
+            tenants: vec![tenant("a", "t1"), tenant("a", "t2")],
+        });
+        assert!(duplicate.is_err());
+        let admin_only = TrackerAuth::from_file_config(TrackerConfigFile {
+            admin_token: Some("admin".to_string()),
+            tenants: Vec::new(),
+        });
+        let err = admin_only.expect_err("admin-only config must not leave register/list open");
+        assert!(err.to_string().contains("[[tenants]]"), "{err:#}");
+    }
+
     #[test]
     fn tracker_client_retries_on_5xx() {
         let listener = TcpListener::bind("127.0.0.1:0").unwrap();
@@ -1,11 +1,52 @@
-use crate::{core::Entry, storage::LocalStore, sync};
+use crate::{
+    content_key::ContentKey,
+    core::{Entry, SyncHeads, Tombstone},
+    storage::LocalStore,
+    sync,
+};
 use anyhow::{Context, Result};
+use rustls_pki_types::pem::PemObject;
+use rustls_pki_types::{CertificateDer, PrivateKeyDer, ServerName, UnixTime};
 use serde::{Deserialize, Serialize};
 use std::io::Read;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// `rr serve` 옵션.
+#[derive(Debug, Clone, Default)]
+pub struct ServeOptions {
+    /// 있으면 모든 요청에 `Authorization: Bearer <token>`(또는 `X-Rustory-Token`)을 요구한다.
+    pub token: Option<String>,
+    /// 있으면 HTTP 대신 HTTPS로 listen한다.
+    pub tls: Option<ServeTls>,
+}
+
+/// PEM 인증서 체인과 개인키(PKCS#8 또는 RSA) 경로.
+#[derive(Debug, Clone)]
+pub struct ServeTls {
+    pub cert_path: PathBuf,
+    pub key_path: PathBuf,
+}
 
-pub fn serve(bind: &str, db_path: &str) -> Result<()> {
-    let store = LocalStore::open(db_path)?;
-    serve_http(bind, store)
+/// `rr sync` 클라이언트 옵션. CA/지문이 모두 없으면 https 피어는 기본(webpki) 루트로 검증한다.
+#[derive(Debug, Clone, Default)]
+pub struct ClientOptions {
+    pub token: Option<String>,
+    /// 서버 인증서를 검증할 CA 인증서(PEM). 기본 루트 대신 이 CA만 신뢰한다.
+    pub ca_path: Option<PathBuf>,
+    /// 서버 인증서의 SHA-256 지문(hex, `:` 허용). CA/호스트명 대신 지문만 비교한다(self-signed용).
+    pub cert_fingerprint: Option<String>,
+}
+
+pub fn serve(
+    bind: &str,
+    db_path: &str,
+    content_key: Option<ContentKey>,
+    opts: &ServeOptions,
+) -> Result<()> {
+    let store = LocalStore::open(db_path)?.with_content_key(content_key);
+    serve_http(bind, store, opts)
 }
 
 pub fn sync(
@@ -13,6 +54,8 @@ pub fn sync(
     db_path: &str,
     push: bool,
     local_device_id: Option<&str>,
+    content_key: Option<ContentKey>,
+    client_opts: &ClientOptions,
 ) -> Result<()> {
     if peers.is_empty() {
         anyhow::bail!("no peers provided");
@@ -21,12 +64,14 @@ pub fn sync(
         anyhow::bail!("local_device_id required for push");
     }
 
-    let store = LocalStore::open(db_path)?;
+    let client = HttpClient::from_options(client_opts)?;
+    let store = LocalStore::open(db_path)?.with_content_key(content_key);
     let mut progress = sync::SyncRunProgress::new(push);
     let mut last_err: Option<anyhow::Error> = None;
     for peer in peers {
         // peer_id는 우선 URL 문자열을 그대로 사용한다.
-        match sync_pull_http_peer(&store, peer, 1000).with_context(|| format!("pull peer: {peer}"))
+        match sync_pull_http_peer(&client, &store, peer, 1000)
+            .with_context(|| format!("pull peer: {peer}"))
         {
             Ok(_) => progress.mark_pull_ok(),
             Err(err) => {
@@ -48,7 +93,7 @@ pub fn sync(
             let push_needed = pending_push > 0;
             progress.note_push_needed(push_needed);
 
-            match sync_push_http_peer(&store, peer, 1000, local_device_id)
+            match sync_push_http_peer(&client, &store, peer, 1000, local_device_id)
                 .with_context(|| format!("push peer: {peer}"))
             {
                 Ok(_) => progress.mark_push_ok(push_needed),
@@ -69,7 +114,19 @@ pub fn sync(
 #[derive(Debug, Serialize, Deserialize)]
 struct EntriesResponse {
     entries: Vec<Entry>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tombstones: Vec<Tombstone>,
     next_cursor: Option<i64>,
+    #[serde(default)]
+    by_heads: bool,
+}
+
+/// `POST /api/v1/pull` 본문. `heads`로 이미 본 것을 알려 빠진 것만 받는다.
+#[derive(Debug, Serialize, Deserialize)]
+struct PullRequest {
+    cursor: i64,
+    limit: usize,
+    heads: SyncHeads,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -77,21 +134,31 @@ struct PushResponse {
     ok: bool,
     inserted: usize,
     ignored: usize,
+    #[serde(default)]
+    deleted: usize,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 #[serde(untagged)]
 enum EntriesRequest {
     Array(Vec<Entry>),
-    Object { entries: Vec<Entry> },
+    Object {
+        entries: Vec<Entry>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        tombstones: Vec<Tombstone>,
+    },
 }
 
-fn serve_http(bind: &str, store: LocalStore) -> Result<()> {
-    let server =
-        tiny_http::Server::http(bind).map_err(|e| anyhow::anyhow!("listen {bind}: {e}"))?;
+fn serve_http(bind: &str, store: LocalStore, opts: &ServeOptions) -> Result<()> {
+    let server = bind_server(bind, opts)?;
+    if opts.token.is_none() {
+        eprintln!(
+            "warn: rr serve has no token; anyone who can reach {bind} can read and write history"
+        );
+    }
 
     for mut req in server.incoming_requests() {
-        let res = route_http_request(&store, &mut req)
+        let res = route_http_request(&store, opts.token.as_deref(), &mut req)
             .unwrap_or_else(|err| respond_text(500, &format!("error: {err:#}\n")));
         let _ = req.respond(res);
     }
@@ -99,27 +166,375 @@ fn serve_http(bind: &str, store: LocalStore) -> Result<()> {
     Ok(())
 }
 
+fn bind_server(bind: &str, opts: &ServeOptions) -> Result<tiny_http::Server> {
+    let Some(tls) = &opts.tls else {
+        return tiny_http::Server::http(bind).map_err(|e| anyhow::anyhow!("listen {bind}: {e}"));
+    };
+    // 복호화한 요청은 loopback의 내부 서버로 넘기므로, 같은 호스트의 다른 사용자가 내부 포트로 직접
+    // 붙어 TLS를 건너뛰지 못하게 내부 서버에서도 토큰을 반드시 확인한다.
+    if opts.token.is_none() {
+        anyhow::bail!("rr serve with tls requires a token (--token or http_token)");
+    }
+
+    let certificate = std::fs::read(&tls.cert_path)
+        .with_context(|| format!("read tls cert: {}", tls.cert_path.display()))?;
+    let private_key = std::fs::read(&tls.key_path)
+        .with_context(|| format!("read tls key: {}", tls.key_path.display()))?;
+    let certs = CertificateDer::pem_slice_iter(&certificate)
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .context("parse tls cert pem")?;
+    let leaf = certs
+        .first()
+        .with_context(|| format!("no certificates in {}", tls.cert_path.display()))?;
+    // 클라이언트가 `http_cert_fingerprint`로 고정할 수 있도록 leaf 지문을 알려 준다.
+    eprintln!(
+        "rr serve: tls cert sha256={}",
+        hex_lower(&cert_fingerprint(leaf))
+    );
+    let key = PrivateKeyDer::from_pem_slice(&private_key).context("parse tls key pem")?;
+
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let config = rustls::ServerConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()
+        .context("tls protocol versions")?
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .context("tls cert/key")?;
+
+    // tiny_http 내장 TLS(rustls 0.20)는 쓰지 않는다. TLS는 여기서 끝내고, 평문은 loopback에만 연
+    // tiny_http로 넘긴다(토큰 검사는 그대로 tiny_http 쪽에서 한다). 연결마다 스레드를 둘 쓰므로
+    // 동시 연결 수는 `MAX_TLS_CONNECTIONS`로 제한한다.
+    let listener = std::net::TcpListener::bind(bind).with_context(|| format!("listen {bind}"))?;
+    let server = tiny_http::Server::http("127.0.0.1:0")
+        .map_err(|e| anyhow::anyhow!("listen tls backend: {e}"))?;
+    let backend = server
+        .server_addr()
+        .to_ip()
+        .context("tls backend address")?;
+    let config = Arc::new(config);
+    let active = Arc::new(AtomicUsize::new(0));
+    std::thread::Builder::new()
+        .name("rr-serve-tls".to_string())
+        .spawn(move || {
+            for stream in listener.incoming() {
+                let stream = match stream {
+                    Ok(stream) => stream,
+                    Err(err) => {
+                        eprintln!("warn: tls accept failed: {err}");
+                        continue;
+                    }
+                };
+                if active.fetch_add(1, Ordering::SeqCst) >= MAX_TLS_CONNECTIONS {
+                    active.fetch_sub(1, Ordering::SeqCst);
+                    eprintln!(
+                        "warn: tls connection limit ({MAX_TLS_CONNECTIONS}) reached; closing {}",
+                        stream
+                            .peer_addr()
+                            .map(|a| a.to_string())
+                            .unwrap_or_else(|_| "?".to_string())
+                    );
+                    continue;
+                }
+                let config = config.clone();
+                let active = active.clone();
+                std::thread::spawn(move || {
+                    if let Err(err) = proxy_tls(stream, backend, config) {
+                        eprintln!("warn: tls connection failed: {err:#}");
+                    }
+                    active.fetch_sub(1, Ordering::SeqCst);
+                });
+            }
+        })
+        .context("spawn tls listener")?;
+    Ok(server)
+}
+
+/// `rr serve` TLS 종단이 동시에 중계하는 연결 수 상한. 넘치면 새 연결은 바로 닫는다.
+const MAX_TLS_CONNECTIONS: usize = 64;
+
+/// TLS 연결 하나를 `backend`(평문 tiny_http)로 중계한다. 클라이언트→backend는 이 스레드가,
+/// backend→클라이언트는 보조 스레드가 맡고, 둘이 `ServerConnection`을 잠가서 나눠 쓴다.
+fn proxy_tls(
+    tcp: std::net::TcpStream,
+    backend: std::net::SocketAddr,
+    config: Arc<rustls::ServerConfig>,
+) -> Result<()> {
+    use std::io::Write;
+    use std::net::{Shutdown, TcpStream};
+    use std::sync::Mutex;
+
+    fn flush_tls(conn: &mut rustls::ServerConnection, tcp: &mut TcpStream) -> std::io::Result<()> {
+        while conn.wants_write() {
+            conn.write_tls(tcp)?;
+        }
+        Ok(())
+    }
+
+    let conn = Arc::new(Mutex::new(
+        rustls::ServerConnection::new(config).context("tls session")?,
+    ));
+    let mut plain = TcpStream::connect(backend).context("connect tls backend")?;
+
+    let responder = {
+        let conn = conn.clone();
+        let mut tcp = tcp.try_clone().context("clone tls socket")?;
+        let mut plain = plain.try_clone().context("clone backend socket")?;
+        std::thread::spawn(move || {
+            let mut buf = [0u8; 16 * 1024];
+            loop {
+                let n = match plain.read(&mut buf) {
+                    Ok(0) | Err(_) => break,
+                    Ok(n) => n,
+                };
+                let mut conn = conn.lock().unwrap();
+                if conn.writer().write_all(&buf[..n]).is_err()
+                    || flush_tls(&mut conn, &mut tcp).is_err()
+                {
+                    break;
+                }
+            }
+            let mut conn = conn.lock().unwrap();
+            conn.send_close_notify();
+            let _ = flush_tls(&mut conn, &mut tcp);
+            let _ = tcp.shutdown(Shutdown::Both);
+        })
+    };
+
+    let mut tcp_write = tcp.try_clone().context("clone tls socket")?;
+    let mut tcp_read = tcp;
+    let result = (|| -> Result<()> {
+        let mut buf = [0u8; 16 * 1024];
+        loop {
+            let n = tcp_read.read(&mut buf).context("read tls")?;
+            if n == 0 {
+                return Ok(());
+            }
+            let mut plaintext = Vec::new();
+            let mut closed = false;
+            {
+                let mut conn = conn.lock().unwrap();
+                let mut incoming = &buf[..n];
+                while !incoming.is_empty() {
+                    conn.read_tls(&mut incoming).context("read tls record")?;
+                    let state = match conn.process_new_packets() {
+                        Ok(state) => state,
+                        Err(err) => {
+                            // 핸드셰이크 실패 등은 alert를 보내고 끊는다.
+                            let _ = flush_tls(&mut conn, &mut tcp_write);
+                            return Err(err).context("tls");
+                        }
+                    };
+                    closed |= state.peer_has_closed();
+                    match conn.reader().read_to_end(&mut plaintext) {
+                        Ok(_) => closed = true,
+                        Err(err) if err.kind() == std::io::ErrorKind::WouldBlock => {}
+                        Err(err) => return Err(err).context("read tls plaintext"),
+                    }
+                }
+                flush_tls(&mut conn, &mut tcp_write).context("write tls")?;
+            }
+            plain.write_all(&plaintext).context("write tls backend")?;
+            if closed {
+                return Ok(());
+            }
+        }
+    })();
+
+    let _ = plain.shutdown(Shutdown::Both);
+    let _ = responder.join();
+    result
+}
+
+/// HTTP sync 요청에 공통으로 붙는 인증/TLS 설정.
+#[derive(Clone, Default)]
+struct HttpClient {
+    token: Option<String>,
+    tls: Option<Arc<rustls::ClientConfig>>,
+}
+
+impl HttpClient {
+    fn from_options(opts: &ClientOptions) -> Result<Self> {
+        Ok(Self {
+            token: opts.token.clone(),
+            tls: client_tls_config(opts)?,
+        })
+    }
+
+    fn request<T, F>(&self, mut f: F) -> Result<T>
+    where
+        F: FnMut(&ureq::Agent, Option<&str>) -> std::result::Result<T, ureq::Error>,
+    {
+        let auth = self.token.as_ref().map(|token| format!("Bearer {token}"));
+        crate::http_retry::request_with_retry_tls(
+            crate::http_retry::RetryPolicy::transport(),
+            self.tls.as_ref(),
+            |agent| f(agent, auth.as_deref()),
+        )
+    }
+}
+
+fn client_tls_config(opts: &ClientOptions) -> Result<Option<Arc<rustls::ClientConfig>>> {
+    if opts.ca_path.is_none() && opts.cert_fingerprint.is_none() {
+        return Ok(None);
+    }
+    if opts.ca_path.is_some() && opts.cert_fingerprint.is_some() {
+        anyhow::bail!("set either http_ca_path or http_cert_fingerprint, not both");
+    }
+
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let builder = rustls::ClientConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()
+        .context("tls protocol versions")?;
+
+    let config = if let Some(fingerprint) = opts.cert_fingerprint.as_deref() {
+        builder
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(PinnedCertVerifier {
+                fingerprint: parse_cert_fingerprint(fingerprint)?,
+                provider,
+            }))
+            .with_no_client_auth()
+    } else {
+        let ca_path = opts.ca_path.as_ref().expect("checked above");
+        let mut roots = rustls::RootCertStore::empty();
+        for cert in CertificateDer::pem_file_iter(ca_path)
+            .with_context(|| format!("read ca: {}", ca_path.display()))?
+        {
+            let cert = cert.with_context(|| format!("parse ca pem: {}", ca_path.display()))?;
+            roots.add(cert).context("add ca certificate")?;
+        }
+        if roots.is_empty() {
+            anyhow::bail!("no certificates in ca: {}", ca_path.display());
+        }
+        builder.with_root_certificates(roots).with_no_client_auth()
+    };
+    Ok(Some(Arc::new(config)))
+}
+
+/// 서버 leaf 인증서의 SHA-256만 비교한다. 체인/호스트명/유효기간은 보지 않지만,
+/// 핸드셰이크 서명은 그대로 검증하므로 개인키를 가진 서버만 통과한다.
+#[derive(Debug)]
+struct PinnedCertVerifier {
+    fingerprint: [u8; 32],
+    provider: Arc<rustls::crypto::CryptoProvider>,
+}
+
+impl rustls::client::danger::ServerCertVerifier for PinnedCertVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> std::result::Result<rustls::client::danger::ServerCertVerified, rustls::Error> {
+        let got = cert_fingerprint(end_entity);
+        if got != self.fingerprint {
+            return Err(rustls::Error::General(format!(
+                "server certificate sha256={} does not match pinned fingerprint",
+                hex_lower(&got)
+            )));
+        }
+        Ok(rustls::client::danger::ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &rustls::DigitallySignedStruct,
+    ) -> std::result::Result<rustls::client::danger::HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &rustls::DigitallySignedStruct,
+    ) -> std::result::Result<rustls::client::danger::HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<rustls::SignatureScheme> {
+        self.provider
+            .signature_verification_algorithms
+            .supported_schemes()
+    }
+}
+
+fn cert_fingerprint(cert: &CertificateDer<'_>) -> [u8; 32] {
+    use sha2::{Digest, Sha256};
+
+    Sha256::digest(cert.as_ref()).into()
+}
+
+/// `openssl x509 -fingerprint -sha256` 출력(`sha256 Fingerprint=AB:CD:...`)도 그대로 받는다.
+fn parse_cert_fingerprint(value: &str) -> Result<[u8; 32]> {
+    let raw = value.trim();
+    let raw = raw.rsplit_once('=').map_or(raw, |(_, v)| v);
+    let hex: String = raw.chars().filter(|c| *c != ':').collect();
+    if hex.len() != 64 || !hex.is_ascii() {
+        anyhow::bail!("cert fingerprint must be 32 bytes of hex (sha256): {value}");
+    }
+
+    let mut out = [0u8; 32];
+    for (i, byte) in out.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16)
+            .with_context(|| format!("parse cert fingerprint: {value}"))?;
+    }
+    Ok(out)
+}
+
+fn hex_lower(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
 fn sync_pull_http_peer(
+    client: &HttpClient,
     local: &LocalStore,
     peer_base_url: &str,
     limit: usize,
 ) -> Result<sync::PullStats> {
     let peer_key = normalize_peer_base_url(peer_base_url)?;
-    sync::sync_pull_from_peer(local, &peer_key, limit, |cursor, limit| {
-        http_pull_batch(&peer_key, cursor, limit)
+    // `/api/v1/pull`이 없는(404) 구버전 서버에는 커서 기준 GET으로 돌아간다.
+    let mut legacy = false;
+    sync::sync_pull_from_peer(local, &peer_key, limit, |cursor, limit, heads| {
+        if !legacy {
+            match http_pull_batch_by_heads(client, &peer_key, cursor, limit, heads) {
+                Err(err) if is_not_found_error(&err) => legacy = true,
+                res => return res,
+            }
+        }
+        http_pull_batch(client, &peer_key, cursor, limit)
     })
 }
 
 fn sync_push_http_peer(
+    client: &HttpClient,
     local: &LocalStore,
     peer_base_url: &str,
     limit: usize,
     local_device_id: Option<&str>,
 ) -> Result<usize> {
     let peer_key = normalize_peer_base_url(peer_base_url)?;
-    sync::sync_push_to_peer(local, &peer_key, limit, local_device_id, |entries| {
-        http_push_batch(&peer_key, entries)
-    })
+    sync::sync_push_to_peer(
+        local,
+        &peer_key,
+        limit,
+        local_device_id,
+        |entries, tombstones| http_push_batch(client, &peer_key, entries, tombstones),
+    )
 }
 
 fn count_pending_http_push_entries(
@@ -140,6 +555,7 @@ fn normalize_peer_base_url(value: &str) -> Result<String> {
 }
 
 fn http_pull_batch(
+    client: &HttpClient,
     peer_base_url: &str,
     cursor: i64,
     limit: usize,
@@ -151,43 +567,109 @@ fn http_pull_batch(
         limit
     );
 
-    let resp = crate::http_retry::request_with_retry(
-        crate::http_retry::RetryPolicy::transport(),
-        |agent| agent.get(&url).call(),
-    )
-    .with_context(|| format!("GET {url}"))?;
+    let resp = client
+        .request(|agent, auth| {
+            let mut req = agent.get(&url);
+            if let Some(auth) = auth {
+                req = req.set("Authorization", auth);
+            }
+            req.call()
+        })
+        .with_context(|| format!("GET {url}"))?;
     let body = resp.into_string().context("read response body")?;
     let parsed: EntriesResponse =
         serde_json::from_str(&body).context("parse entries response json")?;
 
     Ok(crate::storage::PullBatch {
         entries: parsed.entries,
+        tombstones: parsed.tombstones,
         next_cursor: parsed.next_cursor,
+        by_heads: false,
     })
 }
 
-fn http_push_batch(peer_base_url: &str, entries: Vec<Entry>) -> Result<()> {
+fn http_pull_batch_by_heads(
+    client: &HttpClient,
+    peer_base_url: &str,
+    cursor: i64,
+    limit: usize,
+    heads: &SyncHeads,
+) -> Result<crate::storage::PullBatch> {
+    let url = format!("{}/api/v1/pull", peer_base_url.trim_end_matches('/'));
+
+    let body = serde_json::to_vec(&PullRequest {
+        cursor,
+        limit,
+        heads: heads.clone(),
+    })
+    .context("serialize pull request json")?;
+    let resp = client
+        .request(|agent, auth| {
+            let mut req = agent.post(&url).set("Content-Type", "application/json");
+            if let Some(auth) = auth {
+                req = req.set("Authorization", auth);
+            }
+            req.send_bytes(&body)
+        })
+        .with_context(|| format!("POST {url}"))?;
+    let body = resp.into_string().context("read response body")?;
+    let parsed: EntriesResponse =
+        serde_json::from_str(&body).context("parse entries response json")?;
+
+    Ok(crate::storage::PullBatch {
+        entries: parsed.entries,
+        tombstones: parsed.tombstones,
+        next_cursor: parsed.next_cursor,
+        by_heads: parsed.by_heads,
+    })
+}
+
+fn is_not_found_error(err: &anyhow::Error) -> bool {
+    err.chain().any(|cause| {
+        matches!(
+            cause.downcast_ref::<ureq::Error>(),
+            Some(ureq::Error::Status(404, _))
+        )
+    })
+}
+
+fn http_push_batch(
+    client: &HttpClient,
+    peer_base_url: &str,
+    entries: Vec<Entry>,
+    tombstones: Vec<Tombstone>,
+) -> Result<()> {
     let url = format!("{}/api/v1/entries", peer_base_url.trim_end_matches('/'));
 
-    let body = serde_json::to_vec(&entries).context("serialize entries json")?;
-    let resp = crate::http_retry::request_with_retry(
-        crate::http_retry::RetryPolicy::transport(),
-        |agent| {
-            agent
-                .post(&url)
-                .set("Content-Type", "application/json")
-                .send_bytes(&body)
-        },
-    )
-    .with_context(|| format!("POST {url}"))?;
+    let body = serde_json::to_vec(&EntriesRequest::Object {
+        entries,
+        tombstones,
+    })
+    .context("serialize entries json")?;
+    let resp = client
+        .request(|agent, auth| {
+            let mut req = agent.post(&url).set("Content-Type", "application/json");
+            if let Some(auth) = auth {
+                req = req.set("Authorization", auth);
+            }
+            req.send_bytes(&body)
+        })
+        .with_context(|| format!("POST {url}"))?;
     let _ = resp.into_string().context("read response body")?;
     Ok(())
 }
 
 fn route_http_request(
     store: &LocalStore,
+    token: Option<&str>,
     req: &mut tiny_http::Request,
 ) -> Result<tiny_http::Response<std::io::Cursor<Vec<u8>>>> {
+    if let Some(expected) = token
+        && crate::tracker::bearer_token(req).as_deref() != Some(expected)
+    {
+        return Ok(respond_text(401, "unauthorized\n"));
+    }
+
     let url = req.url().to_string();
     let method = req.method().as_str();
 
@@ -205,7 +687,33 @@ fn route_http_request(
                 200,
                 &EntriesResponse {
                     entries: batch.entries,
+                    tombstones: batch.tombstones,
+                    next_cursor: batch.next_cursor,
+                    by_heads: false,
+                },
+            )
+        }
+        ("POST", "/api/v1/pull") => {
+            let mut buf = Vec::new();
+            let max = max_request_body_bytes();
+            req.as_reader()
+                .take((max as u64).saturating_add(1))
+                .read_to_end(&mut buf)
+                .context("read request body")?;
+            if buf.len() > max {
+                return Ok(respond_text(413, "payload too large\n"));
+            }
+
+            let pull: PullRequest =
+                serde_json::from_slice(&buf).context("parse pull request json")?;
+            let batch = store.pull_by_heads(&pull.heads, pull.cursor, pull.limit, None)?;
+            respond_json(
+                200,
+                &EntriesResponse {
+                    entries: batch.entries,
+                    tombstones: batch.tombstones,
                     next_cursor: batch.next_cursor,
+                    by_heads: batch.by_heads,
                 },
             )
         }
@@ -222,10 +730,14 @@ fn route_http_request(
 
             let req_body: EntriesRequest =
                 serde_json::from_slice(&buf).context("parse entries request json")?;
-            let entries = match req_body {
-                EntriesRequest::Array(entries) => entries,
-                EntriesRequest::Object { entries } => entries,
+            let (entries, tombstones) = match req_body {
+                EntriesRequest::Array(entries) => (entries, Vec::new()),
+                EntriesRequest::Object {
+                    entries,
+                    tombstones,
+                } => (entries, tombstones),
             };
+            let deleted = store.apply_tombstones(&tombstones)?;
             let stats = store.insert_entries_with_stats(&entries)?;
             respond_json(
                 200,
@@ -233,6 +745,7 @@ fn route_http_request(
                     ok: true,
                     inserted: stats.inserted,
                     ignored: stats.ignored,
+                    deleted,
                 },
             )
         }
@@ -316,22 +829,28 @@ mod tests {
     }
 
     fn start_test_server(db_path: String) -> TestServer {
+        start_test_server_with(db_path, ServeOptions::default())
+    }
+
+    fn start_test_server_with(db_path: String, opts: ServeOptions) -> TestServer {
         let listener = TcpListener::bind("127.0.0.1:0").unwrap();
         let addr = listener.local_addr().unwrap();
         drop(listener);
 
         let bind = format!("127.0.0.1:{}", addr.port());
-        let base_url = format!("http://{}", bind);
+        let scheme = if opts.tls.is_some() { "https" } else { "http" };
+        let base_url = format!("{scheme}://{bind}");
         let shutdown = Arc::new(AtomicBool::new(false));
         let shutdown2 = shutdown.clone();
 
+        let bind2 = bind.clone();
         let join = thread::spawn(move || {
             let store = LocalStore::open(&db_path).unwrap();
-            let server = tiny_http::Server::http(&bind).unwrap();
+            let server = bind_server(&bind2, &opts).unwrap();
             while !shutdown2.load(Ordering::SeqCst) {
                 match server.recv_timeout(Duration::from_millis(50)) {
                     Ok(Some(mut req)) => {
-                        let res = route_http_request(&store, &mut req)
+                        let res = route_http_request(&store, opts.token.as_deref(), &mut req)
                             .unwrap_or_else(|e| respond_text(500, &format!("error: {e:#}\n")));
                         let _ = req.respond(res);
                     }
@@ -341,10 +860,9 @@ mod tests {
             }
         });
 
-        // 서버가 뜰 때까지 짧게 대기(ping).
+        // 서버가 뜰 때까지 짧게 대기(인증/TLS와 무관하게 listen 여부만 본다).
         for _ in 0..50 {
-            let url = format!("{}/api/v1/ping", base_url);
-            if ureq::get(&url).call().is_ok() {
+            if std::net::TcpStream::connect(&bind).is_ok() {
                 break;
             }
             thread::sleep(Duration::from_millis(20));
@@ -370,6 +888,9 @@ mod tests {
             shell: "zsh".to_string(),
             hostname: "host".to_string(),
             version: "0.1.0".to_string(),
+            session_id: None,
+            origin_seq: None,
+            origin_id: None,
         }
     }
 
@@ -389,7 +910,8 @@ mod tests {
         let server = start_test_server(remote_db.to_str().unwrap().to_string());
 
         let local = LocalStore::open(local_db.to_str().unwrap()).unwrap();
-        let pulled = sync_pull_http_peer(&local, &server.base_url, 1).unwrap();
+        let pulled =
+            sync_pull_http_peer(&HttpClient::default(), &local, &server.base_url, 1).unwrap();
 
         assert_eq!(pulled.received, 2);
         assert_eq!(pulled.inserted, 2);
@@ -401,7 +923,14 @@ mod tests {
         local_entry.device_id = "dev-local".to_string();
         local.insert_entries(&[local_entry]).unwrap();
 
-        let pushed = sync_push_http_peer(&local, &server.base_url, 100, Some("dev-local")).unwrap();
+        let pushed = sync_push_http_peer(
+            &HttpClient::default(),
+            &local,
+            &server.base_url,
+            100,
+            Some("dev-local"),
+        )
+        .unwrap();
         assert_eq!(pushed, 1);
 
         let got = remote.list_recent(10).unwrap();
@@ -411,6 +940,65 @@ mod tests {
         server.shutdown();
     }
 
+    #[test]
+    fn http_sync_replicates_tombstones_both_ways() {
+        let dir = tempdir().unwrap();
+        let remote_db = dir.path().join("remote.db");
+        let local_db = dir.path().join("local.db");
+
+        let remote = LocalStore::open(remote_db.to_str().unwrap()).unwrap();
+        let local = LocalStore::open(local_db.to_str().unwrap()).unwrap();
+        let mut r1 = entry("id-1", 1, "echo remote");
+        r1.device_id = "dev-remote".to_string();
+        let mut l1 = entry("id-2", 2, "echo local");
+        l1.device_id = "dev-local".to_string();
+        for store in [&remote, &local] {
+            store.insert_entries(&[r1.clone(), l1.clone()]).unwrap();
+        }
+
+        let server = start_test_server(remote_db.to_str().unwrap().to_string());
+        sync_pull_http_peer(&HttpClient::default(), &local, &server.base_url, 100).unwrap();
+        sync_push_http_peer(
+            &HttpClient::default(),
+            &local,
+            &server.base_url,
+            100,
+            Some("dev-local"),
+        )
+        .unwrap();
+
+        remote.delete_entries(&["id-1".to_string()], false).unwrap();
+        local.delete_entries(&["id-2".to_string()], false).unwrap();
+
+        let pulled =
+            sync_pull_http_peer(&HttpClient::default(), &local, &server.base_url, 100).unwrap();
+        assert_eq!(pulled.deleted, 1);
+        let pushed = sync_push_http_peer(
+            &HttpClient::default(),
+            &local,
+            &server.base_url,
+            100,
+            Some("dev-local"),
+        )
+        .unwrap();
+        assert_eq!(pushed, 2);
+
+        assert!(local.list_recent(10).unwrap().is_empty());
+        assert!(remote.list_recent(10).unwrap().is_empty());
+
+        // 지운 엔트리를 다시 push해도 되살아나지 않는다.
+        http_push_batch(
+            &HttpClient::default(),
+            &server.base_url,
+            vec![r1, l1],
+            Vec::new(),
+        )
+        .unwrap();
+        assert!(remote.list_recent(10).unwrap().is_empty());
+
+        server.shutdown();
+    }
+
     #[test]
     fn http_sync_normalizes_peer_url_key() {
         let dir = tempdir().unwrap();
@@ -426,7 +1014,8 @@ mod tests {
 
         let local = LocalStore::open(local_db.to_str().unwrap()).unwrap();
         let peer_with_slash = format!("{}/", server.base_url);
-        let pulled = sync_pull_http_peer(&local, &peer_with_slash, 100).unwrap();
+        let pulled =
+            sync_pull_http_peer(&HttpClient::default(), &local, &peer_with_slash, 100).unwrap();
         assert_eq!(pulled.received, 1);
         assert_eq!(pulled.inserted, 1);
         assert_eq!(pulled.ignored, 0);
@@ -495,7 +1084,9 @@ mod tests {
                                 200,
                                 &EntriesResponse {
                                     entries: vec![],
+                                    tombstones: vec![],
                                     next_cursor: None,
+                                    by_heads: false,
                                 },
                             )
                             .unwrap(),
@@ -519,10 +1110,144 @@ mod tests {
         }
 
         let peers = vec![base_url];
-        let err = sync(&peers, local_db.to_str().unwrap(), true, Some("dev-local")).unwrap_err();
+        let err = sync(
+            &peers,
+            local_db.to_str().unwrap(),
+            true,
+            Some("dev-local"),
+            None,
+            &ClientOptions::default(),
+        )
+        .unwrap_err();
         assert!(format!("{err:#}").contains("push peer"));
 
         shutdown.store(true, Ordering::SeqCst);
         let _ = join.join();
     }
+
+    #[test]
+    fn http_serve_requires_token_and_verifies_pinned_tls() {
+        let dir = tempdir().unwrap();
+        let remote_db = dir.path().join("remote.db");
+        let local_db = dir.path().join("local.db");
+
+        let remote = LocalStore::open(remote_db.to_str().unwrap()).unwrap();
+        let mut r1 = entry("id-1", 1, "echo remote");
+        r1.device_id = "dev-remote".to_string();
+        remote.insert_entries(&[r1]).unwrap();
+
+        let cert = rcgen::generate_simple_self_signed(vec!["127.0.0.1".to_string()]).unwrap();
+        let cert_path = dir.path().join("cert.pem");
+        let key_path = dir.path().join("key.pem");
+        let ca_path = dir.path().join("ca.pem");
+        std::fs::write(&cert_path, cert.cert.pem()).unwrap();
+        std::fs::write(&key_path, cert.key_pair.serialize_pem()).unwrap();
+        std::fs::write(&ca_path, cert.cert.pem()).unwrap();
+        let fingerprint = cert_fingerprint(cert.cert.der())
+            .iter()
+            .map(|b| format!("{b:02X}"))
+            .collect::<Vec<_>>()
+            .join(":");
+
+        let tls = ServeTls {
+            cert_path,
+            key_path,
+        };
+        // 내부(loopback) 서버도 토큰으로 막아야 하므로 토큰 없는 TLS는 시작하지 않는다.
+        let Err(err) = bind_server(
+            "127.0.0.1:0",
+            &ServeOptions {
+                token: None,
+                tls: Some(tls.clone()),
+            },
+        ) else {
+            panic!("tls without a token must not start");
+        };
+        assert!(format!("{err:#}").contains("requires a token"), "{err:#}");
+
+        let server = start_test_server_with(
+            remote_db.to_str().unwrap().to_string(),
+            ServeOptions {
+                token: Some("s3cret".to_string()),
+                tls: Some(tls),
+            },
+        );
+        let local = LocalStore::open(local_db.to_str().unwrap()).unwrap();
+        let client = |token: Option<&str>, ca: Option<&std::path::Path>, fp: Option<&str>| {
+            HttpClient::from_options(&ClientOptions {
+                token: token.map(str::to_string),
+                ca_path: ca.map(|p| p.to_path_buf()),
+                cert_fingerprint: fp.map(str::to_string),
+            })
+            .unwrap()
+        };
+
+        // 기본 루트로는 self-signed 인증서를 믿지 않는다.
+        let err = sync_pull_http_peer(
+            &client(Some("s3cret"), None, None),
+            &local,
+            &server.base_url,
+            100,
+        )
+        .unwrap_err();
+        assert!(!format!("{err:#}").contains("status code"), "{err:#}");
+
+        let other_fp = "00".repeat(32);
+        let err = sync_pull_http_peer(
+            &client(Some("s3cret"), None, Some(&other_fp)),
+            &local,
+            &server.base_url,
+            100,
+        )
+        .unwrap_err();
+        assert!(format!("{err:#}").contains("pinned fingerprint"), "{err:#}");
+
+        let err = sync_pull_http_peer(
+            &client(Some("wrong"), None, Some(&fingerprint)),
+            &local,
+            &server.base_url,
+            100,
+        )
+        .unwrap_err();
+        assert!(format!("{err:#}").contains("status code 401"), "{err:#}");
+
+        let pinned = client(Some("s3cret"), None, Some(&fingerprint));
+        let pulled = sync_pull_http_peer(&pinned, &local, &server.base_url, 100).unwrap();
+        assert_eq!(pulled.inserted, 1);
+
+        let mut l1 = entry("id-2", 2, "echo local");
+        l1.device_id = "dev-local".to_string();
+        local.insert_entries(&[l1]).unwrap();
+        let with_ca = client(Some("s3cret"), Some(&ca_path), None);
+        let pushed =
+            sync_push_http_peer(&with_ca, &local, &server.base_url, 100, Some("dev-local"))
+                .unwrap();
+        assert_eq!(pushed, 1);
+        assert_eq!(remote.list_recent(10).unwrap().len(), 2);
+
+        assert!(
+            HttpClient::from_options(&ClientOptions {
+                token: None,
+                ca_path: Some(ca_path),
+                cert_fingerprint: Some(fingerprint),
+            })
+            .is_err()
+        );
+
+        server.shutdown();
+    }
+
+    #[test]
+    fn parse_cert_fingerprint_accepts_openssl_output() {
+        let want = [0xabu8; 32];
+        let colon = vec!["AB"; 32].join(":");
+        assert_eq!(parse_cert_fingerprint(&colon).unwrap(), want);
+        assert_eq!(
+            parse_cert_fingerprint(&format!("sha256 Fingerprint={colon}")).unwrap(),
+            want
+        );
+        assert_eq!(parse_cert_fingerprint(&"ab".repeat(32)).unwrap(), want);
+        assert!(parse_cert_fingerprint("abcd").is_err());
+        assert!(parse_cert_fingerprint(&"zz".repeat(32)).is_err());
+    }
 }